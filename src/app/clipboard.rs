@@ -0,0 +1,207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Copying results to the clipboard with the OSC 52 terminal escape sequence
+
+use std::io::Write;
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use datafusion::arrow::{
+    array::RecordBatch,
+    json::{writer::JsonArray, WriterBuilder},
+    util::display::ArrayFormatter,
+};
+
+use crate::app::config::{ClipboardConfig, CopyFormat, DisplayConfig};
+use crate::app::state::results::ResultsState;
+use crate::ui::convert::{format_options, localize_timestamps};
+
+/// The part of the results that is copied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyTarget {
+    Cell,
+    Row,
+    Results,
+}
+
+/// The text to copy for `target` of the results shown with `state`. A cell is copied as its
+/// plain value while rows and results are copied with a header in `format`.
+pub fn copy_text(
+    batches: &[RecordBatch],
+    state: &ResultsState,
+    target: CopyTarget,
+    format: CopyFormat,
+    config: &DisplayConfig,
+) -> Result<String> {
+    if target == CopyTarget::Results {
+        return format_batches(batches, format, config);
+    }
+    let row = state.selected().ok_or_else(|| eyre!("No row selected"))?;
+    let (batch, idx) = state
+        .locate(row)
+        .ok_or_else(|| eyre!("Row {} is not in the results", row))?;
+    let slice = batches[batch].slice(idx, 1);
+    match target {
+        CopyTarget::Cell => {
+            let column = localize_timestamps(slice.column(state.selected_column()), config);
+            let options = format_options(config).with_null("");
+            let formatter = ArrayFormatter::try_new(column.as_ref(), &options)?;
+            Ok(formatter.value(0).to_string())
+        }
+        _ => format_batches(&[slice], format, config),
+    }
+}
+
+fn escape(value: String, format: CopyFormat) -> String {
+    match format {
+        CopyFormat::Tsv => value.replace('\t', "\\t").replace('\n', "\\n"),
+        CopyFormat::Csv if value.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        }
+        CopyFormat::Markdown => value.replace('|', "\\|").replace('\n', "<br>"),
+        _ => value,
+    }
+}
+
+/// Format `batches` with a header row. JSON is written as an array of objects.
+pub fn format_batches(
+    batches: &[RecordBatch],
+    format: CopyFormat,
+    config: &DisplayConfig,
+) -> Result<String> {
+    if format == CopyFormat::Json {
+        let mut writer = WriterBuilder::new()
+            .with_explicit_nulls(true)
+            .build::<_, JsonArray>(Vec::new());
+        writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+        writer.finish()?;
+        return Ok(String::from_utf8(writer.into_inner())?);
+    }
+
+    let Some(first) = batches.first() else {
+        return Ok(String::new());
+    };
+    let (separator, prefix, suffix) = match format {
+        CopyFormat::Csv => (",", "", ""),
+        CopyFormat::Markdown => (" | ", "| ", " |"),
+        _ => ("\t", "", ""),
+    };
+    let options = match format {
+        CopyFormat::Markdown => format_options(config),
+        _ => format_options(config).with_null(""),
+    };
+
+    let mut lines = Vec::new();
+    let header: Vec<String> = first
+        .schema_ref()
+        .fields()
+        .iter()
+        .map(|f| escape(f.name().clone(), format))
+        .collect();
+    lines.push(format!("{}{}{}", prefix, header.join(separator), suffix));
+    if format == CopyFormat::Markdown {
+        let rule = vec!["---"; header.len()];
+        lines.push(format!("{}{}{}", prefix, rule.join(separator), suffix));
+    }
+    for batch in batches {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| localize_timestamps(c, config))
+            .collect::<Vec<_>>();
+        let formatters = columns
+            .iter()
+            .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for idx in 0..batch.num_rows() {
+            let values: Vec<String> = formatters
+                .iter()
+                .map(|f| escape(f.value(idx).to_string(), format))
+                .collect();
+            lines.push(format!("{}{}{}", prefix, values.join(separator), suffix));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// The OSC 52 sequence that sets the system clipboard to `text`
+fn osc52_sequence(text: &str) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    // tmux only forwards escape sequences to the outer terminal when they are wrapped
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+pub fn copy_to_clipboard(text: &str, config: &ClipboardConfig, data_dir: &Path) -> Result<()> {
+    if !config.osc52 && !config.file {
+        return Err(eyre!(
+            "The clipboard is disabled, enable `osc52` or `file` in [interaction.clipboard]"
+        ));
+    }
+    if config.osc52 {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(osc52_sequence(text).as_bytes())?;
+        stdout.flush()?;
+    }
+    if config.file {
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::write(data_dir.join("clipboard.txt"), text)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+
+    use super::format_batches;
+    use crate::app::config::{CopyFormat, DisplayConfig};
+
+    #[test]
+    fn format_batches_test() {
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None]));
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["a, \"b\"", "c|d"]));
+        let batch = RecordBatch::try_from_iter(vec![("id", ids), ("name", names)]).unwrap();
+        let config = DisplayConfig::default();
+        let batches = [batch];
+
+        assert_eq!(
+            format_batches(&batches, CopyFormat::Tsv, &config).unwrap(),
+            "id\tname\n1\ta, \"b\"\n\tc|d"
+        );
+        assert_eq!(
+            format_batches(&batches, CopyFormat::Csv, &config).unwrap(),
+            "id,name\n1,\"a, \"\"b\"\"\"\n,c|d"
+        );
+        assert_eq!(
+            format_batches(&batches, CopyFormat::Markdown, &config).unwrap(),
+            "| id | name |\n| --- | --- |\n| 1 | a, \"b\" |\n| NULL | c\\|d |"
+        );
+        assert_eq!(
+            format_batches(&batches, CopyFormat::Json, &config).unwrap(),
+            r#"[{"id":1,"name":"a, \"b\""},{"id":null,"name":"c|d"}]"#
+        );
+    }
+}
@@ -15,16 +15,18 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
 use directories::{ProjectDirs, UserDirs};
 use lazy_static::lazy_static;
-use serde::Deserialize;
-
-#[cfg(feature = "s3")]
-use color_eyre::Result;
 #[cfg(feature = "s3")]
 use object_store::aws::{AmazonS3, AmazonS3Builder};
+use serde::Deserialize;
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+use toml_edit::{DocumentMut, Item, Table};
 
 lazy_static! {
     pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
@@ -33,7 +35,7 @@ lazy_static! {
             .ok()
             .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
-    pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
+    pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_CRATE_NAME"));
 }
 
 fn project_directory() -> PathBuf {
@@ -65,6 +67,10 @@ pub struct AppConfig {
     pub display: DisplayConfig,
     #[serde(default = "default_interaction_config")]
     pub interaction: InteractionConfig,
+    #[serde(default)]
+    pub keymap: KeymapConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
     #[cfg(feature = "flightsql")]
     #[serde(default = "default_flightsql_config")]
     pub flightsql: FlightSQLConfig,
@@ -87,12 +93,82 @@ fn default_flightsql_config() -> FlightSQLConfig {
     FlightSQLConfig::default()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct DisplayConfig {
     #[serde(default = "default_tick_rate")]
     pub tick_rate: f64,
     #[serde(default = "default_frame_rate")]
     pub frame_rate: f64,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Text shown in place of NULL values in results
+    #[serde(default = "default_null_marker")]
+    pub null_marker: String,
+    /// Timezone that timezone aware timestamps are displayed in, e.g. `America/New_York` or
+    /// `+02:00`. Timestamps are shown in their own timezone when unset.
+    pub timezone: Option<String>,
+    /// `chrono` format strings for temporal values. RFC3339 is used when unset.
+    pub date_format: Option<String>,
+    pub time_format: Option<String>,
+    pub timestamp_format: Option<String>,
+    pub timestamp_tz_format: Option<String>,
+    /// Result columns are sized to fit their header and a sample of their values, within these
+    /// bounds
+    #[serde(default = "default_min_column_width")]
+    pub min_column_width: u16,
+    #[serde(default = "default_max_column_width")]
+    pub max_column_width: u16,
+}
+
+fn default_null_marker() -> String {
+    "NULL".to_string()
+}
+
+fn default_min_column_width() -> u16 {
+    4
+}
+
+fn default_max_column_width() -> u16 {
+    40
+}
+
+/// Either the name of a preset (`theme = "light"`) or a `[display.theme]` table that can start
+/// from a preset and override individual colours.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Preset(String),
+    Custom(Box<CustomThemeConfig>),
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig::Custom(Box::default())
+    }
+}
+
+/// Colours can be given as names (`"red"`, `"lightblue"`), indexed (`"42"`) or hex (`"#ff8800"`)
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CustomThemeConfig {
+    pub preset: Option<String>,
+    pub tab_fg: Option<String>,
+    pub tab_bg: Option<String>,
+    pub tab_highlight_fg: Option<String>,
+    pub tab_highlight_bg: Option<String>,
+    pub text_fg: Option<String>,
+    pub border_fg: Option<String>,
+    pub border_active_fg: Option<String>,
+    pub header_fg: Option<String>,
+    pub header_bg: Option<String>,
+    pub cell_fg: Option<String>,
+    pub cell_bg: Option<String>,
+    pub selected_fg: Option<String>,
+    pub selected_bg: Option<String>,
+    pub null_fg: Option<String>,
+    pub search_match_fg: Option<String>,
+    pub search_match_bg: Option<String>,
+    /// Colours of the series of a chart, in order
+    pub series: Option<Vec<String>>,
 }
 
 fn default_tick_rate() -> f64 {
@@ -108,6 +184,15 @@ impl Default for DisplayConfig {
         Self {
             tick_rate: 5.0,
             frame_rate: 5.0,
+            theme: ThemeConfig::default(),
+            null_marker: default_null_marker(),
+            timezone: None,
+            date_format: None,
+            time_format: None,
+            timestamp_format: None,
+            timestamp_tz_format: None,
+            min_column_width: default_min_column_width(),
+            max_column_width: default_max_column_width(),
         }
     }
 }
@@ -166,6 +251,147 @@ pub struct ObjectStoreConfig {
 #[derive(Clone, Debug, Default, Deserialize)]
 pub struct ExecutionConfig {
     pub object_store: Option<ObjectStoreConfig>,
+    /// DataFusion options set on the session, keyed without their `datafusion.` prefix, e.g.
+    /// `execution.batch_size = 1024`
+    #[serde(default)]
+    pub datafusion: toml::Table,
+    #[serde(default)]
+    pub memory: MemoryConfig,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPoolKind {
+    /// Reservations are granted first come, first served up to the limit
+    #[default]
+    Greedy,
+    /// Operators that can spill share the memory left by those that can't equally
+    Fair,
+}
+
+/// The memory pool and spill directory of the `RuntimeEnv`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MemoryConfig {
+    /// Memory that queries can reserve, e.g. `4GB` or `512MB`. Unlimited when unset.
+    pub limit: Option<String>,
+    #[serde(default)]
+    pub pool: MemoryPoolKind,
+    /// Directory that operators spill to, the OS temporary directory when unset
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl MemoryConfig {
+    /// The memory limit in bytes
+    pub fn limit_bytes(&self) -> Result<Option<usize>> {
+        self.limit.as_deref().map(parse_size).transpose()
+    }
+}
+
+/// Parse a size such as `512MB`, `4 GiB` or `1024`, with 1024 bytes to the kilobyte
+fn parse_size(size: &str) -> Result<usize> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| eyre!("Invalid size {:?}", size))?;
+    let exponent = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        _ => return Err(eyre!("Invalid size unit {:?} in {:?}", unit.trim(), size)),
+    };
+    Ok((number * 1024_f64.powi(exponent)) as usize)
+}
+
+/// Push the options of `table` and its nested tables, keyed by their dotted path under `prefix`
+fn flatten_options(prefix: &str, table: &toml::Table, options: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let key = format!("{}.{}", prefix, key);
+        match value {
+            toml::Value::Table(table) => flatten_options(&key, table, options),
+            toml::Value::String(value) => options.push((key, value.clone())),
+            value => options.push((key, value.to_string())),
+        }
+    }
+}
+
+impl ExecutionConfig {
+    /// The DataFusion options of the config, keyed as they are in `SET` statements
+    pub fn datafusion_options(&self) -> Vec<(String, String)> {
+        let mut options = Vec::new();
+        flatten_options("datafusion", &self.datafusion, &mut options);
+        options
+    }
+}
+
+/// The value of a DataFusion option as it is written to the config file
+fn option_value(value: &str) -> toml_edit::Value {
+    if let Ok(value) = value.parse::<bool>() {
+        value.into()
+    } else if let Ok(value) = value.parse::<i64>() {
+        value.into()
+    } else {
+        value.into()
+    }
+}
+
+/// Write DataFusion `options`, keyed as in `SET` statements, to the `[execution.datafusion]`
+/// table of the config file at `path`. The rest of the file, including comments, is kept as it
+/// is and the file is created if it doesn't exist.
+pub fn save_datafusion_options(path: &Path, options: &BTreeMap<String, String>) -> Result<()> {
+    let contents = match path.exists() {
+        true => std::fs::read_to_string(path)?,
+        false => String::new(),
+    };
+    let mut doc: DocumentMut = contents.parse()?;
+    let execution = doc
+        .entry("execution")
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .ok_or_else(|| eyre!("`execution` in {} is not a table", path.display()))?;
+    let datafusion = execution
+        .entry("datafusion")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| {
+            eyre!(
+                "`execution.datafusion` in {} is not a table",
+                path.display()
+            )
+        })?;
+    for (key, value) in options {
+        let key = key.strip_prefix("datafusion.").unwrap_or(key);
+        let mut parts: Vec<&str> = key.split('.').collect();
+        let name = parts.pop().unwrap_or_default();
+        // Namespaces are written as dotted keys, e.g. `execution.batch_size = 1024`
+        let mut table = &mut *datafusion;
+        for part in parts {
+            table = table
+                .entry(part)
+                .or_insert_with(|| {
+                    let mut table = Table::new();
+                    table.set_dotted(true);
+                    Item::Table(table)
+                })
+                .as_table_mut()
+                .ok_or_else(|| eyre!("`{}` in {} is not a table", key, path.display()))?;
+        }
+        table.insert(name, toml_edit::value(option_value(value)));
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, doc.to_string())?;
+    Ok(())
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -174,6 +400,181 @@ pub struct InteractionConfig {
     pub mouse: bool,
     #[serde(default = "default_paste")]
     pub paste: bool,
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+    #[serde(default)]
+    pub profile: ProfileConfig,
+    #[serde(default)]
+    pub catalog: CatalogConfig,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// A JSON object per line, with the timestamp, level, target, source location and message
+    Json,
+}
+
+/// Logs written to `dft.log` in the data directory. When the file reaches `max_size` it is
+/// renamed to `dft.log.1`, and older files are shifted up to `dft.log.<max_files>`.
+#[derive(Debug, Deserialize)]
+pub struct LoggingConfig {
+    /// Level filter used when neither `DFT_LOGLEVEL` nor `RUST_LOG` is set, e.g.
+    /// `info,datafusion=warn`
+    pub level: Option<String>,
+    #[serde(default = "default_log_file")]
+    pub file: bool,
+    #[serde(default = "default_log_max_size")]
+    pub max_size: String,
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: None,
+            file: default_log_file(),
+            max_size: default_log_max_size(),
+            max_files: default_log_max_files(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// The size in bytes that the log file is rotated at
+    pub fn max_size_bytes(&self) -> Result<usize> {
+        parse_size(&self.max_size)
+    }
+}
+
+fn default_log_file() -> bool {
+    true
+}
+
+fn default_log_max_size() -> String {
+    "10MB".to_string()
+}
+
+fn default_log_max_files() -> usize {
+    5
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, EnumIter, IntoStaticStr)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CopyFormat {
+    #[default]
+    Tsv,
+    Csv,
+    Markdown,
+    Json,
+}
+
+impl CopyFormat {
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// The format after this one, wrapping around
+    pub fn next(self) -> Self {
+        let formats: Vec<CopyFormat> = CopyFormat::iter().collect();
+        let idx = formats.iter().position(|f| *f == self).unwrap_or_default();
+        formats[(idx + 1) % formats.len()]
+    }
+}
+
+/// How copied results reach the clipboard. OSC 52 asks the terminal to set the clipboard, which
+/// also works over SSH. Terminals that don't support it can use the file, which is written to
+/// `clipboard.txt` in the data directory.
+#[derive(Debug, Deserialize)]
+pub struct ClipboardConfig {
+    #[serde(default)]
+    pub format: CopyFormat,
+    #[serde(default = "default_osc52")]
+    pub osc52: bool,
+    #[serde(default)]
+    pub file: bool,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            format: CopyFormat::default(),
+            osc52: default_osc52(),
+            file: false,
+        }
+    }
+}
+
+fn default_osc52() -> bool {
+    true
+}
+
+/// Statistics computed when profiling the columns of results or a table
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProfileConfig {
+    /// Number of most frequent values listed for each column
+    #[serde(default = "default_top_values")]
+    pub top_values: usize,
+    /// Number of buckets in the histogram of numeric columns
+    #[serde(default = "default_histogram_bins")]
+    pub histogram_bins: usize,
+    /// Estimate distinct counts with HyperLogLog, which is much cheaper for large tables
+    #[serde(default)]
+    pub approximate_distinct: bool,
+    /// Number of rows of a table read for the most frequent values and histograms, or 0 to read
+    /// the whole table
+    #[serde(default = "default_sample_rows")]
+    pub sample_rows: usize,
+}
+
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        Self {
+            top_values: default_top_values(),
+            histogram_bins: default_histogram_bins(),
+            approximate_distinct: false,
+            sample_rows: default_sample_rows(),
+        }
+    }
+}
+
+fn default_top_values() -> usize {
+    3
+}
+
+fn default_histogram_bins() -> usize {
+    10
+}
+
+fn default_sample_rows() -> usize {
+    100_000
+}
+
+/// The catalog browser of the Context tab
+#[derive(Clone, Debug, Deserialize)]
+pub struct CatalogConfig {
+    /// Number of rows shown when previewing a table
+    #[serde(default = "default_preview_rows")]
+    pub preview_rows: usize,
+}
+
+impl Default for CatalogConfig {
+    fn default() -> Self {
+        Self {
+            preview_rows: default_preview_rows(),
+        }
+    }
+}
+
+fn default_preview_rows() -> usize {
+    20
 }
 
 fn default_mouse() -> bool {
@@ -184,23 +585,258 @@ fn default_paste() -> bool {
     false
 }
 
+/// User overrides for key bindings. Each entry maps an action name (for example `run_query`)
+/// to one or more key chords (for example `"ctrl+enter"`). Bindings under `global` apply to
+/// the normal mode of every tab, `prompt` bindings to input prompts on every tab, `detail`
+/// bindings to the record detail view, `profile` bindings to the column profile view,
+/// `connections` bindings to the FlightSQL connection picker, `metadata` bindings to the
+/// FlightSQL metadata browser, `settings` bindings to the session config editor and `functions`
+/// bindings to the function browser.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct KeymapConfig {
+    #[serde(default)]
+    pub global: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub sql: TabKeymapConfig,
+    #[serde(default)]
+    pub flightsql: TabKeymapConfig,
+    #[serde(default)]
+    pub history: TabKeymapConfig,
+    #[serde(default)]
+    pub logs: TabKeymapConfig,
+    #[serde(default)]
+    pub context: TabKeymapConfig,
+    #[serde(default)]
+    pub prompt: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub detail: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub profile: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub connections: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub metadata: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub settings: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub functions: HashMap<String, KeyBindingConfig>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TabKeymapConfig {
+    #[serde(default)]
+    pub normal: HashMap<String, KeyBindingConfig>,
+    #[serde(default)]
+    pub edit: HashMap<String, KeyBindingConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBindingConfig {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl KeyBindingConfig {
+    pub fn chords(&self) -> Vec<&str> {
+        match self {
+            KeyBindingConfig::Single(chord) => vec![chord.as_str()],
+            KeyBindingConfig::Multiple(chords) => chords.iter().map(|c| c.as_str()).collect(),
+        }
+    }
+}
+
+/// FlightSQL servers that can be connected to. The settings at the top level of `[flightsql]`
+/// are the connection used when no named `[[flightsql.connections]]` are given, otherwise the
+/// first named connection is connected to at startup and others can be switched to.
 #[cfg(feature = "flightsql")]
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct FlightSQLConfig {
+    #[serde(flatten)]
+    pub default_connection: FlightSQLConnectionConfig,
+    #[serde(default)]
+    pub connections: Vec<FlightSQLConnectionConfig>,
+}
+
+#[cfg(feature = "flightsql")]
+impl FlightSQLConfig {
+    pub fn connections(&self) -> Vec<&FlightSQLConnectionConfig> {
+        if self.connections.is_empty() {
+            vec![&self.default_connection]
+        } else {
+            self.connections.iter().collect()
+        }
+    }
+}
+
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct FlightSQLConnectionConfig {
+    #[serde(default = "default_connection_name")]
+    pub name: String,
     #[serde(default = "default_connection_url")]
     pub connection_url: String,
+    #[serde(default)]
+    pub auth: FlightSQLAuthConfig,
+    #[serde(default)]
+    pub tls: FlightSQLTlsConfig,
+    /// gRPC headers sent with every request
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub connect_timeout_ms: Option<u64>,
+    pub request_timeout_ms: Option<u64>,
 }
 
 #[cfg(feature = "flightsql")]
-impl Default for FlightSQLConfig {
+impl Default for FlightSQLConnectionConfig {
     fn default() -> Self {
         Self {
+            name: default_connection_name(),
             connection_url: default_connection_url(),
+            auth: FlightSQLAuthConfig::default(),
+            tls: FlightSQLTlsConfig::default(),
+            headers: HashMap::new(),
+            connect_timeout_ms: None,
+            request_timeout_ms: None,
+        }
+    }
+}
+
+/// Credentials for the FlightSQL server. With a `username` a basic auth handshake is made and
+/// the token it returns is used, otherwise a bearer token can be read from an environment
+/// variable or a file so that it doesn't have to be kept in the config.
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FlightSQLAuthConfig {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub password_env: Option<String>,
+    pub bearer_token_env: Option<String>,
+    pub bearer_token_file: Option<PathBuf>,
+}
+
+#[cfg(feature = "flightsql")]
+impl FlightSQLAuthConfig {
+    pub fn password(&self) -> Result<String> {
+        match (&self.password, &self.password_env) {
+            (Some(password), _) => Ok(password.clone()),
+            (None, Some(var)) => std::env::var(var)
+                .map_err(|e| eyre!("Unable to read password from ${}: {}", var, e)),
+            (None, None) => Ok(String::new()),
+        }
+    }
+
+    pub fn bearer_token(&self) -> Result<Option<String>> {
+        if let Some(var) = &self.bearer_token_env {
+            let token = std::env::var(var)
+                .map_err(|e| eyre!("Unable to read bearer token from ${}: {}", var, e))?;
+            return Ok(Some(token));
+        }
+        if let Some(path) = &self.bearer_token_file {
+            let token = std::fs::read_to_string(path)
+                .map_err(|e| eyre!("Unable to read bearer token from {}: {}", path.display(), e))?;
+            return Ok(Some(token.trim().to_string()));
         }
+        Ok(None)
     }
 }
 
+/// TLS is used for `https` connection URLs or when any of these are set. The server is verified
+/// with the system's root certificates unless a `ca_cert` is given, and `domain` overrides the
+/// name the server's certificate is checked against.
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FlightSQLTlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM files
+    pub ca_cert: Option<PathBuf>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    pub domain: Option<String>,
+}
+
+#[cfg(feature = "flightsql")]
+fn default_connection_name() -> String {
+    "default".to_string()
+}
+
 #[cfg(feature = "flightsql")]
 pub fn default_connection_url() -> String {
     "http://localhost:50051".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{parse_size, save_datafusion_options, AppConfig};
+
+    #[test]
+    fn save_datafusion_options_test() {
+        let dir = std::env::temp_dir().join(format!("dft-config-test-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            &path,
+            "# Display settings\n[display]\nnull_marker = \"-\"\n\n[execution.datafusion]\noptimizer.max_passes = 5\n",
+        )
+        .unwrap();
+
+        let options = BTreeMap::from([
+            (
+                "datafusion.execution.batch_size".to_string(),
+                "1024".to_string(),
+            ),
+            (
+                "datafusion.execution.time_zone".to_string(),
+                "+01:00".to_string(),
+            ),
+            (
+                "datafusion.optimizer.prefer_hash_join".to_string(),
+                "false".to_string(),
+            ),
+        ]);
+        save_datafusion_options(&path, &options).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(contents.starts_with("# Display settings\n[display]\n"));
+        assert!(contents.contains("execution.batch_size = 1024\n"));
+        let config: AppConfig = toml::from_str(&contents).unwrap();
+        assert_eq!(config.display.null_marker, "-");
+        let mut saved = config.execution.datafusion_options();
+        saved.sort();
+        assert_eq!(
+            saved,
+            vec![
+                (
+                    "datafusion.execution.batch_size".to_string(),
+                    "1024".to_string()
+                ),
+                (
+                    "datafusion.execution.time_zone".to_string(),
+                    "+01:00".to_string()
+                ),
+                (
+                    "datafusion.optimizer.max_passes".to_string(),
+                    "5".to_string()
+                ),
+                (
+                    "datafusion.optimizer.prefer_hash_join".to_string(),
+                    "false".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_size_test() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("512MB").unwrap(), 512 * 1024 * 1024);
+        assert_eq!(parse_size("1.5 GiB").unwrap(), 3 * 512 * 1024 * 1024);
+        assert_eq!(parse_size("4g").unwrap(), 4 * 1024 * 1024 * 1024);
+        assert!(parse_size("4 bananas").is_err());
+        assert!(parse_size("GB").is_err());
+    }
+}
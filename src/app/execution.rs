@@ -17,6 +17,8 @@
 
 use std::sync::Arc;
 
+#[cfg(any(feature = "flightsql", feature = "substrait"))]
+use color_eyre::eyre::eyre;
 use color_eyre::eyre::Result;
 use datafusion::execution::runtime_env::RuntimeEnv;
 use datafusion::execution::session_state::SessionStateBuilder;
@@ -26,35 +28,97 @@ use datafusion::prelude::*;
 use datafusion::{arrow::util::pretty::pretty_format_batches, physical_plan::ExecutionPlan};
 #[cfg(feature = "deltalake")]
 use deltalake::delta_datafusion::DeltaTableFactory;
-use log::info;
+use log::{error, info};
 use tokio_stream::StreamExt;
 use tokio_util::sync::CancellationToken;
+use tracing::{info_span, Instrument, Span};
+#[cfg(feature = "s3")]
+use url::Url;
 #[cfg(feature = "flightsql")]
 use {
-    arrow_flight::sql::client::FlightSqlServiceClient, tokio::sync::Mutex,
-    tonic::transport::Channel,
+    arrow_flight::flight_service_client::FlightServiceClient,
+    arrow_flight::{
+        decode::DecodedPayload,
+        sql::{
+            client::{FlightSqlServiceClient, PreparedStatement},
+            CommandGetDbSchemas, CommandGetPrimaryKeys, CommandGetTables, CommandGetXdbcTypeInfo,
+        },
+        FlightEndpoint, FlightInfo,
+    },
+    datafusion::arrow::{
+        array::{RecordBatch, RecordBatchOptions, StringArray},
+        compute::{cast_with_options, CastOptions},
+        datatypes::{Field, Schema},
+    },
+    futures::future::try_join_all,
+    std::future::Future,
+    std::time::{Duration, Instant},
+    tokio::sync::Mutex,
+    tonic::{
+        transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri},
+        IntoRequest,
+    },
+};
+#[cfg(all(feature = "flightsql", feature = "substrait"))]
+use {
+    arrow_flight::{
+        sql::{CommandStatementSubstraitPlan, ProstMessageExt, SubstraitPlan},
+        FlightDescriptor, HandshakeRequest,
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    datafusion_substrait::substrait,
+    prost::Message,
+    std::str::FromStr,
+    tonic::metadata::AsciiMetadataKey,
+};
+#[cfg(feature = "substrait")]
+use {
+    datafusion_substrait::{logical_plan::consumer::from_substrait_plan, serializer},
+    std::path::Path,
 };
-#[cfg(feature = "s3")]
-use {log::error, url::Url};
 
 use super::config::ExecutionConfig;
+use super::instrument::{trace_plan, TracedObjectStoreRegistry};
+use super::resources::ResourceMonitor;
+#[cfg(feature = "flightsql")]
+use super::{
+    config::{FlightSQLConnectionConfig, FlightSQLTlsConfig},
+    federation::FlightSQLTableFactory,
+    state::metadata::flightsql::{MetadataBatches, TableRef},
+};
 
 pub struct ExecutionContext {
     pub session_ctx: SessionContext,
     pub config: ExecutionConfig,
+    pub resources: ResourceMonitor,
     pub cancellation_token: CancellationToken,
     #[cfg(feature = "flightsql")]
     pub flightsql_client: Arc<Mutex<Option<FlightSqlServiceClient<Channel>>>>,
+    #[cfg(feature = "flightsql")]
+    pub flightsql_prepared: Arc<Mutex<Option<FlightSQLPrepared>>>,
 }
 
 impl ExecutionContext {
     #[allow(unused_mut)]
     pub fn new(config: ExecutionConfig) -> Self {
-        let cfg = SessionConfig::default()
+        let mut cfg = SessionConfig::default()
             .with_batch_size(1)
             .with_information_schema(true);
+        for (key, value) in config.datafusion_options() {
+            if let Err(e) = cfg.options_mut().set(&key, &value) {
+                error!("Error setting {} to {:?}: {}", key, value, e);
+            }
+        }
 
-        let runtime_env = RuntimeEnv::default();
+        let resources = ResourceMonitor::new(&config.memory);
+        // Requests to object stores are traced along with the query that makes them
+        let runtime_config = resources
+            .runtime_config()
+            .with_object_store_registry(Arc::new(TracedObjectStoreRegistry::new()));
+        let runtime_env = RuntimeEnv::new(runtime_config).unwrap_or_else(|e| {
+            error!("Error creating runtime, using the default: {}", e);
+            RuntimeEnv::default()
+        });
 
         #[cfg(feature = "s3")]
         {
@@ -105,14 +169,31 @@ impl ExecutionContext {
 
             Self {
                 config,
+                resources,
                 session_ctx,
                 cancellation_token,
                 #[cfg(feature = "flightsql")]
                 flightsql_client: Arc::new(Mutex::new(None)),
+                #[cfg(feature = "flightsql")]
+                flightsql_prepared: Arc::new(Mutex::new(None)),
             }
         }
     }
 
+    /// Register the `FLIGHTSQL` table factory. Table locations can name one of `connections` to
+    /// use its credentials and TLS settings.
+    #[cfg(feature = "flightsql")]
+    pub fn register_flightsql_tables(&self, connections: Vec<FlightSQLConnectionConfig>) {
+        self.session_ctx
+            .state_ref()
+            .write()
+            .table_factories_mut()
+            .insert(
+                "FLIGHTSQL".to_string(),
+                Arc::new(FlightSQLTableFactory::new(connections)),
+            );
+    }
+
     pub fn create_tables(&mut self) -> Result<()> {
         Ok(())
     }
@@ -146,6 +227,743 @@ impl ExecutionContext {
     }
 }
 
+/// Plan `sql`, recording logical planning, optimization and physical planning as spans of the
+/// current query span. The operators of the plan record their partitions as spans when executed.
+pub async fn create_traced_physical_plan(
+    ctx: &SessionContext,
+    sql: &str,
+) -> datafusion::common::Result<Arc<dyn ExecutionPlan>> {
+    let df = ctx.sql(sql).instrument(info_span!("logical_plan")).await?;
+    let (state, plan) = df.into_parts();
+    let plan = info_span!("optimize").in_scope(|| state.optimize(&plan))?;
+    let plan = state
+        .query_planner()
+        .create_physical_plan(&plan, &state)
+        .instrument(info_span!("physical_plan"))
+        .await?;
+    trace_plan(plan, &Span::current())
+}
+
+/// The optimized logical plan of `sql` as a serialized Substrait plan
+#[cfg(feature = "substrait")]
+pub async fn substrait_plan(ctx: &SessionContext, sql: &str) -> Result<Vec<u8>> {
+    Ok(serializer::serialize_bytes(sql, ctx).await?)
+}
+
+/// Save the Substrait plan of `sql` to `path`
+#[cfg(feature = "substrait")]
+pub async fn export_substrait(ctx: &SessionContext, sql: &str, path: &Path) -> Result<()> {
+    let plan = substrait_plan(ctx, sql).await?;
+    std::fs::write(path, plan).map_err(|e| {
+        eyre!(
+            "Unable to write Substrait plan to {}: {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// Plan the Substrait plan saved at `path` against the local session context
+#[cfg(feature = "substrait")]
+pub async fn substrait_dataframe(ctx: &SessionContext, path: &Path) -> Result<DataFrame> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        eyre!(
+            "Unable to read Substrait plan from {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+    let plan = serializer::deserialize_bytes(bytes).await?;
+    let logical_plan = from_substrait_plan(ctx, &plan).await?;
+    Ok(ctx.execute_logical_plan(logical_plan).await?)
+}
+
+/// Batches and rows received so far while fetching the results of a FlightSQL query
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlightSQLProgress {
+    pub endpoints: usize,
+    pub batches: usize,
+    pub rows: usize,
+}
+
+/// The URL to connect to for a FlightSQL endpoint location, or `None` when the endpoint is
+/// fetched with the connection the query was sent on
+#[cfg(feature = "flightsql")]
+pub fn location_url(uri: &str) -> Option<String> {
+    if uri.is_empty() || uri.starts_with("arrow-flight-reuse-connection:") {
+        return None;
+    }
+    let url = if let Some(rest) = uri.strip_prefix("grpc+tls://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = uri
+        .strip_prefix("grpc+tcp://")
+        .or_else(|| uri.strip_prefix("grpc://"))
+    {
+        format!("http://{}", rest)
+    } else {
+        uri.to_string()
+    };
+    Some(url)
+}
+
+/// TLS settings from `tls`, checking the server's certificate against `domain` if given
+#[cfg(feature = "flightsql")]
+fn client_tls(tls: &FlightSQLTlsConfig, domain: Option<&str>) -> Result<ClientTlsConfig> {
+    let mut tls_config = ClientTlsConfig::new();
+    if let Some(ca_cert) = &tls.ca_cert {
+        tls_config = tls_config.ca_certificate(Certificate::from_pem(std::fs::read(ca_cert)?));
+    }
+    match (&tls.client_cert, &tls.client_key) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?);
+            tls_config = tls_config.identity(identity);
+        }
+        (None, None) => {}
+        _ => return Err(eyre!("Both client_cert and client_key are required")),
+    }
+    if let Some(domain) = domain {
+        tls_config = tls_config.domain_name(domain);
+    }
+    Ok(tls_config)
+}
+
+/// An endpoint for `url` with the timeouts of `config`, using TLS if `tls_config` is given
+#[cfg(feature = "flightsql")]
+fn build_endpoint(
+    url: &str,
+    config: &FlightSQLConnectionConfig,
+    tls_config: Option<ClientTlsConfig>,
+) -> Result<Endpoint> {
+    let mut endpoint = Channel::from_shared(url.to_string())?;
+    if let Some(tls_config) = tls_config {
+        endpoint = endpoint.tls_config(tls_config)?;
+    }
+    if let Some(ms) = config.connect_timeout_ms {
+        endpoint = endpoint.connect_timeout(Duration::from_millis(ms));
+    }
+    if let Some(ms) = config.request_timeout_ms {
+        endpoint = endpoint.timeout(Duration::from_millis(ms));
+    }
+    Ok(endpoint)
+}
+
+/// An endpoint for `url` with the TLS settings and timeouts of `config`
+#[cfg(feature = "flightsql")]
+fn flightsql_endpoint(url: &str, config: &FlightSQLConnectionConfig) -> Result<Endpoint> {
+    let tls = &config.tls;
+    let use_tls = url.starts_with("https://")
+        || tls.enabled
+        || tls.ca_cert.is_some()
+        || tls.client_cert.is_some()
+        || tls.domain.is_some();
+    let tls_config = match use_tls {
+        true => Some(client_tls(tls, tls.domain.as_deref())?),
+        false => None,
+    };
+    build_endpoint(url, config, tls_config)
+}
+
+/// The name the certificate of the endpoint location `url` is checked against. The `domain`
+/// override of `config` only applies to the host of its connection URL, so other hosts are
+/// checked against their own name.
+#[cfg(feature = "flightsql")]
+fn location_domain<'a>(url: &str, config: &'a FlightSQLConnectionConfig) -> Option<&'a str> {
+    let host = |url: &str| url.parse::<Uri>().ok()?.host().map(str::to_string);
+    match host(url) {
+        Some(location) if Some(&location) == host(&config.connection_url).as_ref() => {
+            config.tls.domain.as_deref()
+        }
+        _ => None,
+    }
+}
+
+/// An endpoint for the endpoint location `url`. Its scheme decides whether TLS is used, with the
+/// certificates of `config`.
+#[cfg(feature = "flightsql")]
+fn location_endpoint(url: &str, config: &FlightSQLConnectionConfig) -> Result<Endpoint> {
+    let tls_config = match url.starts_with("https://") {
+        true => Some(client_tls(&config.tls, location_domain(url, config))?),
+        false => None,
+    };
+    build_endpoint(url, config, tls_config)
+}
+
+/// The outcome of connection attempt `attempt`, with how long connecting took
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Debug)]
+pub struct FlightSQLConnection {
+    pub attempt: usize,
+    pub client: std::result::Result<(FlightSqlServiceClient<Channel>, Duration), String>,
+}
+
+/// Connect to the FlightSQL server of `config`, authenticating if credentials are configured
+#[cfg(feature = "flightsql")]
+pub async fn connect_flightsql(
+    config: &FlightSQLConnectionConfig,
+) -> Result<FlightSqlServiceClient<Channel>> {
+    let channel = flightsql_endpoint(&config.connection_url, config)?
+        .connect()
+        .await
+        .map_err(|e| eyre!("Unable to connect to {}: {}", config.connection_url, e))?;
+    let mut client = FlightSqlServiceClient::new(channel);
+    for (key, value) in &config.headers {
+        client.set_header(key, value);
+    }
+    if let Some(token) = config.auth.bearer_token()? {
+        client.set_token(token);
+    }
+    if let Some(username) = &config.auth.username {
+        let password = config.auth.password()?;
+        client.handshake(username, &password).await?;
+        info!("Authenticated with FlightSQL as {}", username);
+    }
+    Ok(client)
+}
+
+/// The client to fetch `endpoint` with. Endpoints on other hosts list the locations they can
+/// be fetched from, which are tried in order, skipping any that can't be connected to. Clients
+/// for other hosts keep the token and headers of `client`.
+#[cfg(feature = "flightsql")]
+pub async fn endpoint_client(
+    client: &FlightSqlServiceClient<Channel>,
+    endpoint: &FlightEndpoint,
+    config: &FlightSQLConnectionConfig,
+) -> Result<FlightSqlServiceClient<Channel>> {
+    let urls: Vec<String> = endpoint
+        .location
+        .iter()
+        .filter_map(|l| location_url(&l.uri))
+        .collect();
+    if urls.is_empty() {
+        return Ok(client.clone());
+    }
+    for url in &urls {
+        let endpoint = match location_endpoint(url, config) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                info!("Unable to use endpoint location {}: {}", url, e);
+                continue;
+            }
+        };
+        match endpoint.connect().await {
+            Ok(channel) => {
+                info!("Fetching endpoint from {}", url);
+                let mut location_client = client.clone();
+                *location_client.inner_mut() = FlightServiceClient::new(channel);
+                return Ok(location_client);
+            }
+            Err(e) => info!("Unable to connect to endpoint location {}: {}", url, e),
+        }
+    }
+    Err(eyre!(
+        "Unable to connect to any endpoint location: {}",
+        urls.join(", ")
+    ))
+}
+
+/// A clone of the connected client, so that the lock isn't held while requests are made
+#[cfg(feature = "flightsql")]
+async fn connected_client(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+) -> Result<FlightSqlServiceClient<Channel>> {
+    client
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| eyre!("No FlightSQL connection"))
+}
+
+/// How the results of a FlightSQL query were fetched
+#[cfg(feature = "flightsql")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlightSQLStats {
+    pub endpoints: usize,
+    pub batches: usize,
+    pub rows: usize,
+    /// Size of the IPC messages received, which is roughly the data transferred
+    pub ipc_bytes: usize,
+    /// From sending the query until the first batch arrived
+    pub time_to_first_batch: Option<Duration>,
+    /// From sending the query until the last batch arrived
+    pub transfer_time: Duration,
+    /// Totals reported by the server in its `FlightInfo`, when it knows them
+    pub total_records: Option<i64>,
+    pub total_bytes: Option<i64>,
+}
+
+#[cfg(feature = "flightsql")]
+impl FlightSQLStats {
+    fn progress(&self) -> FlightSQLProgress {
+        FlightSQLProgress {
+            endpoints: self.endpoints,
+            batches: self.batches,
+            rows: self.rows,
+        }
+    }
+}
+
+/// Collect every batch of every endpoint of `flight_info`. Endpoints are fetched concurrently
+/// and their batches kept in endpoint order, so results the server marks as `ordered` stay in
+/// order. `on_progress` is called as each batch arrives.
+#[cfg(feature = "flightsql")]
+pub async fn fetch_flight_info(
+    client: &FlightSqlServiceClient<Channel>,
+    config: &FlightSQLConnectionConfig,
+    flight_info: FlightInfo,
+    on_progress: impl Fn(FlightSQLProgress),
+) -> Result<Vec<RecordBatch>> {
+    let (batches, _) =
+        fetch_flight_info_stats(client, config, flight_info, Instant::now(), on_progress).await?;
+    Ok(batches)
+}
+
+/// [`fetch_flight_info`] along with how the batches were fetched, timed from `sent`
+#[cfg(feature = "flightsql")]
+async fn fetch_flight_info_stats(
+    client: &FlightSqlServiceClient<Channel>,
+    config: &FlightSQLConnectionConfig,
+    flight_info: FlightInfo,
+    sent: Instant,
+    on_progress: impl Fn(FlightSQLProgress),
+) -> Result<(Vec<RecordBatch>, FlightSQLStats)> {
+    info!(
+        "Fetching {} endpoints (ordered: {})",
+        flight_info.endpoint.len(),
+        flight_info.ordered
+    );
+    // The server reports -1 for totals it doesn't know
+    let reported = |total: i64| (total >= 0).then_some(total);
+    let stats = std::sync::Mutex::new(FlightSQLStats {
+        endpoints: flight_info.endpoint.len(),
+        total_records: reported(flight_info.total_records),
+        total_bytes: reported(flight_info.total_bytes),
+        ..Default::default()
+    });
+    let fetches = flight_info.endpoint.into_iter().map(|endpoint| {
+        let client = &client;
+        let stats = &stats;
+        let on_progress = &on_progress;
+        async move {
+            let Some(ticket) = endpoint.ticket.clone() else {
+                return Ok(Vec::new());
+            };
+            let mut client = endpoint_client(client, &endpoint, config).await?;
+            let mut stream = client.do_get(ticket.into_request()).await?.into_inner();
+            let mut batches = Vec::new();
+            while let Some(data) = stream.next().await {
+                let data = data?;
+                let mut stats = stats.lock().expect("Stats lock is never poisoned");
+                stats.ipc_bytes += data.inner.data_header.len() + data.inner.data_body.len();
+                if let DecodedPayload::RecordBatch(batch) = data.payload {
+                    stats.batches += 1;
+                    stats.rows += batch.num_rows();
+                    stats
+                        .time_to_first_batch
+                        .get_or_insert_with(|| sent.elapsed());
+                    let progress = stats.progress();
+                    drop(stats);
+                    on_progress(progress);
+                    batches.push(batch);
+                }
+            }
+            Ok::<_, color_eyre::eyre::Error>(batches)
+        }
+    });
+    let batches = try_join_all(fetches).await?;
+    let mut stats = stats.into_inner().expect("Stats lock is never poisoned");
+    stats.transfer_time = sent.elapsed();
+    Ok((batches.into_iter().flatten().collect(), stats))
+}
+
+/// Run `sql` on the FlightSQL server, collecting every batch of every endpoint along with how
+/// they were fetched
+#[cfg(feature = "flightsql")]
+pub async fn flightsql_batches(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    config: &FlightSQLConnectionConfig,
+    sql: &str,
+    on_progress: impl Fn(FlightSQLProgress),
+) -> Result<(Vec<RecordBatch>, FlightSQLStats)> {
+    let mut client = connected_client(client).await?;
+    let sent = Instant::now();
+    let flight_info = client.execute(sql.to_string(), None).await?;
+    fetch_flight_info_stats(&client, config, flight_info, sent, on_progress).await
+}
+
+/// A statement prepared on the FlightSQL server, with the SQL it was prepared from
+#[cfg(feature = "flightsql")]
+#[derive(Debug)]
+pub struct FlightSQLPrepared {
+    sql: String,
+    statement: PreparedStatement<Channel>,
+}
+
+/// What running a prepared statement returned. Statements without a result schema, such as
+/// inserts and updates, are run as updates that return how many rows they affected.
+#[cfg(feature = "flightsql")]
+pub enum PreparedOutcome {
+    Batches(Vec<RecordBatch>, FlightSQLStats),
+    Updated(i64),
+}
+
+/// A single row batch of `schema` from comma separated `values`, each cast from text to the type
+/// of its parameter. `NULL` binds a null.
+#[cfg(feature = "flightsql")]
+pub fn parameter_batch(schema: &Schema, values: &str) -> Result<RecordBatch> {
+    let values: Vec<&str> = if values.trim().is_empty() {
+        Vec::new()
+    } else {
+        values.split(',').map(str::trim).collect()
+    };
+    if values.len() != schema.fields().len() {
+        return Err(eyre!(
+            "Expected {} parameters, got {}",
+            schema.fields().len(),
+            values.len()
+        ));
+    }
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(values)
+        .map(|(field, value)| {
+            let value = (!value.eq_ignore_ascii_case("null")).then_some(value);
+            let text = StringArray::from(vec![value]);
+            let options = CastOptions {
+                safe: false,
+                ..Default::default()
+            };
+            cast_with_options(&text, field.data_type(), &options)
+                .map_err(|e| eyre!("Invalid value for parameter {}: {}", field.name(), e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // Parameters are bound by position, so names and nullability don't have to match
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|f| Field::new(f.name(), f.data_type().clone(), true))
+        .collect();
+    let options = RecordBatchOptions::new().with_row_count(Some(1));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        columns,
+        &options,
+    )?)
+}
+
+/// Close the prepared statement, if there is one
+#[cfg(feature = "flightsql")]
+pub async fn close_prepared(prepared: &Mutex<Option<FlightSQLPrepared>>) {
+    if let Some(prepared) = prepared.lock().await.take() {
+        info!("Closing prepared statement: {}", prepared.sql);
+        if let Err(e) = prepared.statement.close().await {
+            info!("Error closing prepared statement: {}", e);
+        }
+    }
+}
+
+/// Prepare `sql` on the FlightSQL server, returning the schema of its parameters. The prepared
+/// statement is reused while the SQL stays the same, otherwise the previous one is closed.
+#[cfg(feature = "flightsql")]
+pub async fn prepare_flightsql(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    prepared: &Mutex<Option<FlightSQLPrepared>>,
+    sql: &str,
+) -> Result<Schema> {
+    if let Some(existing) = prepared.lock().await.as_ref().filter(|p| p.sql == sql) {
+        return Ok(existing.statement.parameter_schema()?.clone());
+    }
+    close_prepared(prepared).await;
+    let statement = connected_client(client)
+        .await?
+        .prepare(sql.to_string(), None)
+        .await?;
+    let parameters = statement.parameter_schema()?.clone();
+    *prepared.lock().await = Some(FlightSQLPrepared {
+        sql: sql.to_string(),
+        statement,
+    });
+    Ok(parameters)
+}
+
+/// Bind `parameters` to the prepared statement and run it
+#[cfg(feature = "flightsql")]
+pub async fn execute_prepared(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    prepared: &Mutex<Option<FlightSQLPrepared>>,
+    config: &FlightSQLConnectionConfig,
+    parameters: &str,
+    on_progress: impl Fn(FlightSQLProgress),
+) -> Result<PreparedOutcome> {
+    let mut prepared = prepared.lock().await;
+    let statement = &mut prepared
+        .as_mut()
+        .ok_or_else(|| eyre!("No prepared statement"))?
+        .statement;
+    let schema = statement.parameter_schema()?.clone();
+    if !schema.fields().is_empty() {
+        statement.set_parameters(parameter_batch(&schema, parameters)?)?;
+    }
+    if statement.dataset_schema()?.fields().is_empty() {
+        return Ok(PreparedOutcome::Updated(statement.execute_update().await?));
+    }
+    let sent = Instant::now();
+    let flight_info = statement.execute().await?;
+    let client = connected_client(client).await?;
+    let (batches, stats) =
+        fetch_flight_info_stats(&client, config, flight_info, sent, on_progress).await?;
+    Ok(PreparedOutcome::Batches(batches, stats))
+}
+
+/// The token to authenticate requests that the FlightSQL client has no method for. Basic auth
+/// tokens are kept private by the client, so a handshake is made to get a new one.
+#[cfg(all(feature = "flightsql", feature = "substrait"))]
+async fn request_token(
+    client: &FlightSqlServiceClient<Channel>,
+    config: &FlightSQLConnectionConfig,
+) -> Result<Option<String>> {
+    if let Some(token) = config.auth.bearer_token()? {
+        return Ok(Some(token));
+    }
+    let Some(username) = &config.auth.username else {
+        return Ok(None);
+    };
+    let credentials = BASE64_STANDARD.encode(format!("{}:{}", username, config.auth.password()?));
+    let mut request = futures::stream::iter(vec![HandshakeRequest::default()]).into_request();
+    set_request_headers(&mut request, config)?;
+    request
+        .metadata_mut()
+        .insert("authorization", format!("Basic {}", credentials).parse()?);
+    let response = client.inner().clone().handshake(request).await?;
+    let token = response
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    Ok(token)
+}
+
+#[cfg(all(feature = "flightsql", feature = "substrait"))]
+fn set_request_headers<T>(
+    request: &mut tonic::Request<T>,
+    config: &FlightSQLConnectionConfig,
+) -> Result<()> {
+    for (key, value) in &config.headers {
+        request
+            .metadata_mut()
+            .insert(AsciiMetadataKey::from_str(key)?, value.parse()?);
+    }
+    Ok(())
+}
+
+/// Run a serialized Substrait plan on the FlightSQL server, collecting every batch of every
+/// endpoint
+#[cfg(all(feature = "flightsql", feature = "substrait"))]
+pub async fn flightsql_substrait_batches(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    config: &FlightSQLConnectionConfig,
+    plan: Vec<u8>,
+    on_progress: impl Fn(FlightSQLProgress),
+) -> Result<(Vec<RecordBatch>, FlightSQLStats)> {
+    let client = connected_client(client).await?;
+    let version = substrait::version::version();
+    let command = CommandStatementSubstraitPlan {
+        plan: Some(SubstraitPlan {
+            plan: plan.into(),
+            version: format!(
+                "{}.{}.{}",
+                version.major_number, version.minor_number, version.patch_number
+            ),
+        }),
+        transaction_id: None,
+    };
+    let descriptor = FlightDescriptor::new_cmd(command.as_any().encode_to_vec());
+    let mut request = descriptor.into_request();
+    set_request_headers(&mut request, config)?;
+    if let Some(token) = request_token(&client, config).await? {
+        request
+            .metadata_mut()
+            .insert("authorization", format!("Bearer {}", token).parse()?);
+    }
+    let sent = Instant::now();
+    let flight_info = client
+        .inner()
+        .clone()
+        .get_flight_info(request)
+        .await?
+        .into_inner();
+    fetch_flight_info_stats(&client, config, flight_info, sent, on_progress).await
+}
+
+/// The batches of an optional metadata command, or none if the server doesn't support it
+#[cfg(feature = "flightsql")]
+async fn optional_metadata(
+    command: &str,
+    fetched: impl Future<Output = Result<Vec<RecordBatch>>>,
+) -> Vec<RecordBatch> {
+    fetched.await.unwrap_or_else(|e| {
+        info!("Unable to get FlightSQL {}: {}", command, e);
+        Vec::new()
+    })
+}
+
+/// Run the FlightSQL metadata commands. Only listing tables is required, the other commands
+/// are left out if the server doesn't support them. Primary keys are requested separately, for
+/// each table whose columns are shown.
+#[cfg(feature = "flightsql")]
+pub async fn flightsql_metadata(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    config: &FlightSQLConnectionConfig,
+) -> Result<MetadataBatches> {
+    let client = connected_client(client).await?;
+    let fetch = |flight_info| fetch_flight_info(&client, config, flight_info, |_| {});
+
+    let command = CommandGetTables {
+        include_schema: true,
+        ..Default::default()
+    };
+    let tables = fetch(client.clone().get_tables(command).await?).await?;
+    let mut metadata = MetadataBatches {
+        tables,
+        ..Default::default()
+    };
+    metadata.catalogs = optional_metadata("catalogs", async {
+        fetch(client.clone().get_catalogs().await?).await
+    })
+    .await;
+    metadata.schemas = optional_metadata("schemas", async {
+        let command = CommandGetDbSchemas::default();
+        fetch(client.clone().get_db_schemas(command).await?).await
+    })
+    .await;
+    metadata.types = optional_metadata("types", async {
+        let command = CommandGetXdbcTypeInfo::default();
+        fetch(client.clone().get_xdbc_type_info(command).await?).await
+    })
+    .await;
+    metadata.sql_info = optional_metadata("SQL info", async {
+        fetch(client.clone().get_sql_info(Vec::new()).await?).await
+    })
+    .await;
+
+    Ok(metadata)
+}
+
+/// The primary keys of a table, requested when its columns are first shown as they can only be
+/// listed a table at a time. None are returned if the server doesn't support listing them.
+#[cfg(feature = "flightsql")]
+pub async fn flightsql_primary_keys(
+    client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    config: &FlightSQLConnectionConfig,
+    table: &TableRef,
+) -> Result<Vec<RecordBatch>> {
+    let client = connected_client(client).await?;
+    let command = CommandGetPrimaryKeys {
+        catalog: Some(table.catalog.clone()),
+        db_schema: Some(table.schema.clone()),
+        table: table.table.clone(),
+    };
+    Ok(optional_metadata("primary keys", async {
+        let flight_info = client.clone().get_primary_keys(command).await?;
+        fetch_flight_info(&client, config, flight_info, |_| {}).await
+    })
+    .await)
+}
+
+#[cfg(all(test, feature = "flightsql"))]
+mod tests {
+    use datafusion::arrow::{
+        array::{Array, AsArray},
+        datatypes::{DataType, Field, Int64Type, Schema},
+    };
+
+    use super::{location_domain, location_url, parameter_batch};
+    use crate::app::config::FlightSQLConnectionConfig;
+
+    #[test]
+    fn parameter_batch_test() {
+        let schema = Schema::new(vec![
+            Field::new("$1", DataType::Int64, false),
+            Field::new("$2", DataType::Utf8, false),
+        ]);
+        let batch = parameter_batch(&schema, " 42, NULL").unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.column(0).as_primitive::<Int64Type>().value(0), 42);
+        assert!(batch.column(1).is_null(0));
+        assert!(parameter_batch(&schema, "42").is_err());
+        assert!(parameter_batch(&schema, "forty two, a").is_err());
+        assert_eq!(
+            parameter_batch(&Schema::empty(), "").unwrap().num_columns(),
+            0
+        );
+    }
+
+    #[test]
+    fn location_url_test() {
+        assert_eq!(location_url("arrow-flight-reuse-connection://?"), None);
+        assert_eq!(
+            location_url("grpc+tcp://host:50051").as_deref(),
+            Some("http://host:50051")
+        );
+        assert_eq!(
+            location_url("grpc+tls://host:443").as_deref(),
+            Some("https://host:443")
+        );
+        assert_eq!(
+            location_url("http://host:50051").as_deref(),
+            Some("http://host:50051")
+        );
+    }
+
+    #[test]
+    fn location_domain_test() {
+        let mut config = FlightSQLConnectionConfig {
+            connection_url: "https://10.0.0.1:443".to_string(),
+            ..Default::default()
+        };
+        config.tls.domain = Some("flight.example.com".to_string());
+        assert_eq!(
+            location_domain("https://10.0.0.1:8443", &config),
+            Some("flight.example.com")
+        );
+        assert_eq!(location_domain("https://other-host:443", &config), None);
+        config.tls.domain = None;
+        assert_eq!(location_domain("https://10.0.0.1:443", &config), None);
+    }
+}
+
+#[cfg(all(test, feature = "substrait"))]
+mod substrait_tests {
+    use datafusion::arrow::{array::AsArray, datatypes::Int64Type};
+    use datafusion::prelude::SessionContext;
+
+    use super::{export_substrait, substrait_dataframe};
+
+    #[tokio::test]
+    async fn substrait_roundtrip_test() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE t AS VALUES (1, 'a'), (2, 'b')")
+            .await
+            .unwrap();
+        let path = std::env::temp_dir().join("dft_substrait_roundtrip_test.substrait");
+        export_substrait(&ctx, "SELECT column1 FROM t WHERE column2 = 'b'", &path)
+            .await
+            .unwrap();
+        let batches = substrait_dataframe(&ctx, &path)
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 1);
+        assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 2);
+    }
+}
+
 // #[derive(Debug, Clone)]
 // pub struct ExecMetrics {
 //     name: String,
@@ -0,0 +1,584 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tables backed by a FlightSQL server, so that remote data can be queried and joined with
+//! local tables from the SQL tab. They are registered with
+//! `CREATE EXTERNAL TABLE t STORED AS FLIGHTSQL LOCATION 'grpc://host:port' OPTIONS (...)`
+//! with either a remote `table` or a `query` to read from.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use arrow_flight::{
+    sql::{client::FlightSqlServiceClient, CommandGetTables},
+    IpcMessage,
+};
+use async_trait::async_trait;
+use color_eyre::eyre::{eyre, Report};
+use datafusion::arrow::array::{AsArray, RecordBatch, RecordBatchOptions};
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::catalog::{Session, TableProviderFactory};
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{Column, DataFusionError, ScalarValue, TableReference};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::logical_expr::{
+    expr::{InList, Like},
+    Between, BinaryExpr, CreateExternalTable, Expr, Operator, TableProviderFilterPushDown,
+};
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, Partitioning, PlanProperties,
+};
+use datafusion::sql::unparser::{dialect::CustomDialectBuilder, Unparser};
+use futures::{StreamExt, TryStreamExt};
+use log::info;
+use tonic::{transport::Channel, IntoRequest};
+
+use super::config::FlightSQLConnectionConfig;
+use super::execution::{connect_flightsql, endpoint_client, fetch_flight_info, location_url};
+
+type DFResult<T> = datafusion::common::Result<T>;
+
+fn external_error(e: Report) -> DataFusionError {
+    DataFusionError::External(e.into())
+}
+
+/// Creates [`FlightSQLTable`]s. The location is either the name of a configured connection or a
+/// URL, and tables on a configured connection's server use its credentials and TLS settings.
+pub struct FlightSQLTableFactory {
+    connections: Vec<FlightSQLConnectionConfig>,
+}
+
+impl FlightSQLTableFactory {
+    pub fn new(connections: Vec<FlightSQLConnectionConfig>) -> Self {
+        Self { connections }
+    }
+
+    fn connection(&self, location: &str) -> DFResult<FlightSQLConnectionConfig> {
+        if let Some(config) = self.connections.iter().find(|c| c.name == location) {
+            return Ok(config.clone());
+        }
+        let url = location_url(location).ok_or_else(|| {
+            DataFusionError::Plan("A FlightSQL table needs a LOCATION".to_string())
+        })?;
+        let config = self
+            .connections
+            .iter()
+            .find(|c| c.connection_url == url)
+            .cloned()
+            .unwrap_or_else(|| FlightSQLConnectionConfig {
+                name: location.to_string(),
+                connection_url: url,
+                ..Default::default()
+            });
+        Ok(config)
+    }
+}
+
+/// An option of a `CREATE EXTERNAL TABLE` statement. Options without a namespace are given a
+/// `format.` prefix by DataFusion, so both `'query'` and `'flightsql.query'` are accepted.
+fn table_option<'a>(cmd: &'a CreateExternalTable, key: &str) -> Option<&'a str> {
+    cmd.options
+        .get(&format!("format.{}", key))
+        .or_else(|| cmd.options.get(&format!("flightsql.{}", key)))
+        .map(|v| v.as_str())
+}
+
+#[async_trait]
+impl TableProviderFactory for FlightSQLTableFactory {
+    async fn create(
+        &self,
+        _state: &dyn Session,
+        cmd: &CreateExternalTable,
+    ) -> DFResult<Arc<dyn TableProvider>> {
+        let source = match (table_option(cmd, "table"), table_option(cmd, "query")) {
+            (Some(table), None) => RemoteSource::Table(table.to_string()),
+            (None, Some(query)) => {
+                RemoteSource::Query(query.trim().trim_end_matches(';').trim_end().to_string())
+            }
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "A FlightSQL table needs exactly one of the 'table' or 'query' options"
+                        .to_string(),
+                ))
+            }
+        };
+        let config = self.connection(&cmd.location)?;
+        let client = connect_flightsql(&config).await.map_err(external_error)?;
+        let schema = if cmd.schema.fields().is_empty() {
+            remote_schema(&client, &config, &source)
+                .await
+                .map_err(external_error)?
+        } else {
+            Arc::clone(cmd.schema.inner())
+        };
+        info!(
+            "Created FlightSQL table {} on {} with {} columns",
+            cmd.name,
+            config.connection_url,
+            schema.fields().len()
+        );
+        Ok(Arc::new(FlightSQLTable {
+            client,
+            config,
+            source,
+            schema,
+        }))
+    }
+}
+
+/// What a [`FlightSQLTable`] reads from on the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemoteSource {
+    /// A table name, in the server's syntax
+    Table(String),
+    /// A query whose results are the table
+    Query(String),
+}
+
+impl RemoteSource {
+    /// The relation to select from in the queries sent to the server
+    fn relation(&self) -> String {
+        match self {
+            RemoteSource::Table(table) => table.clone(),
+            RemoteSource::Query(query) => format!("({}) AS dft_source", query),
+        }
+    }
+}
+
+/// The schema of `source`. Tables are looked up with `get_tables`, and queries (or tables the
+/// server can't list) use the schema the server plans for the query.
+async fn remote_schema(
+    client: &FlightSqlServiceClient<Channel>,
+    config: &FlightSQLConnectionConfig,
+    source: &RemoteSource,
+) -> color_eyre::Result<SchemaRef> {
+    if let RemoteSource::Table(table) = source {
+        match table_schema(client, config, table).await {
+            Ok(Some(schema)) => return Ok(schema),
+            Ok(None) => info!("Table {} wasn't listed by the server", table),
+            Err(e) => info!("Unable to get the schema of {}: {}", table, e),
+        }
+    }
+    let sql = format!("SELECT * FROM {}", source.relation());
+    let flight_info = client.clone().execute(sql, None).await?;
+    if flight_info.schema.is_empty() {
+        return Err(eyre!(
+            "The server didn't return a schema, declare the columns in CREATE EXTERNAL TABLE"
+        ));
+    }
+    Ok(Arc::new(flight_info.try_decode_schema()?))
+}
+
+async fn table_schema(
+    client: &FlightSqlServiceClient<Channel>,
+    config: &FlightSQLConnectionConfig,
+    table: &str,
+) -> color_eyre::Result<Option<SchemaRef>> {
+    let reference = TableReference::parse_str(table);
+    let command = CommandGetTables {
+        catalog: reference.catalog().map(|c| c.to_string()),
+        db_schema_filter_pattern: reference.schema().map(|s| s.to_string()),
+        table_name_filter_pattern: Some(reference.table().to_string()),
+        table_types: vec![],
+        include_schema: true,
+    };
+    let flight_info = client.clone().get_tables(command).await?;
+    let batches = fetch_flight_info(client, config, flight_info, |_| {}).await?;
+    for batch in batches {
+        let (Some(names), Some(schemas)) = (
+            batch.column_by_name("table_name"),
+            batch.column_by_name("table_schema"),
+        ) else {
+            continue;
+        };
+        let names = names.as_string::<i32>();
+        let schemas = schemas.as_binary::<i32>();
+        // Filter patterns are `LIKE` patterns, so an exact match is preferred
+        if let Some(i) = (0..batch.num_rows()).find(|i| names.value(*i) == reference.table()) {
+            let schema = Schema::try_from(IpcMessage(schemas.value(i).to_vec().into()))?;
+            return Ok(Some(Arc::new(schema)));
+        }
+    }
+    Ok(None)
+}
+
+/// A table on a FlightSQL server. Projections, simple filters and limits are sent to the server
+/// as SQL and the results streamed into the local plan.
+pub struct FlightSQLTable {
+    client: FlightSqlServiceClient<Channel>,
+    config: FlightSQLConnectionConfig,
+    source: RemoteSource,
+    schema: SchemaRef,
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn is_pushdown_literal(value: &ScalarValue) -> bool {
+    !value.is_null()
+        && matches!(
+            value,
+            ScalarValue::Boolean(_)
+                | ScalarValue::Int8(_)
+                | ScalarValue::Int16(_)
+                | ScalarValue::Int32(_)
+                | ScalarValue::Int64(_)
+                | ScalarValue::UInt8(_)
+                | ScalarValue::UInt16(_)
+                | ScalarValue::UInt32(_)
+                | ScalarValue::UInt64(_)
+                | ScalarValue::Float32(_)
+                | ScalarValue::Float64(_)
+                | ScalarValue::Utf8(_)
+                | ScalarValue::LargeUtf8(_)
+        )
+}
+
+/// Whether `expr` only uses columns, plain literals, comparisons and boolean logic, which are
+/// written the same way by most SQL dialects
+fn is_simple_filter(expr: &Expr) -> bool {
+    match expr {
+        Expr::Column(_) => true,
+        Expr::Literal(value) => is_pushdown_literal(value),
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            matches!(
+                op,
+                Operator::Eq
+                    | Operator::NotEq
+                    | Operator::Lt
+                    | Operator::LtEq
+                    | Operator::Gt
+                    | Operator::GtEq
+                    | Operator::And
+                    | Operator::Or
+            ) && is_simple_filter(left)
+                && is_simple_filter(right)
+        }
+        Expr::Not(e) | Expr::IsNull(e) | Expr::IsNotNull(e) => is_simple_filter(e),
+        Expr::Between(Between {
+            expr, low, high, ..
+        }) => is_simple_filter(expr) && is_simple_filter(low) && is_simple_filter(high),
+        Expr::Like(Like {
+            expr,
+            pattern,
+            escape_char: None,
+            case_insensitive: false,
+            ..
+        }) => is_simple_filter(expr) && is_simple_filter(pattern),
+        Expr::InList(InList { expr, list, .. }) => {
+            is_simple_filter(expr) && list.iter().all(is_simple_filter)
+        }
+        _ => false,
+    }
+}
+
+/// The SQL for `filter` when it can be evaluated by the server. Columns are unqualified, as the
+/// server doesn't know the local table name, and always quoted like projected columns so that
+/// servers don't fold the case of their names.
+fn filter_sql(filter: &Expr) -> Option<String> {
+    if !is_simple_filter(filter) {
+        return None;
+    }
+    let unqualified = filter
+        .clone()
+        .transform(|e| {
+            Ok(match e {
+                Expr::Column(c) => Transformed::yes(Expr::Column(Column::new_unqualified(c.name))),
+                e => Transformed::no(e),
+            })
+        })
+        .ok()?
+        .data;
+    let dialect = CustomDialectBuilder::new()
+        .with_identifier_quote_style('"')
+        .build();
+    Unparser::new(&dialect)
+        .expr_to_sql(&unqualified)
+        .ok()
+        .map(|sql| sql.to_string())
+}
+
+impl FlightSQLTable {
+    /// The URL of the server the table is on
+    pub fn url(&self) -> &str {
+        &self.config.connection_url
+    }
+
+    pub fn source(&self) -> &RemoteSource {
+        &self.source
+    }
+
+    /// The query sent to the server for a scan
+    fn scan_sql(
+        &self,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> String {
+        let columns = match projection {
+            Some(indices) if indices.is_empty() => "1".to_string(),
+            Some(indices) => indices
+                .iter()
+                .map(|i| quote_identifier(self.schema.field(*i).name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "*".to_string(),
+        };
+        let mut sql = format!("SELECT {} FROM {}", columns, self.source.relation());
+        let filters: Vec<String> = filters
+            .iter()
+            .filter_map(filter_sql)
+            .map(|f| format!("({})", f))
+            .collect();
+        if !filters.is_empty() {
+            sql.push_str(&format!(" WHERE {}", filters.join(" AND ")));
+        }
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        sql
+    }
+}
+
+#[async_trait]
+impl TableProvider for FlightSQLTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => Arc::clone(&self.schema),
+        };
+        let sql = self.scan_sql(projection, filters, limit);
+        Ok(Arc::new(FlightSQLExec::new(
+            self.client.clone(),
+            self.config.clone(),
+            sql,
+            schema,
+        )))
+    }
+
+    /// Filters are applied again locally, as the server's semantics (for example collations)
+    /// may differ from DataFusion's
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DFResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|f| {
+                if filter_sql(f).is_some() {
+                    TableProviderFilterPushDown::Inexact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+}
+
+/// Runs a query on a FlightSQL server, streaming the batches of each endpoint in order
+pub struct FlightSQLExec {
+    client: FlightSqlServiceClient<Channel>,
+    config: FlightSQLConnectionConfig,
+    sql: String,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl FlightSQLExec {
+    fn new(
+        client: FlightSqlServiceClient<Channel>,
+        config: FlightSQLConnectionConfig,
+        sql: String,
+        schema: SchemaRef,
+    ) -> Self {
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(Arc::clone(&schema)),
+            Partitioning::UnknownPartitioning(1),
+            ExecutionMode::Bounded,
+        );
+        Self {
+            client,
+            config,
+            sql,
+            schema,
+            properties,
+        }
+    }
+}
+
+impl fmt::Debug for FlightSQLExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FlightSQLExec: {}", self.sql)
+    }
+}
+
+impl DisplayAs for FlightSQLExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "FlightSQLExec: url={}, sql={}",
+            self.config.connection_url, self.sql
+        )
+    }
+}
+
+/// `batch` with the schema of the scan. The server's schema can differ in metadata and
+/// nullability, and scans without columns get a single placeholder column.
+fn conform_batch(batch: RecordBatch, schema: &SchemaRef) -> DFResult<RecordBatch> {
+    if schema.fields().is_empty() {
+        let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+        return Ok(RecordBatch::try_new_with_options(
+            Arc::clone(schema),
+            vec![],
+            &options,
+        )?);
+    }
+    Ok(RecordBatch::try_new(
+        Arc::clone(schema),
+        batch.columns().to_vec(),
+    )?)
+}
+
+impl ExecutionPlan for FlightSQLExec {
+    fn name(&self) -> &str {
+        "FlightSQLExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let client = self.client.clone();
+        let config = self.config.clone();
+        let sql = self.sql.clone();
+        info!("Running on FlightSQL server: {}", sql);
+        let endpoints = futures::stream::once(async move {
+            let flight_info = client
+                .clone()
+                .execute(sql, None)
+                .await
+                .map_err(|e| DataFusionError::ArrowError(e, None))?;
+            let batches = futures::stream::iter(flight_info.endpoint)
+                .then(move |endpoint| {
+                    let client = client.clone();
+                    let config = config.clone();
+                    async move {
+                        let Some(ticket) = endpoint.ticket.clone() else {
+                            return Ok(futures::stream::empty().boxed());
+                        };
+                        let mut client = endpoint_client(&client, &endpoint, &config)
+                            .await
+                            .map_err(external_error)?;
+                        let stream = client
+                            .do_get(ticket.into_request())
+                            .await
+                            .map_err(|e| DataFusionError::ArrowError(e, None))?;
+                        Ok::<_, DataFusionError>(
+                            stream
+                                .map_err(|e| DataFusionError::External(Box::new(e)))
+                                .boxed(),
+                        )
+                    }
+                })
+                .try_flatten();
+            Ok::<_, DataFusionError>(batches)
+        })
+        .try_flatten();
+        let schema = Arc::clone(&self.schema);
+        let batches = endpoints.map(move |batch| batch.and_then(|b| conform_batch(b, &schema)));
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            Arc::clone(&self.schema),
+            batches,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::{col, ident, lit};
+
+    use super::{filter_sql, RemoteSource};
+
+    #[test]
+    fn filter_sql_test() {
+        assert_eq!(
+            filter_sql(&col("t.a").gt(lit(1)).and(col("b").eq(lit("x")))).as_deref(),
+            Some("((\"a\" > 1) AND (\"b\" = 'x'))")
+        );
+        assert_eq!(
+            filter_sql(&col("a").in_list(vec![lit(1), lit(2)], false)).as_deref(),
+            Some("\"a\" IN (1, 2)")
+        );
+        assert_eq!(
+            filter_sql(&col("a").is_null()).as_deref(),
+            Some("\"a\" IS NULL")
+        );
+        assert_eq!(
+            filter_sql(&ident("UserId").eq(lit(7))).as_deref(),
+            Some("(\"UserId\" = 7)")
+        );
+        assert_eq!(filter_sql(&(col("a") + lit(1)).gt(lit(2))), None);
+        assert_eq!(filter_sql(&col("a").ilike(lit("x%"))), None);
+        assert_eq!(
+            RemoteSource::Query("select 1".to_string()).relation(),
+            "(select 1) AS dft_source"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use log::{error, info};
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::app::config::save_datafusion_options;
+use crate::app::keymap::{Action, Mode};
+use crate::app::state::{metadata::Metadata, prompt::PromptKind, tabs::sql::Query};
+use crate::app::{handlers::tab_navigation_handler, AppEvent};
+use crate::ui::SelectedTab;
+
+use super::App;
+
+/// Load the catalog of the session context for the catalog browser
+pub fn load_catalog(app: &mut App) {
+    if app.state.context_tab.catalog().is_none() {
+        app.state
+            .context_tab
+            .set_catalog(Metadata::message("Loading catalog..."));
+    }
+    let ctx = app.execution.session_ctx.clone();
+    let event_tx = app.app_event_tx.clone();
+    tokio::spawn(async move {
+        let catalog = Metadata::from_session(&ctx).await.unwrap_or_else(|e| {
+            error!("Error loading catalog: {:?}", e);
+            Metadata::message(format!("Unable to load catalog: {}", e))
+        });
+        let _ = event_tx.send(AppEvent::Catalog(catalog));
+    });
+}
+
+/// Read the first rows of `table`, sending them back once done
+fn preview_table(app: &App, table: String) {
+    let sql = format!(
+        "SELECT * FROM {} LIMIT {}",
+        table, app.state.config.interaction.catalog.preview_rows
+    );
+    let ctx = app.execution.session_ctx.clone();
+    let event_tx = app.app_event_tx.clone();
+    tokio::spawn(async move {
+        let start = std::time::Instant::now();
+        let preview = match ctx.sql(&sql).await {
+            Ok(df) => df.collect().await,
+            Err(e) => Err(e),
+        };
+        let query = match preview {
+            Ok(batches) => {
+                let rows = batches.iter().map(|b| b.num_rows()).sum();
+                Query::new(sql, Some(batches), Some(rows), None, start.elapsed(), None)
+            }
+            Err(e) => {
+                error!("Error previewing {}: {}", table, e);
+                Query::new(sql, None, None, Some(e.to_string()), start.elapsed(), None)
+            }
+        };
+        let _ = event_tx.send(AppEvent::TablePreview(query));
+    });
+}
+
+fn normal_mode_handler(app: &mut App, key: KeyEvent) {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::Context, Mode::Normal, &key)
+    {
+        Some(Action::Quit) => app.state.should_quit = true,
+        Some(
+            tab @ (Action::SelectSqlTab
+            | Action::SelectFlightsqlTab
+            | Action::SelectHistoryTab
+            | Action::SelectLogsTab
+            | Action::SelectContextTab),
+        ) => tab_navigation_handler(app, tab),
+        Some(Action::RefreshMetadata) => load_catalog(app),
+        Some(Action::OpenSettings) => {
+            refresh_settings(app);
+            app.state.context_tab.set_show_settings(true);
+        }
+        Some(Action::InsertName) => {
+            let name = app
+                .state
+                .context_tab
+                .catalog()
+                .as_ref()
+                .and_then(|c| c.selected_node())
+                .and_then(|n| n.name())
+                .map(str::to_string);
+            if let Some(name) = name {
+                app.state.sql_tab.insert_into_editor(&name);
+                app.state.tabs.selected = SelectedTab::SQL;
+            }
+        }
+        Some(Action::PreviewTable) => {
+            let table = app
+                .state
+                .context_tab
+                .catalog()
+                .as_ref()
+                .and_then(|c| c.selected_table())
+                .and_then(|t| t.name())
+                .map(str::to_string);
+            match table {
+                Some(table) => preview_table(app, table),
+                None => info!("No table selected to preview"),
+            }
+        }
+        action => {
+            if let Some(catalog) = app.state.context_tab.catalog_mut() {
+                match action {
+                    Some(Action::SelectNextRow) => catalog.select_next(),
+                    Some(Action::SelectPreviousRow) => catalog.select_previous(),
+                    Some(Action::SelectFirstRow) => catalog.select_first(),
+                    Some(Action::SelectLastRow) => catalog.select_last(),
+                    Some(Action::ToggleExpand) => catalog.toggle_expanded(),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Read the session config into the settings editor
+fn refresh_settings(app: &mut App) {
+    let state = app.execution.session_ctx.state_ref();
+    let state = state.read();
+    app.state
+        .context_tab
+        .settings_mut()
+        .refresh(state.config_options());
+}
+
+/// Set an option of the live session config, as a `SET` statement would
+fn set_setting(app: &mut App, key: String, value: String) {
+    let state = app.execution.session_ctx.state_ref();
+    let mut state = state.write();
+    match state.config_mut().options_mut().set(&key, &value) {
+        Ok(()) => {
+            info!("Set {} to {:?}", key, value);
+            let settings = app.state.context_tab.settings_mut();
+            settings.record_change(key, value);
+            settings.refresh(state.config_options());
+        }
+        Err(e) => error!("Error setting {} to {:?}: {}", key, value, e),
+    }
+}
+
+/// Save the settings changed in the editor to the config file
+fn save_settings(app: &App) {
+    let changes = app.state.context_tab.settings().changes();
+    if changes.is_empty() {
+        info!("No settings have been changed");
+        return;
+    }
+    let Some(path) = app.cli.get_config() else {
+        error!("There is no config file to save settings to");
+        return;
+    };
+    match save_datafusion_options(&path, changes) {
+        Ok(()) => info!("Saved {} settings to {}", changes.len(), path.display()),
+        Err(e) => error!("Error saving settings to {}: {}", path.display(), e),
+    }
+}
+
+fn settings_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::Context, Mode::Settings, &key);
+    let settings = app.state.context_tab.settings_mut();
+    match action {
+        Some(Action::CloseSettings) => app.state.context_tab.set_show_settings(false),
+        Some(Action::SelectNextRow) => settings.select_next(),
+        Some(Action::SelectPreviousRow) => settings.select_previous(),
+        Some(Action::SelectFirstRow) => settings.select_first(),
+        Some(Action::SelectLastRow) => settings.select_last(),
+        Some(Action::FilterSettings) => {
+            let filter = settings.filter().unwrap_or_default().to_string();
+            app.state
+                .context_tab
+                .open_prompt_with_value(PromptKind::FilterSettings, filter);
+        }
+        Some(Action::EditSetting) => {
+            if let Some(setting) = settings.selected_setting() {
+                let value = setting.value.clone().unwrap_or_default();
+                app.state
+                    .context_tab
+                    .open_prompt_with_value(PromptKind::SettingValue, value);
+            }
+        }
+        Some(Action::SaveSettings) => save_settings(app),
+        _ => {}
+    }
+}
+
+fn prompt_handler(app: &mut App, key: KeyEvent) {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::Context, Mode::Prompt, &key)
+    {
+        Some(Action::CancelPrompt) => {
+            app.state.context_tab.close_prompt();
+        }
+        Some(Action::SubmitPrompt) => {
+            let Some(prompt) = app.state.context_tab.close_prompt() else {
+                return;
+            };
+            let settings = app.state.context_tab.settings_mut();
+            match prompt.kind() {
+                PromptKind::FilterSettings => {
+                    let filter = prompt.value().trim().to_string();
+                    settings.set_filter((!filter.is_empty()).then_some(filter));
+                }
+                PromptKind::SettingValue => {
+                    if let Some(setting) = settings.selected_setting() {
+                        let key = setting.key.clone();
+                        set_setting(app, key, prompt.value().trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {
+            if let Some(prompt) = app.state.context_tab.prompt_mut() {
+                prompt.update(key)
+            }
+        }
+    }
+}
+
+pub fn app_event_handler(app: &mut App, event: AppEvent) {
+    match event {
+        AppEvent::Key(key) if app.state.context_tab.prompt().is_some() => prompt_handler(app, key),
+        AppEvent::Key(key) if app.state.context_tab.show_settings() => settings_handler(app, key),
+        AppEvent::Key(key) => normal_mode_handler(app, key),
+        AppEvent::Tick => {}
+        AppEvent::Error => {}
+        _ => {}
+    };
+}
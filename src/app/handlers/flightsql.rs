@@ -15,30 +15,58 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use datafusion::arrow::array::RecordBatch;
-use log::{error, info};
-use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use tokio_stream::StreamExt;
-use tonic::IntoRequest;
+use log::{debug, error, info};
+use ratatui::crossterm::event::KeyEvent;
 
-use crate::app::state::tabs::flightsql::FlightSQLQuery;
-use crate::app::{handlers::tab_navigation_handler, AppEvent};
+use crate::app::execution::{
+    execute_prepared, flightsql_batches, flightsql_metadata, flightsql_primary_keys,
+    prepare_flightsql, FlightSQLProgress, PreparedOutcome,
+};
+use crate::app::state::chart::ChartConfig;
+use crate::app::state::metadata::{flightsql::PrimaryKeys, Metadata};
+use crate::app::state::tabs::flightsql::{FlightSQLQuery, PreparedQuery};
+use crate::app::state::tabs::history::Context;
+use crate::app::{
+    handlers::{
+        copy_results, copy_target, filter_results, flightsql_connection, go_to_row, profile,
+        profile_handler, record_detail_handler, results_navigation_handler, tab_navigation_handler,
+    },
+    keymap::{Action, Mode},
+    state::prompt::PromptKind,
+    AppEvent,
+};
+use crate::ui::SelectedTab;
+#[cfg(feature = "substrait")]
+use {
+    crate::app::{
+        execution::{flightsql_substrait_batches, substrait_plan},
+        handlers::export_substrait,
+    },
+    color_eyre::eyre::eyre,
+};
 
 use super::App;
 
 pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('q') => app.state.should_quit = true,
-        tab @ (KeyCode::Char('1')
-        | KeyCode::Char('2')
-        | KeyCode::Char('3')
-        | KeyCode::Char('4')
-        | KeyCode::Char('5')) => tab_navigation_handler(app, tab),
-        KeyCode::Char('c') => app.state.flightsql_tab.clear_editor(),
-        KeyCode::Char('e') => {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Normal, &key)
+    {
+        Some(Action::Quit) => app.state.should_quit = true,
+        Some(
+            tab @ (Action::SelectSqlTab
+            | Action::SelectFlightsqlTab
+            | Action::SelectHistoryTab
+            | Action::SelectLogsTab
+            | Action::SelectContextTab),
+        ) => tab_navigation_handler(app, tab),
+        Some(Action::ClearEditor) => app.state.flightsql_tab.clear_editor(),
+        Some(Action::Edit) => {
             info!("Handling");
             let editor = app.state.flightsql_tab.editor();
             let lines = editor.lines();
@@ -51,84 +79,129 @@ pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
             }
             app.state.flightsql_tab.edit();
         }
-        KeyCode::Down => {
-            if let Some(s) = app.state.flightsql_tab.query_results_state() {
-                info!("Select next");
-                let mut s = s.borrow_mut();
-                s.select_next();
+        Some(
+            action @ (Action::SelectNextRow
+            | Action::SelectPreviousRow
+            | Action::SelectFirstRow
+            | Action::SelectLastRow
+            | Action::PageDown
+            | Action::PageUp
+            | Action::SelectNextColumn
+            | Action::SelectPreviousColumn
+            | Action::FreezeColumns),
+        ) => results_navigation_handler(
+            app.state.flightsql_tab.query_results_state().as_ref(),
+            action,
+        ),
+        Some(Action::GoToRow) => app.state.flightsql_tab.open_prompt(PromptKind::GoToRow),
+        Some(Action::SortColumn) => {
+            if let Err(e) = app
+                .state
+                .flightsql_tab
+                .sort_by_selected_column(&app.state.config.display)
+            {
+                info!("Unable to sort results: {}", e)
             }
         }
-        KeyCode::Up => {
-            if let Some(s) = app.state.flightsql_tab.query_results_state() {
-                info!("Select previous");
-                let mut s = s.borrow_mut();
-                s.select_previous();
+        Some(Action::FilterResults) => app.state.flightsql_tab.open_prompt(PromptKind::Filter),
+        Some(Action::Search) => app.state.flightsql_tab.open_prompt(PromptKind::Search),
+        Some(action @ (Action::NextMatch | Action::PreviousMatch)) => {
+            let forward = action == Action::NextMatch;
+            if let Err(e) = app
+                .state
+                .flightsql_tab
+                .find_next_match(forward, &app.state.config.display)
+            {
+                info!("Unable to search results: {}", e)
             }
         }
+        Some(action @ (Action::CopyCell | Action::CopyRow | Action::CopyResults)) => copy_results(
+            app,
+            app.state.flightsql_tab.displayed_results(),
+            app.state.flightsql_tab.query_results_state(),
+            copy_target(action),
+        ),
+        Some(Action::NextCopyFormat) => app.state.copy_format = app.state.copy_format.next(),
+        Some(Action::ToggleChart) if app.state.flightsql_tab.chart().is_some() => {
+            app.state.flightsql_tab.toggle_chart()
+        }
+        Some(Action::ToggleChart | Action::EditChart) => {
+            let chart = app.state.flightsql_tab.chart_suggestion();
+            app.state
+                .flightsql_tab
+                .open_prompt_with_value(PromptKind::Chart, chart)
+        }
+        Some(Action::OpenDetail) => {
+            if let Err(e) = app
+                .state
+                .flightsql_tab
+                .open_detail(&app.state.config.display)
+            {
+                info!("Unable to show row: {}", e)
+            }
+        }
+        Some(Action::ProfileResults) => profile(
+            app,
+            SelectedTab::FlightSQL,
+            None,
+            app.state.flightsql_tab.displayed_results(),
+        ),
 
-        KeyCode::Enter => {
+        Some(Action::OpenConnections) => app.state.flightsql_tab.open_connection_picker(),
+        Some(Action::OpenMetadata) => {
+            app.state.flightsql_tab.set_show_metadata(true);
+            if app.state.flightsql_tab.metadata().is_none() {
+                load_metadata(app);
+            }
+        }
+        Some(Action::PrepareQuery) => prepare_query(app),
+        #[cfg(feature = "substrait")]
+        Some(Action::ExportSubstrait) => app
+            .state
+            .flightsql_tab
+            .open_prompt_with_value(PromptKind::ExportSubstrait, "query.substrait".to_string()),
+        #[cfg(feature = "substrait")]
+        Some(Action::RunSubstraitFile) => app
+            .state
+            .flightsql_tab
+            .open_prompt(PromptKind::RunSubstrait),
+        #[cfg(feature = "substrait")]
+        Some(Action::RunSubstrait) => run_substrait(app, None),
+        Some(Action::RunQuery) => {
             info!("Run FS query");
             let sql = app.state.flightsql_tab.editor().lines().join("");
             info!("SQL: {}", sql);
             let client = Arc::clone(&app.execution.flightsql_client);
-            let _event_tx = app.app_event_tx.clone();
+            let config = flightsql_connection(app);
+            let event_tx = app.app_event_tx.clone();
+            app.state
+                .flightsql_tab
+                .set_progress(Some(FlightSQLProgress::default()));
             tokio::spawn(async move {
                 let mut query =
                     FlightSQLQuery::new(sql.clone(), None, None, None, Duration::default(), None);
+                query.set_connection(Some(config.name.clone()));
                 let start = Instant::now();
-                if let Some(ref mut c) = *client.lock().await {
-                    info!("Sending query");
-                    match c.execute(sql, None).await {
-                        Ok(flight_info) => {
-                            for endpoint in flight_info.endpoint {
-                                if let Some(ticket) = endpoint.ticket {
-                                    match c.do_get(ticket.into_request()).await {
-                                        Ok(mut stream) => {
-                                            let mut batches: Vec<RecordBatch> = Vec::new();
-                                            while let Some(maybe_batch) = stream.next().await {
-                                                match maybe_batch {
-                                                    Ok(batch) => {
-                                                        info!("Batch rows: {}", batch.num_rows());
-                                                        batches.push(batch);
-                                                        break;
-                                                    }
-                                                    Err(e) => {
-                                                        error!("Error getting batch: {:?}", e);
-                                                        let elapsed = start.elapsed();
-                                                        query.set_error(Some(e.to_string()));
-                                                        query.set_execution_time(elapsed);
-                                                        break;
-                                                    }
-                                                }
-                                            }
-
-                                            let elapsed = start.elapsed();
-                                            let rows: usize =
-                                                batches.iter().map(|r| r.num_rows()).sum();
-                                            query.set_results(Some(batches));
-                                            query.set_num_rows(Some(rows));
-                                            query.set_execution_time(elapsed);
-                                        }
-                                        Err(e) => {
-                                            error!("Error getting response: {:?}", e);
-                                            let elapsed = start.elapsed();
-                                            query.set_error(Some(e.to_string()));
-                                            query.set_execution_time(elapsed);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error getting response: {:?}", e);
-                            let elapsed = start.elapsed();
-                            query.set_error(Some(e.to_string()));
-                            query.set_execution_time(elapsed);
-                        }
+                let progress_tx = event_tx.clone();
+                let fetched = flightsql_batches(&client, &config, &sql, |progress| {
+                    let _ = progress_tx.send(AppEvent::FlightSQLProgress(progress));
+                })
+                .await;
+                match fetched {
+                    Ok((batches, stats)) => {
+                        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                        info!("Received {} rows in {} batches", rows, batches.len());
+                        query.set_results(Some(batches));
+                        query.set_num_rows(Some(rows));
+                        query.set_flightsql_stats(Some(stats));
+                    }
+                    Err(e) => {
+                        error!("Error running FlightSQL query: {:?}", e);
+                        query.set_error(Some(e.to_string()));
                     }
                 }
-
-                let _ = _event_tx.send(AppEvent::FlightSQLQueryResult(query));
+                query.set_execution_time(start.elapsed());
+                let _ = event_tx.send(AppEvent::FlightSQLQueryResult(query));
             });
         }
         _ => {}
@@ -136,23 +209,410 @@ pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
 }
 
 pub fn editable_handler(app: &mut App, key: KeyEvent) {
-    match (key.code, key.modifiers) {
-        (KeyCode::Left, KeyModifiers::ALT) => app.state.flightsql_tab.previous_word(),
-        (KeyCode::Right, KeyModifiers::ALT) => app.state.flightsql_tab.next_word(),
-        (KeyCode::Backspace, KeyModifiers::ALT) => app.state.flightsql_tab.delete_word(),
-        (KeyCode::Esc, _) => app.state.flightsql_tab.exit_edit(),
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Edit, &key)
+    {
+        Some(Action::PreviousWord) => app.state.flightsql_tab.previous_word(),
+        Some(Action::NextWord) => app.state.flightsql_tab.next_word(),
+        Some(Action::DeleteWord) => app.state.flightsql_tab.delete_word(),
+        Some(Action::ExitEdit) => app.state.flightsql_tab.exit_edit(),
         _ => app.state.flightsql_tab.update_editor_content(key),
     }
 }
 
+pub fn prompt_handler(app: &mut App, key: KeyEvent) {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Prompt, &key)
+    {
+        Some(Action::CancelPrompt) => {
+            app.state.flightsql_tab.close_prompt();
+        }
+        Some(Action::SubmitPrompt) => {
+            if let Some(prompt) = app.state.flightsql_tab.close_prompt() {
+                match prompt.kind() {
+                    PromptKind::GoToRow => go_to_row(
+                        app.state.flightsql_tab.query_results_state(),
+                        &prompt.value(),
+                    ),
+                    PromptKind::Filter => {
+                        let filter = prompt.value();
+                        if filter.trim().is_empty() {
+                            if let Err(e) = app
+                                .state
+                                .flightsql_tab
+                                .set_filter(None, &app.state.config.display)
+                            {
+                                info!("Unable to clear filter: {}", e)
+                            }
+                        } else if let Some(q) = app.state.flightsql_tab.query() {
+                            let results = q.results().clone().unwrap_or_default();
+                            filter_results(
+                                app,
+                                SelectedTab::FlightSQL,
+                                q.sql().clone(),
+                                results,
+                                filter,
+                            );
+                        }
+                    }
+                    PromptKind::Search => {
+                        let term = prompt.value();
+                        let searching = !term.is_empty();
+                        if let Some(s) = app.state.flightsql_tab.query_results_state() {
+                            s.borrow_mut().set_search(searching.then_some(term));
+                        }
+                        if searching {
+                            if let Err(e) = app
+                                .state
+                                .flightsql_tab
+                                .find_next_match(true, &app.state.config.display)
+                            {
+                                info!("Unable to search results: {}", e)
+                            }
+                        }
+                    }
+                    PromptKind::Chart => {
+                        let input = prompt.value();
+                        let chart = match input.trim() {
+                            "" => Ok(None),
+                            chart => ChartConfig::from_str(chart).map(Some),
+                        };
+                        match chart {
+                            Ok(chart) => {
+                                if let Some(q) = app.state.flightsql_tab.query() {
+                                    app.state.history_tab.set_chart(
+                                        Context::FlightSQL,
+                                        q.sql(),
+                                        chart.clone(),
+                                    );
+                                }
+                                app.state
+                                    .flightsql_tab
+                                    .set_chart(chart, &app.state.config.display);
+                            }
+                            Err(e) => error!("Invalid chart: {}", e),
+                        }
+                    }
+                    PromptKind::Parameters => {
+                        let values = prompt.value();
+                        app.state.flightsql_tab.set_prepared_values(values.clone());
+                        run_prepared(app, values)
+                    }
+                    #[cfg(feature = "substrait")]
+                    PromptKind::ExportSubstrait => {
+                        let sql = app.state.flightsql_tab.editor().lines().join("");
+                        export_substrait(app, sql, prompt.value().trim().to_string())
+                    }
+                    #[cfg(feature = "substrait")]
+                    PromptKind::RunSubstrait => {
+                        run_substrait(app, Some(prompt.value().trim().to_string()))
+                    }
+                    // Tables are only profiled through the local session context
+                    PromptKind::ProfileTable | PromptKind::Diff | PromptKind::ExportTrace => {}
+                    // Only the Context tab edits the session config, and only the functions of
+                    // the local session are browsed
+                    PromptKind::FilterSettings
+                    | PromptKind::SettingValue
+                    | PromptKind::SearchFunctions => {}
+                }
+            }
+        }
+        _ => {
+            if let Some(prompt) = app.state.flightsql_tab.prompt_mut() {
+                prompt.update(key)
+            }
+        }
+    }
+}
+
+pub fn detail_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Detail, &key);
+    record_detail_handler(app.state.flightsql_tab.detail_mut(), action);
+}
+
+pub fn profile_view_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Profile, &key);
+    profile_handler(app.state.flightsql_tab.profile_mut(), action);
+}
+
+/// Prepare the statement in the editor. Once it is prepared its parameters are prompted for,
+/// see [`run_prepared`].
+fn prepare_query(app: &mut App) {
+    let sql = app.state.flightsql_tab.editor().lines().join("");
+    info!("Preparing FlightSQL statement: {}", sql);
+    let client = Arc::clone(&app.execution.flightsql_client);
+    let prepared = Arc::clone(&app.execution.flightsql_prepared);
+    let connection = flightsql_connection(app).name;
+    let event_tx = app.app_event_tx.clone();
+    tokio::spawn(async move {
+        match prepare_flightsql(&client, &prepared, &sql).await {
+            Ok(schema) => {
+                let parameters = schema
+                    .fields()
+                    .iter()
+                    .map(|f| (f.name().clone(), f.data_type().to_string()))
+                    .collect();
+                let prepared = PreparedQuery {
+                    sql,
+                    parameters,
+                    values: String::new(),
+                };
+                let _ = event_tx.send(AppEvent::FlightSQLPrepared(prepared));
+            }
+            Err(e) => {
+                error!("Error preparing FlightSQL statement: {:?}", e);
+                let mut query = FlightSQLQuery::new(
+                    sql,
+                    None,
+                    None,
+                    Some(e.to_string()),
+                    Duration::default(),
+                    None,
+                );
+                query.set_connection(Some(connection));
+                let _ = event_tx.send(AppEvent::FlightSQLQueryResult(query));
+            }
+        }
+    });
+}
+
+/// Run the prepared statement with `values` bound to its parameters
+pub fn run_prepared(app: &mut App, values: String) {
+    let Some(sql) = app
+        .state
+        .flightsql_tab
+        .prepared()
+        .as_ref()
+        .map(|p| p.sql.clone())
+    else {
+        return;
+    };
+    let client = Arc::clone(&app.execution.flightsql_client);
+    let prepared = Arc::clone(&app.execution.flightsql_prepared);
+    let config = flightsql_connection(app);
+    let event_tx = app.app_event_tx.clone();
+    app.state
+        .flightsql_tab
+        .set_progress(Some(FlightSQLProgress::default()));
+    tokio::spawn(async move {
+        let mut query = FlightSQLQuery::new(sql, None, None, None, Duration::default(), None);
+        query.set_connection(Some(config.name.clone()));
+        let start = Instant::now();
+        let progress_tx = event_tx.clone();
+        let executed = execute_prepared(&client, &prepared, &config, &values, |progress| {
+            let _ = progress_tx.send(AppEvent::FlightSQLProgress(progress));
+        })
+        .await;
+        match executed {
+            Ok(PreparedOutcome::Batches(batches, stats)) => {
+                let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                info!("Received {} rows in {} batches", rows, batches.len());
+                query.set_results(Some(batches));
+                query.set_num_rows(Some(rows));
+                query.set_flightsql_stats(Some(stats));
+            }
+            Ok(PreparedOutcome::Updated(rows)) => {
+                info!("Prepared statement affected {} rows", rows);
+                query.set_affected_rows(Some(rows));
+            }
+            Err(e) => {
+                error!("Error running prepared statement: {:?}", e);
+                query.set_error(Some(e.to_string()));
+            }
+        }
+        query.set_execution_time(start.elapsed());
+        let _ = event_tx.send(AppEvent::FlightSQLQueryResult(query));
+    });
+}
+
+/// Run a Substrait plan on the server, either the plan saved at `path` or the plan of the
+/// editor's query. The editor's query is planned by the local session context, so the tables it
+/// uses must be registered locally, for example as FlightSQL tables.
+#[cfg(feature = "substrait")]
+fn run_substrait(app: &mut App, path: Option<String>) {
+    let sql = app.state.flightsql_tab.editor().lines().join("");
+    let ctx = app.execution.session_ctx.clone();
+    let client = Arc::clone(&app.execution.flightsql_client);
+    let config = flightsql_connection(app);
+    let event_tx = app.app_event_tx.clone();
+    app.state
+        .flightsql_tab
+        .set_progress(Some(FlightSQLProgress::default()));
+    tokio::spawn(async move {
+        let label = match &path {
+            Some(path) => format!("-- Substrait plan {}", path),
+            None => sql.clone(),
+        };
+        let mut query = FlightSQLQuery::new(label, None, None, None, Duration::default(), None);
+        query.set_connection(Some(config.name.clone()));
+        let start = Instant::now();
+        let progress_tx = event_tx.clone();
+        let fetched = async {
+            let plan = match &path {
+                Some(path) => std::fs::read(path)
+                    .map_err(|e| eyre!("Unable to read Substrait plan from {}: {}", path, e))?,
+                None => substrait_plan(&ctx, &sql).await?,
+            };
+            flightsql_substrait_batches(&client, &config, plan, |progress| {
+                let _ = progress_tx.send(AppEvent::FlightSQLProgress(progress));
+            })
+            .await
+        };
+        match fetched.await {
+            Ok((batches, stats)) => {
+                let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                info!("Received {} rows in {} batches", rows, batches.len());
+                query.set_results(Some(batches));
+                query.set_num_rows(Some(rows));
+                query.set_flightsql_stats(Some(stats));
+            }
+            Err(e) => {
+                error!("Error running Substrait plan: {:?}", e);
+                query.set_error(Some(e.to_string()));
+            }
+        }
+        query.set_execution_time(start.elapsed());
+        let _ = event_tx.send(AppEvent::FlightSQLQueryResult(query));
+    });
+}
+
+/// Fetch the objects on the server for the metadata browser
+fn load_metadata(app: &mut App) {
+    let client = Arc::clone(&app.execution.flightsql_client);
+    let config = flightsql_connection(app);
+    let display_config = app.state.config.display.clone();
+    let event_tx = app.app_event_tx.clone();
+    app.state
+        .flightsql_tab
+        .set_metadata(Some(Metadata::message("Loading metadata...")));
+    tokio::spawn(async move {
+        let metadata = flightsql_metadata(&client, &config)
+            .await
+            .and_then(|batches| Metadata::try_new(&batches, &display_config));
+        let metadata = metadata.unwrap_or_else(|e| {
+            error!("Error loading FlightSQL metadata: {:?}", e);
+            Metadata::message(format!("Unable to load metadata: {}", e))
+        });
+        let _ = event_tx.send(AppEvent::FlightSQLMetadata(metadata));
+    });
+}
+
+/// Request the primary keys of the tables that have been expanded since they were last requested
+fn load_primary_keys(app: &mut App) {
+    let Some(metadata) = app.state.flightsql_tab.metadata_mut() else {
+        return;
+    };
+    for table in metadata.take_pending_keys() {
+        let client = Arc::clone(&app.execution.flightsql_client);
+        let config = flightsql_connection(app);
+        let event_tx = app.app_event_tx.clone();
+        tokio::spawn(async move {
+            match flightsql_primary_keys(&client, &config, &table).await {
+                Ok(batches) => {
+                    let keys = PrimaryKeys { table, batches };
+                    let _ = event_tx.send(AppEvent::FlightSQLPrimaryKeys(keys));
+                }
+                Err(e) => error!("Error loading FlightSQL primary keys: {:?}", e),
+            }
+        });
+    }
+}
+
+pub fn metadata_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Metadata, &key);
+    match action {
+        Some(Action::CloseMetadata) => app.state.flightsql_tab.set_show_metadata(false),
+        Some(Action::RefreshMetadata) => load_metadata(app),
+        Some(Action::InsertName) => {
+            let name = app
+                .state
+                .flightsql_tab
+                .metadata()
+                .as_ref()
+                .and_then(|m| m.selected_node())
+                .and_then(|n| n.name())
+                .map(str::to_string);
+            if let Some(name) = name {
+                app.state.flightsql_tab.insert_into_editor(&name);
+                app.state.flightsql_tab.set_show_metadata(false);
+            }
+        }
+        _ => {
+            if let Some(metadata) = app.state.flightsql_tab.metadata_mut() {
+                match action {
+                    Some(Action::SelectNextRow) => metadata.select_next(),
+                    Some(Action::SelectPreviousRow) => metadata.select_previous(),
+                    Some(Action::SelectFirstRow) => metadata.select_first(),
+                    Some(Action::SelectLastRow) => metadata.select_last(),
+                    Some(Action::ToggleExpand) => {
+                        metadata.toggle_expanded();
+                        load_primary_keys(app);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+pub fn connections_handler(app: &mut App, key: KeyEvent) {
+    let count = app.state.config.flightsql.connections().len();
+    let tab = &mut app.state.flightsql_tab;
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::FlightSQL, Mode::Connections, &key)
+    {
+        Some(Action::SelectNextRow) => tab.move_connection_picker(1, count),
+        Some(Action::SelectPreviousRow) => tab.move_connection_picker(-1, count),
+        Some(Action::CloseConnections) => tab.close_connection_picker(),
+        // Connecting to the current connection again reconnects to it
+        Some(Action::Connect) => {
+            if let Some(index) = tab.connection_picker() {
+                tab.close_connection_picker();
+                let _ = app
+                    .app_event_tx
+                    .send(AppEvent::EstablishFlightSQLConnection(index));
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn app_event_handler(app: &mut App, event: AppEvent) {
     match event {
+        AppEvent::Key(key) if app.state.flightsql_tab.prompt().is_some() => {
+            prompt_handler(app, key)
+        }
+        AppEvent::Key(key) if app.state.flightsql_tab.detail().is_some() => {
+            detail_handler(app, key)
+        }
+        AppEvent::Key(key) if app.state.flightsql_tab.profile().is_some() => {
+            profile_view_handler(app, key)
+        }
+        AppEvent::Key(key) if app.state.flightsql_tab.connection_picker().is_some() => {
+            connections_handler(app, key)
+        }
+        AppEvent::Key(key) if app.state.flightsql_tab.show_metadata() => metadata_handler(app, key),
         AppEvent::Key(key) => match app.state.flightsql_tab.editor_editable() {
             true => editable_handler(app, key),
             false => normal_mode_handler(app, key),
         },
         AppEvent::FlightSQLQueryResult(r) => {
-            info!("Query results: {:?}", r);
+            debug!("Query results: {:?}", r);
             app.state.flightsql_tab.set_query(r);
             app.state.flightsql_tab.refresh_query_results_state();
         }
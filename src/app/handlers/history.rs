@@ -15,36 +15,53 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use ratatui::crossterm::event::KeyCode;
-
+use crate::app::keymap::{Action, Mode};
+use crate::app::state::prompt::PromptKind;
 use crate::app::{handlers::tab_navigation_handler, AppEvent};
+use crate::ui::SelectedTab;
 
 use super::App;
 
 pub fn app_event_handler(app: &mut App, event: AppEvent) {
     match event {
-        AppEvent::Key(key) => match key.code {
-            KeyCode::Char('q') => app.state.should_quit = true,
-            tab @ (KeyCode::Char('1')
-            | KeyCode::Char('2')
-            | KeyCode::Char('3')
-            | KeyCode::Char('4')
-            | KeyCode::Char('5')) => tab_navigation_handler(app, tab),
-
-            KeyCode::Down => {
-                if let Some(s) = app.state.history_tab.history_table_state() {
-                    let mut s = s.borrow_mut();
-                    s.select_next();
+        AppEvent::Key(key) => {
+            match app
+                .state
+                .keymap
+                .action(SelectedTab::History, Mode::Normal, &key)
+            {
+                Some(Action::Quit) => app.state.should_quit = true,
+                Some(
+                    tab @ (Action::SelectSqlTab
+                    | Action::SelectFlightsqlTab
+                    | Action::SelectHistoryTab
+                    | Action::SelectLogsTab
+                    | Action::SelectContextTab),
+                ) => tab_navigation_handler(app, tab),
+                Some(Action::SelectNextRow) => {
+                    if let Some(s) = app.state.history_tab.history_table_state() {
+                        let mut s = s.borrow_mut();
+                        s.select_next();
+                    }
                 }
-            }
-            KeyCode::Up => {
-                if let Some(s) = app.state.history_tab.history_table_state() {
-                    let mut s = s.borrow_mut();
-                    s.select_previous();
+                Some(Action::SelectPreviousRow) => {
+                    if let Some(s) = app.state.history_tab.history_table_state() {
+                        let mut s = s.borrow_mut();
+                        s.select_previous();
+                    }
+                }
+                Some(Action::MarkDiff) => app.state.history_tab.toggle_diff_mark(),
+                // Diffs are entered and shown on the SQL tab
+                Some(Action::Diff) => {
+                    let diff = app.state.history_tab.diff_suggestion();
+                    app.state
+                        .sql_tab
+                        .open_prompt_with_value(PromptKind::Diff, diff);
+                    app.state.tabs.selected = SelectedTab::SQL;
                 }
+                _ => {}
             }
-            _ => {}
-        },
+        }
         AppEvent::Tick => {}
         AppEvent::Error => {}
         _ => {}
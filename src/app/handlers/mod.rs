@@ -14,27 +14,59 @@
 // KIND, either express or implied.  See the License for the
 // specific language governing permissions and limitations
 // under the License.
+pub mod context;
 #[cfg(feature = "flightsql")]
 pub mod flightsql;
 pub mod history;
 pub mod sql;
 
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::SessionContext;
 use log::{error, info, trace};
-use ratatui::crossterm::event::{self, KeyCode, KeyEvent};
+use ratatui::crossterm::event::{self, KeyEvent};
 use tui_logger::TuiWidgetEvent;
 
 use crate::state::tabs::history::Context;
 
 #[cfg(feature = "flightsql")]
 use arrow_flight::sql::client::FlightSqlServiceClient;
+#[cfg(feature = "substrait")]
+use std::path::Path;
 #[cfg(feature = "flightsql")]
-use std::sync::Arc;
+use tokio::sync::Mutex;
 #[cfg(feature = "flightsql")]
 use tonic::transport::Channel;
 
+#[cfg(feature = "substrait")]
+use crate::app::execution::export_substrait as export_substrait_plan;
+#[cfg(feature = "flightsql")]
+use crate::app::{
+    config::FlightSQLConnectionConfig,
+    execution::{close_prepared, connect_flightsql, flightsql_batches, FlightSQLConnection},
+    state::{prompt::PromptKind, tabs::flightsql::ConnectionStatus},
+};
+
 use crate::{
-    app::{state::tabs::history::HistoryQuery, AppEvent},
+    app::{
+        clipboard::{copy_text, copy_to_clipboard, CopyTarget},
+        keymap::{Action, Mode},
+        state::{
+            detail::RecordDetail,
+            diff::{diff_frames, DiffInput, DiffResults, DiffSpec},
+            profile::{profile_columns, Profile, ProfileResults},
+            results::ResultsState,
+            tabs::{history::HistoryQuery, sql::Query},
+            view::FilteredResults,
+        },
+        AppEvent,
+    },
     ui::SelectedTab,
 };
 
@@ -53,99 +85,388 @@ pub fn crossterm_event_handler(event: event::Event) -> Option<AppEvent> {
     }
 }
 
-fn tab_navigation_handler(app: &mut App, key: KeyCode) {
-    #[cfg(feature = "flightsql")]
-    match key {
-        KeyCode::Char('1') => app.state.tabs.selected = SelectedTab::SQL,
-        KeyCode::Char('2') => app.state.tabs.selected = SelectedTab::FlightSQL,
-        KeyCode::Char('3') => app.state.tabs.selected = SelectedTab::History,
-        KeyCode::Char('4') => app.state.tabs.selected = SelectedTab::Logs,
-        KeyCode::Char('5') => app.state.tabs.selected = SelectedTab::Context,
-        _ => {}
-    };
-    #[cfg(not(feature = "flightsql"))]
-    match key {
-        KeyCode::Char('1') => app.state.tabs.selected = SelectedTab::SQL,
-        KeyCode::Char('2') => app.state.tabs.selected = SelectedTab::History,
-        KeyCode::Char('3') => app.state.tabs.selected = SelectedTab::Logs,
-        KeyCode::Char('4') => app.state.tabs.selected = SelectedTab::Context,
-        _ => {}
-    };
-}
+/// Lines the full value in the record detail view scrolls by on page up and down
+const DETAIL_SCROLL_LINES: u16 = 5;
 
-fn logs_tab_key_event_handler(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('q') => app.state.should_quit = true,
-        tab @ (KeyCode::Char('1')
-        | KeyCode::Char('2')
-        | KeyCode::Char('3')
-        | KeyCode::Char('4')
-        | KeyCode::Char('5')) => tab_navigation_handler(app, tab),
-        KeyCode::Char('f') => {
-            app.state.logs_tab.transition(TuiWidgetEvent::FocusKey);
+fn tab_navigation_handler(app: &mut App, action: Action) {
+    if let Some(tab) = action.selected_tab() {
+        app.state.tabs.selected = tab;
+        // Tables may have been created or dropped since the catalog was last loaded
+        if tab == SelectedTab::Context {
+            context::load_catalog(app);
         }
-        KeyCode::Char('h') => {
-            app.state.logs_tab.transition(TuiWidgetEvent::HideKey);
+    }
+}
+
+/// Navigation of the results tables shared by the SQL and FlightSQL tabs
+fn results_navigation_handler(results: Option<&RefCell<ResultsState>>, action: Action) {
+    if let Some(s) = results {
+        let mut s = s.borrow_mut();
+        match action {
+            Action::SelectNextRow => s.select_next(),
+            Action::SelectPreviousRow => s.select_previous(),
+            Action::SelectFirstRow => s.select_first(),
+            Action::SelectLastRow => s.select_last(),
+            Action::PageDown => s.page_down(),
+            Action::PageUp => s.page_up(),
+            Action::SelectNextColumn => s.select_next_column(),
+            Action::SelectPreviousColumn => s.select_previous_column(),
+            Action::FreezeColumns => s.toggle_freeze_columns(),
+            _ => {}
         }
-        KeyCode::Char('+') => {
-            app.state.logs_tab.transition(TuiWidgetEvent::PlusKey);
+    }
+}
+
+/// Navigation of the record detail view shared by the SQL and FlightSQL tabs
+fn record_detail_handler(detail: &mut Option<RecordDetail>, action: Option<Action>) {
+    if let Some(Action::CloseDetail) = action {
+        *detail = None;
+        return;
+    }
+    if let (Some(d), Some(action)) = (detail, action) {
+        match action {
+            Action::SelectNextRow => d.select_next(),
+            Action::SelectPreviousRow => d.select_previous(),
+            Action::SelectFirstRow => d.select_first(),
+            Action::SelectLastRow => d.select_last(),
+            Action::ToggleExpand => d.toggle_expanded(),
+            Action::PageDown => d.scroll_value_down(DETAIL_SCROLL_LINES),
+            Action::PageUp => d.scroll_value_up(DETAIL_SCROLL_LINES),
+            _ => {}
         }
-        KeyCode::Char('-') => {
-            app.state.logs_tab.transition(TuiWidgetEvent::MinusKey);
+    }
+}
+
+/// Run `filter` as a `WHERE` expression over the query results of `tab`, sending the matching
+/// rows back once done. The results are scanned as an unregistered `MemTable` so that nothing is
+/// added to the session's catalog and no query is sent to the original source.
+fn filter_results(
+    app: &App,
+    tab: SelectedTab,
+    sql: String,
+    results: Vec<RecordBatch>,
+    filter: String,
+) {
+    let Some(schema) = results.first().map(|b| b.schema()) else {
+        info!("No results to filter");
+        return;
+    };
+    let ctx = app.execution.session_ctx.clone();
+    let event_tx = app.app_event_tx.clone();
+    tokio::spawn(async move {
+        let trimmed = filter.trim();
+        let predicate = match trimmed.get(..6) {
+            Some(keyword) if keyword.eq_ignore_ascii_case("where ") => &trimmed[6..],
+            _ => trimmed,
+        };
+        let filtered = async {
+            let table = MemTable::try_new(schema, vec![results])?;
+            let df = ctx.read_table(Arc::new(table))?;
+            let expr = df.parse_sql_expr(predicate)?;
+            df.filter(expr)?.collect().await
+        };
+        match filtered.await {
+            Ok(batches) => {
+                let filtered = FilteredResults {
+                    tab,
+                    sql,
+                    filter: predicate.to_string(),
+                    batches,
+                };
+                let _ = event_tx.send(AppEvent::FilteredResults(filtered));
+            }
+            Err(e) => error!("Error filtering results with {:?}: {}", predicate, e),
         }
-        KeyCode::Char(' ') => {
-            app.state.logs_tab.transition(TuiWidgetEvent::SpaceKey);
+    });
+}
+
+/// Profile the columns of `table`, or of the results shown on `tab` when no table is given,
+/// sending the profile back once done. Statistics are computed with aggregate queries, so
+/// profiling a table scans it, without reading it into memory. The most common values and
+/// histograms of a table are computed from its first `sample_rows` rows.
+fn profile(app: &App, tab: SelectedTab, table: Option<String>, results: Option<&[RecordBatch]>) {
+    let results = match (&table, results.unwrap_or_default()) {
+        (Some(_), _) => Vec::new(),
+        (None, []) => {
+            info!("No results to profile");
+            return;
         }
-        KeyCode::Esc => {
-            app.state.logs_tab.transition(TuiWidgetEvent::EscapeKey);
+        (None, batches) => batches.to_vec(),
+    };
+    let ctx = app.execution.session_ctx.clone();
+    let config = app.state.config.interaction.profile.clone();
+    let display = app.state.config.display.clone();
+    let event_tx = app.app_event_tx.clone();
+    let source = table.clone().unwrap_or_else(|| "results".to_string());
+    // The results are already in memory, so are read whole
+    let sample_rows = match (&table, config.sample_rows) {
+        (Some(_), sample_rows) if sample_rows > 0 => Some(sample_rows),
+        _ => None,
+    };
+    info!("Profiling {}", source);
+    tokio::spawn(async move {
+        let profiled = async {
+            let df = match &table {
+                Some(table) => ctx.table(table.as_str()).await?,
+                None => {
+                    let schema = results[0].schema();
+                    let results = MemTable::try_new(schema, vec![results])?;
+                    ctx.read_table(Arc::new(results))?
+                }
+            };
+            profile_columns(df, sample_rows, &config, &display).await
+        };
+        match profiled.await {
+            Ok((rows, batch)) => {
+                let profile = ProfileResults {
+                    tab,
+                    table,
+                    rows,
+                    sampled: sample_rows.filter(|&sample_rows| sample_rows < rows),
+                    batch,
+                };
+                let _ = event_tx.send(AppEvent::ProfileResults(profile));
+            }
+            Err(e) => error!("Error profiling {}: {}", source, e),
         }
-        KeyCode::Down => {
-            app.state.logs_tab.transition(TuiWidgetEvent::DownKey);
+    });
+}
+
+/// Save the Substrait plan of `sql`, as planned by the local session context, to `path`
+#[cfg(feature = "substrait")]
+fn export_substrait(app: &App, sql: String, path: String) {
+    let ctx = app.execution.session_ctx.clone();
+    tokio::spawn(async move {
+        match export_substrait_plan(&ctx, &sql, Path::new(&path)).await {
+            Ok(()) => info!("Saved Substrait plan to {}", path),
+            Err(e) => error!("Error saving Substrait plan: {}", e),
         }
-        KeyCode::Up => {
-            app.state.logs_tab.transition(TuiWidgetEvent::UpKey);
+    });
+}
+
+/// The configuration of the selected FlightSQL connection
+#[cfg(feature = "flightsql")]
+pub fn flightsql_connection(app: &App) -> FlightSQLConnectionConfig {
+    let connections = app.state.config.flightsql.connections();
+    connections
+        .get(app.state.flightsql_tab.connection())
+        .map(|c| (*c).clone())
+        .unwrap_or_default()
+}
+
+/// The configuration of the FlightSQL connection a diff input was run on, or of the selected
+/// connection when it wasn't recorded
+#[cfg(feature = "flightsql")]
+fn diff_connection(app: &App, input: &DiffInput) -> Result<FlightSQLConnectionConfig> {
+    match input {
+        DiffInput::Query(Context::FlightSQL, _, Some(name)) => app
+            .state
+            .config
+            .flightsql
+            .connections()
+            .into_iter()
+            .find(|c| &c.name == name)
+            .cloned()
+            .ok_or_else(|| eyre!("The FlightSQL connection '{}' no longer exists", name)),
+        _ => Ok(flightsql_connection(app)),
+    }
+}
+
+/// Read one side of a diff. History entries are run again in the context they were run in,
+/// with the batches of FlightSQL queries read into memory so they can be compared locally.
+/// FlightSQL queries run on a connection other than the `selected` one connect to it first.
+async fn read_diff_input(
+    ctx: &SessionContext,
+    #[cfg(feature = "flightsql")] client: &Mutex<Option<FlightSqlServiceClient<Channel>>>,
+    #[cfg(feature = "flightsql")] selected: &str,
+    #[cfg(feature = "flightsql")] config: &FlightSQLConnectionConfig,
+    input: DiffInput,
+) -> Result<DataFrame> {
+    match input {
+        DiffInput::Table(table) => Ok(ctx.table(table.as_str()).await?),
+        DiffInput::Query(Context::Local, sql, _) => Ok(ctx.sql(&sql).await?),
+        #[cfg(feature = "flightsql")]
+        DiffInput::Query(Context::FlightSQL, sql, _) => {
+            let (batches, _) = if config.name == selected {
+                flightsql_batches(client, config, &sql, |_| {}).await?
+            } else {
+                info!("Connecting to {} to diff {}", config.name, sql);
+                let other = Mutex::new(Some(connect_flightsql(config).await?));
+                flightsql_batches(&other, config, &sql, |_| {}).await?
+            };
+            let schema = batches
+                .first()
+                .map(|b| b.schema())
+                .ok_or_else(|| eyre!("FlightSQL query returned no results: {}", sql))?;
+            let results = MemTable::try_new(schema, vec![batches])?;
+            Ok(ctx.read_table(Arc::new(results))?)
         }
-        KeyCode::Right => {
-            app.state.logs_tab.transition(TuiWidgetEvent::RightKey);
+        #[cfg(not(feature = "flightsql"))]
+        DiffInput::Query(Context::FlightSQL, _, _) => Err(eyre!(
+            "FlightSQL queries can't be run without the flightsql feature"
+        )),
+    }
+}
+
+/// Compare the two sides of `spec`, sending the rows that differ back once done
+fn diff(app: &App, spec: DiffSpec) {
+    let history = app.state.history_tab.history();
+    let inputs = spec
+        .left
+        .input(history)
+        .and_then(|left| Ok((left, spec.right.input(history)?)));
+    let (left, right) = match inputs {
+        Ok(inputs) => inputs,
+        Err(e) => {
+            error!("Unable to diff {}: {}", spec, e);
+            return;
         }
-        KeyCode::Left => {
-            app.state.logs_tab.transition(TuiWidgetEvent::LeftKey);
+    };
+    #[cfg(feature = "flightsql")]
+    let connections =
+        diff_connection(app, &left).and_then(|left| Ok((left, diff_connection(app, &right)?)));
+    #[cfg(feature = "flightsql")]
+    let (left_config, right_config) = match connections {
+        Ok(connections) => connections,
+        Err(e) => {
+            error!("Unable to diff {}: {}", spec, e);
+            return;
         }
-        KeyCode::PageDown => {
-            app.state.logs_tab.transition(TuiWidgetEvent::NextPageKey);
+    };
+    let ctx = app.execution.session_ctx.clone();
+    #[cfg(feature = "flightsql")]
+    let client = Arc::clone(&app.execution.flightsql_client);
+    #[cfg(feature = "flightsql")]
+    let selected = flightsql_connection(app).name;
+    let event_tx = app.app_event_tx.clone();
+    info!("Diffing {}", spec);
+    tokio::spawn(async move {
+        let start = std::time::Instant::now();
+        let diffed = async {
+            #[cfg(feature = "flightsql")]
+            let (left, right) = (
+                read_diff_input(&ctx, &client, &selected, &left_config, left).await?,
+                read_diff_input(&ctx, &client, &selected, &right_config, right).await?,
+            );
+            #[cfg(not(feature = "flightsql"))]
+            let (left, right) = (
+                read_diff_input(&ctx, left).await?,
+                read_diff_input(&ctx, right).await?,
+            );
+            diff_frames(left, right, &spec.keys).await
+        };
+        match diffed.await {
+            Ok((summary, batches)) => {
+                let results = DiffResults {
+                    spec,
+                    summary,
+                    batches,
+                    elapsed: start.elapsed(),
+                };
+                let _ = event_tx.send(AppEvent::DiffResults(results));
+            }
+            Err(e) => error!("Error diffing {}: {}", spec, e),
         }
+    });
+}
 
-        KeyCode::PageUp => {
-            app.state.logs_tab.transition(TuiWidgetEvent::PrevPageKey);
-        }
+/// Navigation of the column profile view shared by the SQL and FlightSQL tabs
+fn profile_handler(profile: &mut Option<Profile>, action: Option<Action>) {
+    match (&profile, action) {
+        (_, Some(Action::CloseProfile)) => *profile = None,
+        (Some(p), Some(action)) => results_navigation_handler(Some(p.state()), action),
         _ => {}
     }
 }
 
-fn context_tab_key_event_handler(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('q') => app.state.should_quit = true,
-        tab @ (KeyCode::Char('1')
-        | KeyCode::Char('2')
-        | KeyCode::Char('3')
-        | KeyCode::Char('4')
-        | KeyCode::Char('5')) => tab_navigation_handler(app, tab),
-        _ => {}
+/// Copy part of the results shown on a tab to the clipboard
+fn copy_results(
+    app: &App,
+    results: Option<&[RecordBatch]>,
+    state: &Option<RefCell<ResultsState>>,
+    target: CopyTarget,
+) {
+    let (Some(batches), Some(state)) = (results, state) else {
+        info!("No results to copy");
+        return;
+    };
+    let format = app.state.copy_format;
+    let copied = copy_text(
+        batches,
+        &state.borrow(),
+        target,
+        format,
+        &app.state.config.display,
+    )
+    .and_then(|text| {
+        copy_to_clipboard(
+            &text,
+            &app.state.config.interaction.clipboard,
+            &app.state.data_dir,
+        )
+    });
+    match copied {
+        Ok(()) => info!("Copied {:?} as {}", target, format.name()),
+        Err(e) => error!("Error copying results: {}", e),
     }
 }
 
-fn logs_tab_app_event_handler(app: &mut App, event: AppEvent) {
-    match event {
-        AppEvent::Key(key) => logs_tab_key_event_handler(app, key),
-        AppEvent::Tick => {}
-        AppEvent::Error => {}
-        _ => {}
+fn copy_target(action: Action) -> CopyTarget {
+    match action {
+        Action::CopyCell => CopyTarget::Cell,
+        Action::CopyRow => CopyTarget::Row,
+        _ => CopyTarget::Results,
+    }
+}
+
+fn go_to_row(results: &Option<RefCell<ResultsState>>, input: &str) {
+    match (results, input.trim().parse::<usize>()) {
+        (Some(s), Ok(row)) => s.borrow_mut().select(row),
+        (None, _) => info!("No results to go to row {} in", input),
+        (_, Err(e)) => error!("Invalid row number {:?}: {}", input, e),
+    }
+}
+
+fn logs_tab_key_event_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::Logs, Mode::Normal, &key);
+    let event = match action {
+        Some(Action::Quit) => {
+            app.state.should_quit = true;
+            return;
+        }
+        Some(
+            tab @ (Action::SelectSqlTab
+            | Action::SelectFlightsqlTab
+            | Action::SelectHistoryTab
+            | Action::SelectLogsTab
+            | Action::SelectContextTab),
+        ) => {
+            tab_navigation_handler(app, tab);
+            return;
+        }
+        Some(Action::LogsFocus) => TuiWidgetEvent::FocusKey,
+        Some(Action::LogsHide) => TuiWidgetEvent::HideKey,
+        Some(Action::LogsCaptureMore) => TuiWidgetEvent::PlusKey,
+        Some(Action::LogsCaptureLess) => TuiWidgetEvent::MinusKey,
+        Some(Action::LogsToggleHidden) => TuiWidgetEvent::SpaceKey,
+        Some(Action::LogsExitPageMode) => TuiWidgetEvent::EscapeKey,
+        Some(Action::LogsNextTarget) => TuiWidgetEvent::DownKey,
+        Some(Action::LogsPreviousTarget) => TuiWidgetEvent::UpKey,
+        Some(Action::LogsShowMore) => TuiWidgetEvent::RightKey,
+        Some(Action::LogsShowLess) => TuiWidgetEvent::LeftKey,
+        Some(Action::LogsPageDown) => TuiWidgetEvent::NextPageKey,
+        Some(Action::LogsPageUp) => TuiWidgetEvent::PrevPageKey,
+        _ => return,
     };
+    app.state.logs_tab.transition(event);
 }
 
-fn context_tab_app_event_handler(app: &mut App, event: AppEvent) {
+fn logs_tab_app_event_handler(app: &mut App, event: AppEvent) {
     match event {
-        AppEvent::Key(key) => context_tab_key_event_handler(app, key),
+        AppEvent::Key(key) => logs_tab_key_event_handler(app, key),
         AppEvent::Tick => {}
         AppEvent::Error => {}
         _ => {}
@@ -157,12 +478,18 @@ pub fn app_event_handler(app: &mut App, event: AppEvent) -> Result<()> {
     // each tab
     trace!("Tui::Event: {:?}", event);
     let now = std::time::Instant::now();
+    // Resource usage is shown on every tab, so is refreshed before the tick reaches the tab
+    if let AppEvent::Tick = event {
+        app.state.resources.refresh(&app.execution.resources);
+    }
     match event {
         AppEvent::ExecuteDDL(ddl) => {
             let queries: Vec<String> = ddl.split(';').map(|s| s.to_string()).collect();
             queries.into_iter().for_each(|q| {
                 let ctx = app.execution.session_ctx.clone();
+                let active = app.execution.resources.start_query();
                 tokio::spawn(async move {
+                    let _active = active;
                     match ctx.sql(&q).await {
                         Ok(df) => {
                             if df.collect().await.is_ok() {
@@ -176,6 +503,57 @@ pub fn app_event_handler(app: &mut App, event: AppEvent) -> Result<()> {
                 });
             })
         }
+        AppEvent::FilteredResults(f) => {
+            let filtered = Some((f.filter, f.batches));
+            let result = match f.tab {
+                SelectedTab::SQL
+                    if app.state.sql_tab.query().as_ref().map(|q| q.sql()) == Some(&f.sql) =>
+                {
+                    app.state
+                        .sql_tab
+                        .set_filter(filtered, &app.state.config.display)
+                }
+                #[cfg(feature = "flightsql")]
+                SelectedTab::FlightSQL
+                    if app.state.flightsql_tab.query().as_ref().map(|q| q.sql())
+                        == Some(&f.sql) =>
+                {
+                    app.state
+                        .flightsql_tab
+                        .set_filter(filtered, &app.state.config.display)
+                }
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                error!("Error showing filtered results: {}", e);
+            }
+        }
+        AppEvent::ProfileResults(p) => match p.tab {
+            SelectedTab::SQL => app.state.sql_tab.set_profile(Some(Profile::new(p))),
+            #[cfg(feature = "flightsql")]
+            SelectedTab::FlightSQL => app.state.flightsql_tab.set_profile(Some(Profile::new(p))),
+            _ => {}
+        },
+        // Diffs are shown as the results of the SQL tab so they can be browsed and copied like
+        // any other results, but are not added to the query history
+        AppEvent::DiffResults(d) => {
+            let rows = d.batches.iter().map(|b| b.num_rows()).sum();
+            let query = Query::new(
+                format!("Diff {}", d.spec),
+                Some(d.batches),
+                Some(rows),
+                None,
+                d.elapsed,
+                None,
+            );
+            app.state.sql_tab.set_query(query);
+            app.state.sql_tab.refresh_query_results_state();
+            app.state.sql_tab.set_chart(None, &app.state.config.display);
+            app.state.sql_tab.set_diff(Some(d.summary));
+            app.state.tabs.selected = SelectedTab::SQL;
+        }
+        AppEvent::Catalog(catalog) => app.state.context_tab.set_catalog(catalog),
+        AppEvent::TablePreview(preview) => app.state.context_tab.set_preview(preview),
         AppEvent::QueryResult(r) => {
             app.state.sql_tab.set_query(r.clone());
             app.state.sql_tab.refresh_query_results_state();
@@ -185,48 +563,126 @@ pub fn app_event_handler(app: &mut App, event: AppEvent) -> Result<()> {
                 *r.execution_time(),
                 r.execution_stats().clone(),
             );
-            app.state.history_tab.add_to_history(history_query);
+            let chart = app.state.history_tab.add_run_to_history(history_query);
+            app.state
+                .sql_tab
+                .set_chart(chart, &app.state.config.display);
             app.state.history_tab.refresh_history_table_state()
         }
         #[cfg(feature = "flightsql")]
+        AppEvent::FlightSQLProgress(progress) => {
+            app.state.flightsql_tab.set_progress(Some(progress))
+        }
+        #[cfg(feature = "flightsql")]
         AppEvent::FlightSQLQueryResult(r) => {
             app.state.flightsql_tab.set_query(r.clone());
             app.state.flightsql_tab.refresh_query_results_state();
-            let history_query = HistoryQuery::new(
+            let mut history_query = HistoryQuery::new(
                 Context::FlightSQL,
                 r.sql().clone(),
                 *r.execution_time(),
                 r.execution_stats().clone(),
             );
-            app.state.history_tab.add_to_history(history_query);
+            history_query.set_connection(r.connection().clone());
+            history_query.set_flightsql_stats(r.flightsql_stats().clone());
+            let chart = app.state.history_tab.add_run_to_history(history_query);
+            app.state
+                .flightsql_tab
+                .set_chart(chart, &app.state.config.display);
             app.state.history_tab.refresh_history_table_state()
         }
         #[cfg(feature = "flightsql")]
-        AppEvent::EstablishFlightSQLConnection => {
-            let url = app.state.config.flightsql.connection_url.clone();
-            info!("Connection to FlightSQL host: {}", url);
-            let url: &'static str = Box::leak(url.into_boxed_str());
+        AppEvent::EstablishFlightSQLConnection(index) => {
+            let connections = app.state.config.flightsql.connections();
+            let Some(config) = connections.get(index).map(|c| (*c).clone()) else {
+                error!("There is no FlightSQL connection {}", index);
+                return Ok(());
+            };
+            info!(
+                "Connecting to FlightSQL connection {}: {}",
+                config.name, config.connection_url
+            );
+            let attempt = app.state.flightsql_tab.start_connecting(index);
             let client = Arc::clone(&app.execution.flightsql_client);
+            let event_tx = app.app_event_tx.clone();
+            let prepared = Arc::clone(&app.execution.flightsql_prepared);
             tokio::spawn(async move {
-                let maybe_channel = Channel::from_static(&url).connect().await;
-                info!("Created channel");
-                match maybe_channel {
-                    Ok(channel) => {
-                        let flightsql_client = FlightSqlServiceClient::new(channel);
-                        let mut locked_client = client.lock().await;
-                        *locked_client = Some(flightsql_client);
+                close_prepared(&prepared).await;
+                // Queries aren't sent to the previous connection while connecting
+                *client.lock().await = None;
+                let start = std::time::Instant::now();
+                let connected = match connect_flightsql(&config).await {
+                    Ok(flightsql_client) => {
+                        info!("Connected to FlightSQL connection {}", config.name);
+                        Ok((flightsql_client, start.elapsed()))
                     }
                     Err(e) => {
-                        info!("Error creating channel for FlightSQL: {:?}", e);
+                        error!("Error connecting to FlightSQL: {:?}", e);
+                        Err(e.to_string())
                     }
-                }
+                };
+                let connection = FlightSQLConnection {
+                    attempt,
+                    client: connected,
+                };
+                let _ = event_tx.send(AppEvent::FlightSQLConnected(connection));
             });
         }
+        #[cfg(feature = "flightsql")]
+        AppEvent::FlightSQLPrepared(prepared) => {
+            let has_parameters = !prepared.parameters.is_empty();
+            app.state.flightsql_tab.set_prepared(prepared);
+            if has_parameters {
+                let values = app
+                    .state
+                    .flightsql_tab
+                    .prepared()
+                    .as_ref()
+                    .map(|p| p.values.clone())
+                    .unwrap_or_default();
+                app.state
+                    .flightsql_tab
+                    .open_prompt_with_value(PromptKind::Parameters, values);
+            } else {
+                flightsql::run_prepared(app, String::new());
+            }
+        }
+        #[cfg(feature = "flightsql")]
+        AppEvent::FlightSQLMetadata(metadata) => {
+            app.state.flightsql_tab.set_metadata(Some(metadata))
+        }
+        #[cfg(feature = "flightsql")]
+        AppEvent::FlightSQLPrimaryKeys(keys) => {
+            if let Some(metadata) = app.state.flightsql_tab.metadata_mut() {
+                if let Err(e) = metadata.set_primary_keys(&keys) {
+                    error!("Error reading primary keys: {}", e);
+                }
+            }
+        }
+        #[cfg(feature = "flightsql")]
+        AppEvent::FlightSQLConnected(connection) => {
+            let (status, flightsql_client) = match connection.client {
+                Ok((flightsql_client, latency)) => {
+                    (ConnectionStatus::Connected(latency), Some(flightsql_client))
+                }
+                Err(e) => (ConnectionStatus::Failed(e), None),
+            };
+            if app
+                .state
+                .flightsql_tab
+                .finish_connecting(connection.attempt, status)
+            {
+                let client = Arc::clone(&app.execution.flightsql_client);
+                tokio::spawn(async move {
+                    *client.lock().await = flightsql_client;
+                });
+            }
+        }
         _ => {
             match app.state.tabs.selected {
                 SelectedTab::SQL => sql::app_event_handler(app, event),
                 SelectedTab::Logs => logs_tab_app_event_handler(app, event),
-                SelectedTab::Context => context_tab_app_event_handler(app, event),
+                SelectedTab::Context => context::app_event_handler(app, event),
                 SelectedTab::History => history::app_event_handler(app, event),
                 #[cfg(feature = "flightsql")]
                 SelectedTab::FlightSQL => flightsql::app_event_handler(app, event),
@@ -16,32 +16,62 @@
 // under the License.
 
 use std::{
+    str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use datafusion::{arrow::array::RecordBatch, physical_plan::execute_stream};
-use log::{error, info};
-use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use log::{debug, error, info};
+use ratatui::crossterm::event::KeyEvent;
 use tokio_stream::StreamExt;
+use tracing::{info_span, Instrument};
 
 use crate::app::{
-    execution::collect_plan_stats, handlers::tab_navigation_handler, state::tabs::sql::Query,
+    execution::{collect_plan_stats, create_traced_physical_plan},
+    handlers::{
+        copy_results, copy_target, diff, filter_results, go_to_row, profile, profile_handler,
+        record_detail_handler, results_navigation_handler, tab_navigation_handler,
+    },
+    keymap::{Action, Mode},
+    state::chart::ChartConfig,
+    state::diff::DiffSpec,
+    state::functions::FunctionBrowser,
+    state::prompt::PromptKind,
+    state::tabs::history::Context,
+    state::tabs::sql::Query,
     AppEvent,
 };
+use crate::telemetry::trace::{last_query_trace, QUERY_SPAN};
+use crate::ui::SelectedTab;
+#[cfg(feature = "substrait")]
+use {
+    crate::app::{
+        execution::substrait_dataframe, handlers::export_substrait, instrument::trace_plan,
+    },
+    datafusion::physical_plan::collect,
+    std::path::Path,
+    tracing::Span,
+};
 
 use super::App;
 
 pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Char('q') => app.state.should_quit = true,
-        tab @ (KeyCode::Char('1')
-        | KeyCode::Char('2')
-        | KeyCode::Char('3')
-        | KeyCode::Char('4')
-        | KeyCode::Char('5')) => tab_navigation_handler(app, tab),
-        KeyCode::Char('c') => app.state.sql_tab.clear_editor(),
-        KeyCode::Char('e') => {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::SQL, Mode::Normal, &key)
+    {
+        Some(Action::Quit) => app.state.should_quit = true,
+        Some(
+            tab @ (Action::SelectSqlTab
+            | Action::SelectFlightsqlTab
+            | Action::SelectHistoryTab
+            | Action::SelectLogsTab
+            | Action::SelectContextTab),
+        ) => tab_navigation_handler(app, tab),
+        Some(Action::ClearEditor) => app.state.sql_tab.clear_editor(),
+        Some(Action::Edit) => {
             let editor = app.state.sql_tab.editor();
             let lines = editor.lines();
             let content = lines.join("");
@@ -51,47 +81,114 @@ pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
             }
             app.state.sql_tab.edit();
         }
-        KeyCode::Down => {
-            if let Some(s) = app.state.sql_tab.query_results_state() {
-                info!("Select next");
-                let mut s = s.borrow_mut();
-                s.select_next();
+        Some(
+            action @ (Action::SelectNextRow
+            | Action::SelectPreviousRow
+            | Action::SelectFirstRow
+            | Action::SelectLastRow
+            | Action::PageDown
+            | Action::PageUp
+            | Action::SelectNextColumn
+            | Action::SelectPreviousColumn
+            | Action::FreezeColumns),
+        ) => results_navigation_handler(app.state.sql_tab.query_results_state().as_ref(), action),
+        Some(Action::GoToRow) => app.state.sql_tab.open_prompt(PromptKind::GoToRow),
+        Some(Action::SortColumn) => {
+            if let Err(e) = app
+                .state
+                .sql_tab
+                .sort_by_selected_column(&app.state.config.display)
+            {
+                info!("Unable to sort results: {}", e)
+            }
+        }
+        Some(Action::FilterResults) => app.state.sql_tab.open_prompt(PromptKind::Filter),
+        Some(Action::Search) => app.state.sql_tab.open_prompt(PromptKind::Search),
+        Some(action @ (Action::NextMatch | Action::PreviousMatch)) => {
+            let forward = action == Action::NextMatch;
+            if let Err(e) = app
+                .state
+                .sql_tab
+                .find_next_match(forward, &app.state.config.display)
+            {
+                info!("Unable to search results: {}", e)
             }
         }
-        KeyCode::Up => {
-            if let Some(s) = app.state.sql_tab.query_results_state() {
-                info!("Select previous");
-                let mut s = s.borrow_mut();
-                s.select_previous();
+        Some(action @ (Action::CopyCell | Action::CopyRow | Action::CopyResults)) => copy_results(
+            app,
+            app.state.sql_tab.displayed_results(),
+            app.state.sql_tab.query_results_state(),
+            copy_target(action),
+        ),
+        Some(Action::NextCopyFormat) => app.state.copy_format = app.state.copy_format.next(),
+        Some(Action::ToggleChart) if app.state.sql_tab.chart().is_some() => {
+            app.state.sql_tab.toggle_chart()
+        }
+        Some(Action::ToggleChart | Action::EditChart) => {
+            let chart = app.state.sql_tab.chart_suggestion();
+            app.state
+                .sql_tab
+                .open_prompt_with_value(PromptKind::Chart, chart)
+        }
+        Some(Action::OpenDetail) => {
+            if let Err(e) = app.state.sql_tab.open_detail(&app.state.config.display) {
+                info!("Unable to show row: {}", e)
             }
         }
+        Some(Action::ProfileResults) => profile(
+            app,
+            SelectedTab::SQL,
+            None,
+            app.state.sql_tab.displayed_results(),
+        ),
+        Some(Action::ProfileTable) => app.state.sql_tab.open_prompt(PromptKind::ProfileTable),
+        Some(Action::Diff) => app.state.sql_tab.open_prompt(PromptKind::Diff),
+        Some(Action::OpenFunctions) => {
+            let state = app.execution.session_ctx.state();
+            app.state
+                .sql_tab
+                .set_functions(Some(FunctionBrowser::new(&state)));
+        }
+        Some(Action::ExportTrace) => app
+            .state
+            .sql_tab
+            .open_prompt_with_value(PromptKind::ExportTrace, "trace.json".to_string()),
+        #[cfg(feature = "substrait")]
+        Some(Action::ExportSubstrait) => app
+            .state
+            .sql_tab
+            .open_prompt_with_value(PromptKind::ExportSubstrait, "query.substrait".to_string()),
+        #[cfg(feature = "substrait")]
+        Some(Action::RunSubstraitFile) => app.state.sql_tab.open_prompt(PromptKind::RunSubstrait),
 
-        KeyCode::Enter => {
+        Some(Action::RunQuery) => {
             info!("Run query");
             let sql = app.state.sql_tab.editor().lines().join("");
             info!("SQL: {}", sql);
             let mut query = Query::new(sql.clone(), None, None, None, Duration::default(), None);
             let ctx = app.execution.session_ctx.clone();
             let _event_tx = app.app_event_tx.clone();
+            let active = app.execution.resources.start_query();
+            let span = info_span!(QUERY_SPAN, sql = %sql);
             // TODO: Maybe this should be on a separate runtime to prevent blocking main thread /
             // runtime
             // TODO: Extract this into function to be used in both normal and editable handler
-            tokio::spawn(async move {
-                let start = std::time::Instant::now();
-                match ctx.sql(&sql).await {
-                    Ok(df) => {
-                        let plan = df.create_physical_plan().await;
-                        match plan {
-                            Ok(p) => {
-                                let task_ctx = ctx.task_ctx();
-                                let stream = execute_stream(Arc::clone(&p), task_ctx);
-                                let mut batches: Vec<RecordBatch> = Vec::new();
-                                match stream {
-                                    Ok(mut s) => {
+            tokio::spawn(
+                async move {
+                    let _active = active;
+                    let start = std::time::Instant::now();
+                    match create_traced_physical_plan(&ctx, &sql).await {
+                        Ok(p) => {
+                            let task_ctx = ctx.task_ctx();
+                            let stream = execute_stream(Arc::clone(&p), task_ctx);
+                            let mut batches: Vec<RecordBatch> = Vec::new();
+                            match stream {
+                                Ok(mut s) => {
+                                    async {
                                         while let Some(b) = s.next().await {
                                             match b {
                                                 Ok(b) => {
-                                                    info!("Got batch with {} rows", b.num_rows());
+                                                    debug!("Got batch with {} rows", b.num_rows());
                                                     batches.push(b)
                                                 }
                                                 Err(e) => {
@@ -99,73 +196,81 @@ pub fn normal_mode_handler(app: &mut App, key: KeyEvent) {
                                                 }
                                             }
                                         }
-
-                                        let elapsed = start.elapsed();
-                                        let stats = collect_plan_stats(p);
-                                        info!("Got stats: {:?}", stats);
-                                        let rows: usize =
-                                            batches.iter().map(|b| b.num_rows()).sum();
-                                        query.set_num_rows(Some(rows));
-                                        query.set_execution_time(elapsed);
-                                        query.set_results(Some(batches));
-                                        query.set_execution_stats(stats);
-                                    }
-                                    Err(e) => {
-                                        error!("Error getting RecordBatchStream: {:?}", e)
+                                        // Operator spans end when their streams are dropped
+                                        drop(s);
                                     }
+                                    .instrument(info_span!("execute"))
+                                    .await;
+
+                                    let elapsed = start.elapsed();
+                                    let stats = collect_plan_stats(p);
+                                    info!("Got stats: {:?}", stats);
+                                    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                                    query.set_num_rows(Some(rows));
+                                    query.set_execution_time(elapsed);
+                                    query.set_results(Some(batches));
+                                    query.set_execution_stats(stats);
+                                }
+                                Err(e) => {
+                                    error!("Error getting RecordBatchStream: {:?}", e)
                                 }
-                            }
-                            Err(e) => {
-                                error!("Error constructing physical plan: {:?}", e)
                             }
                         }
+                        Err(e) => {
+                            error!("Error planning query: {:?}", e);
+                            let elapsed = start.elapsed();
+                            query.set_error(Some(e.to_string()));
+                            query.set_execution_time(elapsed);
+                        }
                     }
-                    Err(e) => {
-                        error!("Error creating dataframe: {:?}", e);
-                        let elapsed = start.elapsed();
-                        query.set_error(Some(e.to_string()));
-                        query.set_execution_time(elapsed);
-                    }
+                    let _ = _event_tx.send(AppEvent::QueryResult(query));
                 }
-                let _ = _event_tx.send(AppEvent::QueryResult(query));
-            });
+                .instrument(span),
+            );
         }
         _ => {}
     }
 }
 
 pub fn editable_handler(app: &mut App, key: KeyEvent) {
-    match (key.code, key.modifiers) {
-        (KeyCode::Left, KeyModifiers::ALT) => app.state.sql_tab.previous_word(),
-        (KeyCode::Right, KeyModifiers::ALT) => app.state.sql_tab.next_word(),
-        (KeyCode::Backspace, KeyModifiers::ALT) => app.state.sql_tab.delete_word(),
-        (KeyCode::Esc, _) => app.state.sql_tab.exit_edit(),
-        (KeyCode::Enter, KeyModifiers::CONTROL) => {
+    match app.state.keymap.action(SelectedTab::SQL, Mode::Edit, &key) {
+        Some(Action::PreviousWord) => app.state.sql_tab.previous_word(),
+        Some(Action::NextWord) => app.state.sql_tab.next_word(),
+        Some(Action::DeleteWord) => app.state.sql_tab.delete_word(),
+        Some(Action::ExitEdit) => app.state.sql_tab.exit_edit(),
+        Some(Action::RunQuery) => {
             let query = app.state.sql_tab.editor().lines().join("");
             let ctx = app.execution.session_ctx.clone();
             let _event_tx = app.app_event_tx.clone();
+            let active = app.execution.resources.start_query();
+            let span = info_span!(QUERY_SPAN, sql = %query);
             // TODO: Maybe this should be on a separate runtime to prevent blocking main thread /
             // runtime
-            tokio::spawn(async move {
-                // TODO: Turn this into a match and return the error somehow
-                let start = Instant::now();
-                if let Ok(df) = ctx.sql(&query).await {
-                    let plan = df.create_physical_plan().await;
-                    match plan {
+            tokio::spawn(
+                async move {
+                    let _active = active;
+                    // TODO: Turn this into a match and return the error somehow
+                    let start = Instant::now();
+                    match create_traced_physical_plan(&ctx, &query).await {
                         Ok(p) => {
                             let task_ctx = ctx.task_ctx();
                             let stream = execute_stream(Arc::clone(&p), task_ctx);
                             let mut batches: Vec<RecordBatch> = Vec::new();
                             match stream {
                                 Ok(mut s) => {
-                                    while let Some(b) = s.next().await {
-                                        match b {
-                                            Ok(b) => batches.push(b),
-                                            Err(e) => {
-                                                error!("Error getting RecordBatch: {:?}", e)
+                                    async {
+                                        while let Some(b) = s.next().await {
+                                            match b {
+                                                Ok(b) => batches.push(b),
+                                                Err(e) => {
+                                                    error!("Error getting RecordBatch: {:?}", e)
+                                                }
                                             }
                                         }
+                                        drop(s);
                                     }
+                                    .instrument(info_span!("execute"))
+                                    .await;
 
                                     let elapsed = start.elapsed();
                                     let stats = collect_plan_stats(p);
@@ -180,20 +285,242 @@ pub fn editable_handler(app: &mut App, key: KeyEvent) {
                             }
                         }
                         Err(e) => {
-                            error!("Error creating physical plan: {:?}", e)
+                            error!("Error planning query: {:?}", e)
                         }
                     }
-                } else {
-                    error!("Error creating dataframe")
                 }
-            });
+                .instrument(span),
+            );
         }
         _ => app.state.sql_tab.update_editor_content(key),
     }
 }
 
+pub fn prompt_handler(app: &mut App, key: KeyEvent) {
+    match app
+        .state
+        .keymap
+        .action(SelectedTab::SQL, Mode::Prompt, &key)
+    {
+        Some(Action::CancelPrompt) => {
+            app.state.sql_tab.close_prompt();
+        }
+        Some(Action::SubmitPrompt) => {
+            if let Some(prompt) = app.state.sql_tab.close_prompt() {
+                match prompt.kind() {
+                    PromptKind::GoToRow => {
+                        go_to_row(app.state.sql_tab.query_results_state(), &prompt.value())
+                    }
+                    PromptKind::Filter => {
+                        let filter = prompt.value();
+                        if filter.trim().is_empty() {
+                            if let Err(e) = app
+                                .state
+                                .sql_tab
+                                .set_filter(None, &app.state.config.display)
+                            {
+                                info!("Unable to clear filter: {}", e)
+                            }
+                        } else if let Some(q) = app.state.sql_tab.query() {
+                            let results = q.results().clone().unwrap_or_default();
+                            filter_results(app, SelectedTab::SQL, q.sql().clone(), results, filter);
+                        }
+                    }
+                    PromptKind::Search => {
+                        let term = prompt.value();
+                        let searching = !term.is_empty();
+                        if let Some(s) = app.state.sql_tab.query_results_state() {
+                            s.borrow_mut().set_search(searching.then_some(term));
+                        }
+                        if searching {
+                            if let Err(e) = app
+                                .state
+                                .sql_tab
+                                .find_next_match(true, &app.state.config.display)
+                            {
+                                info!("Unable to search results: {}", e)
+                            }
+                        }
+                    }
+                    PromptKind::Chart => {
+                        let input = prompt.value();
+                        let chart = match input.trim() {
+                            "" => Ok(None),
+                            chart => ChartConfig::from_str(chart).map(Some),
+                        };
+                        match chart {
+                            Ok(chart) => {
+                                if let Some(q) = app.state.sql_tab.query() {
+                                    app.state.history_tab.set_chart(
+                                        Context::Local,
+                                        q.sql(),
+                                        chart.clone(),
+                                    );
+                                }
+                                app.state
+                                    .sql_tab
+                                    .set_chart(chart, &app.state.config.display);
+                            }
+                            Err(e) => error!("Invalid chart: {}", e),
+                        }
+                    }
+                    PromptKind::ProfileTable => {
+                        let table = prompt.value().trim().to_string();
+                        if !table.is_empty() {
+                            profile(app, SelectedTab::SQL, Some(table), None)
+                        }
+                    }
+                    PromptKind::Diff => match DiffSpec::from_str(&prompt.value()) {
+                        Ok(spec) => diff(app, spec),
+                        Err(e) => error!("Invalid diff: {}", e),
+                    },
+                    // Only FlightSQL statements are prepared
+                    PromptKind::Parameters => {}
+                    PromptKind::SearchFunctions => {
+                        let search = prompt.value().trim().to_string();
+                        if let Some(functions) = app.state.sql_tab.functions_mut() {
+                            functions.set_filter((!search.is_empty()).then_some(search));
+                        }
+                    }
+                    // Only the Context tab edits the session config
+                    PromptKind::FilterSettings | PromptKind::SettingValue => {}
+                    PromptKind::ExportTrace => export_trace(prompt.value().trim()),
+                    #[cfg(feature = "substrait")]
+                    PromptKind::ExportSubstrait => {
+                        let sql = app.state.sql_tab.editor().lines().join("");
+                        export_substrait(app, sql, prompt.value().trim().to_string())
+                    }
+                    #[cfg(feature = "substrait")]
+                    PromptKind::RunSubstrait => {
+                        run_substrait_file(app, prompt.value().trim().to_string())
+                    }
+                }
+            }
+        }
+        _ => {
+            if let Some(prompt) = app.state.sql_tab.prompt_mut() {
+                prompt.update(key)
+            }
+        }
+    }
+}
+
+/// Save the trace of the last query run in the local session as a Chrome trace
+fn export_trace(path: &str) {
+    match last_query_trace() {
+        Some(trace) => match trace.save(std::path::Path::new(path)) {
+            Ok(()) => info!("Saved query trace to {}", path),
+            Err(e) => error!("Error saving query trace to {}: {}", path, e),
+        },
+        None => error!("No query has been traced yet"),
+    }
+}
+
+/// Run the Substrait plan saved at `path` against the local session context
+#[cfg(feature = "substrait")]
+fn run_substrait_file(app: &mut App, path: String) {
+    info!("Running Substrait plan {}", path);
+    let ctx = app.execution.session_ctx.clone();
+    let event_tx = app.app_event_tx.clone();
+    let active = app.execution.resources.start_query();
+    let span = info_span!(QUERY_SPAN, substrait = %path);
+    tokio::spawn(
+        async move {
+            let _active = active;
+            let label = format!("-- Substrait plan {}", path);
+            let mut query = Query::new(label, None, None, None, Duration::default(), None);
+            let start = Instant::now();
+            let executed = async {
+                let df = substrait_dataframe(&ctx, Path::new(&path))
+                    .instrument(info_span!("logical_plan"))
+                    .await?;
+                let plan = df
+                    .create_physical_plan()
+                    .instrument(info_span!("physical_plan"))
+                    .await?;
+                let plan = trace_plan(plan, &Span::current())?;
+                let batches = collect(Arc::clone(&plan), ctx.task_ctx())
+                    .instrument(info_span!("execute"))
+                    .await?;
+                Ok::<_, color_eyre::eyre::Error>((batches, collect_plan_stats(plan)))
+            };
+            match executed.await {
+                Ok((batches, stats)) => {
+                    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+                    query.set_num_rows(Some(rows));
+                    query.set_results(Some(batches));
+                    query.set_execution_stats(stats);
+                }
+                Err(e) => {
+                    error!("Error running Substrait plan: {:?}", e);
+                    query.set_error(Some(e.to_string()));
+                }
+            }
+            query.set_execution_time(start.elapsed());
+            let _ = event_tx.send(AppEvent::QueryResult(query));
+        }
+        .instrument(span),
+    );
+}
+
+pub fn functions_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::SQL, Mode::Functions, &key);
+    let Some(functions) = app.state.sql_tab.functions_mut() else {
+        return;
+    };
+    match action {
+        Some(Action::CloseFunctions) => app.state.sql_tab.set_functions(None),
+        Some(Action::SelectNextRow) => functions.select_next(),
+        Some(Action::SelectPreviousRow) => functions.select_previous(),
+        Some(Action::SelectFirstRow) => functions.select_first(),
+        Some(Action::SelectLastRow) => functions.select_last(),
+        Some(Action::SearchFunctions) => {
+            let search = functions.filter().unwrap_or_default().to_string();
+            app.state
+                .sql_tab
+                .open_prompt_with_value(PromptKind::SearchFunctions, search);
+        }
+        // The call is inserted at the cursor, which is left between the parentheses
+        Some(Action::InsertFunction) => {
+            if let Some((template, back)) = functions.selected_function().map(|f| f.template()) {
+                app.state.sql_tab.set_functions(None);
+                app.state.sql_tab.insert_template(&template, back);
+                app.state.sql_tab.edit();
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn detail_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::SQL, Mode::Detail, &key);
+    record_detail_handler(app.state.sql_tab.detail_mut(), action);
+}
+
+pub fn profile_view_handler(app: &mut App, key: KeyEvent) {
+    let action = app
+        .state
+        .keymap
+        .action(SelectedTab::SQL, Mode::Profile, &key);
+    profile_handler(app.state.sql_tab.profile_mut(), action);
+}
+
 pub fn app_event_handler(app: &mut App, event: AppEvent) {
     match event {
+        AppEvent::Key(key) if app.state.sql_tab.prompt().is_some() => prompt_handler(app, key),
+        AppEvent::Key(key) if app.state.sql_tab.functions().is_some() => {
+            functions_handler(app, key)
+        }
+        AppEvent::Key(key) if app.state.sql_tab.detail().is_some() => detail_handler(app, key),
+        AppEvent::Key(key) if app.state.sql_tab.profile().is_some() => {
+            profile_view_handler(app, key)
+        }
         AppEvent::Key(key) => match app.state.sql_tab.editor_editable() {
             true => editable_handler(app, key),
             false => normal_mode_handler(app, key),
@@ -0,0 +1,345 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! `tracing` spans for the operators of a physical plan and the requests made to object stores
+
+use std::any::Any;
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{Result as DFResult, Statistics};
+use datafusion::datasource::object_store::{DefaultObjectStoreRegistry, ObjectStoreRegistry};
+use datafusion::execution::{RecordBatchStream, SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::metrics::MetricsSet;
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result as ObjectStoreResult,
+};
+use tracing::{field, info_span, Instrument, Span};
+use url::Url;
+
+/// Wrap every operator of `plan` so that each partition it executes is recorded as a span.
+/// Partitions executed outside of an operator's span, e.g. on tasks spawned by a repartition,
+/// are recorded under `parent`.
+pub fn trace_plan(plan: Arc<dyn ExecutionPlan>, parent: &Span) -> DFResult<Arc<dyn ExecutionPlan>> {
+    plan.transform_up(|plan| {
+        let traced: Arc<dyn ExecutionPlan> = Arc::new(TracedExec {
+            inner: plan,
+            parent: parent.clone(),
+        });
+        Ok(Transformed::yes(traced))
+    })
+    .map(|t| t.data)
+}
+
+/// Operator that records each partition of the operator it wraps as a span, from when it's
+/// executed until its stream is dropped
+#[derive(Debug)]
+struct TracedExec {
+    inner: Arc<dyn ExecutionPlan>,
+    parent: Span,
+}
+
+impl DisplayAs for TracedExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt_as(t, f)
+    }
+}
+
+impl ExecutionPlan for TracedExec {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.inner.properties()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        self.inner.children()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(TracedExec {
+            inner: Arc::clone(&self.inner).with_new_children(children)?,
+            parent: self.parent.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        let current = Span::current();
+        let parent = if current.is_none() {
+            &self.parent
+        } else {
+            &current
+        };
+        let span = info_span!(
+            parent: parent,
+            "operator",
+            name = self.inner.name(),
+            partition,
+            rows = field::Empty,
+            batches = field::Empty,
+        );
+        // Inputs executed along with this partition are nested under it
+        let stream = span.in_scope(|| self.inner.execute(partition, context))?;
+        Ok(Box::pin(TracedStream {
+            inner: stream,
+            span,
+            rows: 0,
+            batches: 0,
+        }))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        self.inner.metrics()
+    }
+
+    fn statistics(&self) -> DFResult<Statistics> {
+        self.inner.statistics()
+    }
+}
+
+/// Stream of a partition, entering its span whenever it's polled
+struct TracedStream {
+    inner: SendableRecordBatchStream,
+    span: Span,
+    rows: usize,
+    batches: usize,
+}
+
+impl Stream for TracedStream {
+    type Item = DFResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        let poll = self.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(batch))) = &poll {
+            self.rows += batch.num_rows();
+            self.batches += 1;
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for TracedStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Drop for TracedStream {
+    fn drop(&mut self) {
+        self.span.record("rows", self.rows);
+        self.span.record("batches", self.batches);
+    }
+}
+
+/// Object store recording each request as a span
+#[derive(Debug)]
+pub struct TracedObjectStore {
+    inner: Arc<dyn ObjectStore>,
+}
+
+impl TracedObjectStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Display for TracedObjectStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Traced({})", self.inner)
+    }
+}
+
+fn request_span(request: &str, location: &Path) -> Span {
+    info_span!(
+        "object_store",
+        request,
+        location = %location,
+        bytes = field::Empty
+    )
+}
+
+#[async_trait]
+impl ObjectStore for TracedObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> ObjectStoreResult<PutResult> {
+        self.inner
+            .put_opts(location, payload, opts)
+            .instrument(request_span("put", location))
+            .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> ObjectStoreResult<Box<dyn MultipartUpload>> {
+        self.inner
+            .put_multipart_opts(location, opts)
+            .instrument(request_span("put_multipart", location))
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> ObjectStoreResult<GetResult> {
+        self.inner
+            .get_opts(location, options)
+            .instrument(request_span("get", location))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> ObjectStoreResult<Bytes> {
+        let span = request_span("get_range", location);
+        span.record("bytes", range.len());
+        self.inner.get_range(location, range).instrument(span).await
+    }
+
+    async fn get_ranges(
+        &self,
+        location: &Path,
+        ranges: &[Range<usize>],
+    ) -> ObjectStoreResult<Vec<Bytes>> {
+        self.inner
+            .get_ranges(location, ranges)
+            .instrument(request_span("get_ranges", location))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> ObjectStoreResult<ObjectMeta> {
+        self.inner
+            .head(location)
+            .instrument(request_span("head", location))
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> ObjectStoreResult<()> {
+        self.inner
+            .delete(location)
+            .instrument(request_span("delete", location))
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, ObjectStoreResult<ObjectMeta>> {
+        let span = request_span("list", prefix.unwrap_or(&Path::default()));
+        let inner = span.in_scope(|| self.inner.list(prefix));
+        Box::pin(ListStream { inner, span })
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> ObjectStoreResult<ListResult> {
+        self.inner
+            .list_with_delimiter(prefix)
+            .instrument(request_span(
+                "list_with_delimiter",
+                prefix.unwrap_or(&Path::default()),
+            ))
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> ObjectStoreResult<()> {
+        self.inner
+            .copy(from, to)
+            .instrument(request_span("copy", from))
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> ObjectStoreResult<()> {
+        self.inner
+            .copy_if_not_exists(from, to)
+            .instrument(request_span("copy_if_not_exists", from))
+            .await
+    }
+}
+
+/// Listing of an object store, entering its span whenever it's polled
+struct ListStream<'a> {
+    inner: BoxStream<'a, ObjectStoreResult<ObjectMeta>>,
+    span: Span,
+}
+
+impl Stream for ListStream<'_> {
+    type Item = ObjectStoreResult<ObjectMeta>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let span = self.span.clone();
+        let _entered = span.enter();
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Registry wrapping the object stores registered with it so their requests are traced
+#[derive(Debug, Default)]
+pub struct TracedObjectStoreRegistry {
+    inner: DefaultObjectStoreRegistry,
+}
+
+impl TracedObjectStoreRegistry {
+    pub fn new() -> Self {
+        let registry = Self::default();
+        // The default registry already has the local filesystem, which needs wrapping too
+        if let Ok(url) = Url::parse("file://") {
+            if let Ok(local) = registry.inner.get_store(&url) {
+                registry.register_store(&url, local);
+            }
+        }
+        registry
+    }
+}
+
+impl ObjectStoreRegistry for TracedObjectStoreRegistry {
+    fn register_store(
+        &self,
+        url: &Url,
+        store: Arc<dyn ObjectStore>,
+    ) -> Option<Arc<dyn ObjectStore>> {
+        self.inner
+            .register_store(url, Arc::new(TracedObjectStore::new(store)))
+    }
+
+    fn get_store(&self, url: &Url) -> DFResult<Arc<dyn ObjectStore>> {
+        self.inner.get_store(url)
+    }
+}
@@ -0,0 +1,966 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Mapping of key chords to named actions.
+//!
+//! Every tab has a `normal` mode and optionally an `edit` mode. Bindings in the `global` scope
+//! apply to the normal mode of every tab, while edit modes only see their own bindings so that
+//! characters can be typed into editors. Modal views that behave the same on every tab, such as
+//! prompts, the record detail view, the column profile view and the FlightSQL connection picker
+//! and metadata browser, have a single scope of their own.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
+
+use crate::app::config::{KeyBindingConfig, KeymapConfig, TabKeymapConfig};
+use crate::ui::SelectedTab;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum Mode {
+    Normal,
+    Edit,
+    Prompt,
+    Detail,
+    Profile,
+    Connections,
+    Metadata,
+    Settings,
+    Functions,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Edit => "edit",
+            Mode::Prompt => "prompt",
+            Mode::Detail => "detail",
+            Mode::Profile => "profile",
+            Mode::Connections => "connections",
+            Mode::Metadata => "metadata",
+            Mode::Settings => "settings",
+            Mode::Functions => "functions",
+        }
+    }
+
+    /// Whether the mode has separate bindings per tab
+    fn per_tab(&self) -> bool {
+        matches!(self, Mode::Normal | Mode::Edit)
+    }
+}
+
+/// The scope a binding lives in. `Global` bindings are shared by the normal mode of every tab
+/// and `Shared` bindings by a mode that behaves the same on every tab.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Global,
+    Tab(SelectedTab, Mode),
+    Shared(Mode),
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::Global => write!(f, "global"),
+            Scope::Tab(tab, mode) => write!(f, "{} {}", tab.keymap_name(), mode.as_str()),
+            Scope::Shared(mode) => write!(f, "{}", mode.as_str()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    SelectSqlTab,
+    SelectFlightsqlTab,
+    SelectHistoryTab,
+    SelectLogsTab,
+    SelectContextTab,
+    Edit,
+    ExitEdit,
+    ClearEditor,
+    RunQuery,
+    NextWord,
+    PreviousWord,
+    DeleteWord,
+    SelectNextRow,
+    SelectPreviousRow,
+    SelectFirstRow,
+    SelectLastRow,
+    PageDown,
+    PageUp,
+    GoToRow,
+    SelectNextColumn,
+    SelectPreviousColumn,
+    FreezeColumns,
+    SortColumn,
+    FilterResults,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    CopyCell,
+    CopyRow,
+    CopyResults,
+    NextCopyFormat,
+    ToggleChart,
+    EditChart,
+    OpenDetail,
+    CloseDetail,
+    ToggleExpand,
+    ProfileResults,
+    ProfileTable,
+    CloseProfile,
+    Diff,
+    MarkDiff,
+    OpenConnections,
+    Connect,
+    CloseConnections,
+    OpenMetadata,
+    CloseMetadata,
+    RefreshMetadata,
+    InsertName,
+    PreviewTable,
+    OpenSettings,
+    CloseSettings,
+    FilterSettings,
+    EditSetting,
+    SaveSettings,
+    OpenFunctions,
+    CloseFunctions,
+    SearchFunctions,
+    InsertFunction,
+    ExportTrace,
+    PrepareQuery,
+    ExportSubstrait,
+    RunSubstraitFile,
+    RunSubstrait,
+    SubmitPrompt,
+    CancelPrompt,
+    LogsFocus,
+    LogsHide,
+    LogsCaptureMore,
+    LogsCaptureLess,
+    LogsToggleHidden,
+    LogsExitPageMode,
+    LogsNextTarget,
+    LogsPreviousTarget,
+    LogsShowMore,
+    LogsShowLess,
+    LogsPageDown,
+    LogsPageUp,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Short description used when generating help text, read as "'<key>' to <description>"
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::SelectSqlTab => "open SQL tab",
+            Action::SelectFlightsqlTab => "open FlightSQL tab",
+            Action::SelectHistoryTab => "open History tab",
+            Action::SelectLogsTab => "open Logs tab",
+            Action::SelectContextTab => "open Context tab",
+            Action::Edit => "edit",
+            Action::ExitEdit => "exit edit mode",
+            Action::ClearEditor => "clear editor",
+            Action::RunQuery => "run query",
+            Action::NextWord => "move to next word",
+            Action::PreviousWord => "move to previous word",
+            Action::DeleteWord => "delete word",
+            Action::SelectNextRow => "select next row",
+            Action::SelectPreviousRow => "select previous row",
+            Action::SelectFirstRow => "select first row",
+            Action::SelectLastRow => "select last row",
+            Action::PageDown => "page down",
+            Action::PageUp => "page up",
+            Action::GoToRow => "go to row",
+            Action::SelectNextColumn => "select next column",
+            Action::SelectPreviousColumn => "select previous column",
+            Action::FreezeColumns => "freeze columns",
+            Action::SortColumn => "sort",
+            Action::FilterResults => "filter",
+            Action::Search => "search",
+            Action::NextMatch => "next match",
+            Action::PreviousMatch => "previous match",
+            Action::CopyCell => "copy cell",
+            Action::CopyRow => "copy row",
+            Action::CopyResults => "copy results",
+            Action::NextCopyFormat => "change copy format",
+            Action::ToggleChart => "toggle chart",
+            Action::EditChart => "edit chart",
+            Action::OpenDetail => "view row",
+            Action::CloseDetail => "close",
+            Action::ToggleExpand => "expand / collapse",
+            Action::ProfileResults => "profile results",
+            Action::ProfileTable => "profile table",
+            Action::CloseProfile => "close",
+            Action::Diff => "diff",
+            Action::MarkDiff => "mark for diff",
+            Action::OpenConnections => "connections",
+            Action::Connect => "connect",
+            Action::CloseConnections => "close",
+            Action::OpenMetadata => "browse metadata",
+            Action::CloseMetadata => "close",
+            Action::RefreshMetadata => "refresh",
+            Action::InsertName => "insert name",
+            Action::PreviewTable => "preview rows",
+            Action::OpenSettings => "session config",
+            Action::CloseSettings => "close",
+            Action::FilterSettings => "filter",
+            Action::EditSetting => "edit value",
+            Action::SaveSettings => "save to config file",
+            Action::OpenFunctions => "browse functions",
+            Action::CloseFunctions => "close",
+            Action::SearchFunctions => "search",
+            Action::InsertFunction => "insert call",
+            Action::ExportTrace => "save query trace",
+            Action::PrepareQuery => "run with parameters",
+            Action::ExportSubstrait => "save Substrait plan",
+            Action::RunSubstraitFile => "run Substrait plan file",
+            Action::RunSubstrait => "run as Substrait",
+            Action::SubmitPrompt => "submit",
+            Action::CancelPrompt => "cancel",
+            Action::LogsFocus => "focus logs",
+            Action::LogsHide => "hide logs",
+            Action::LogsCaptureMore => "capture more",
+            Action::LogsCaptureLess => "capture less",
+            Action::LogsToggleHidden => "toggle hidden targets",
+            Action::LogsExitPageMode => "exit page mode",
+            Action::LogsNextTarget => "select next target",
+            Action::LogsPreviousTarget => "select previous target",
+            Action::LogsShowMore => "show more",
+            Action::LogsShowLess => "show less",
+            Action::LogsPageDown => "page down",
+            Action::LogsPageUp => "page up",
+        }
+    }
+
+    /// The tab this action navigates to, if it is a tab selection action
+    pub fn selected_tab(&self) -> Option<SelectedTab> {
+        match self {
+            Action::SelectSqlTab => Some(SelectedTab::SQL),
+            #[cfg(feature = "flightsql")]
+            Action::SelectFlightsqlTab => Some(SelectedTab::FlightSQL),
+            Action::SelectHistoryTab => Some(SelectedTab::History),
+            Action::SelectLogsTab => Some(SelectedTab::Logs),
+            Action::SelectContextTab => Some(SelectedTab::Context),
+            _ => None,
+        }
+    }
+
+    pub fn select_tab(tab: SelectedTab) -> Action {
+        match tab {
+            SelectedTab::SQL => Action::SelectSqlTab,
+            #[cfg(feature = "flightsql")]
+            SelectedTab::FlightSQL => Action::SelectFlightsqlTab,
+            SelectedTab::History => Action::SelectHistoryTab,
+            SelectedTab::Logs => Action::SelectLogsTab,
+            SelectedTab::Context => Action::SelectContextTab,
+        }
+    }
+}
+
+/// A key plus the modifiers that must be held with it, e.g. `ctrl+enter`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let relevant = KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT;
+        let mut modifiers = modifiers & relevant;
+        // The shift state of characters is already reflected in the character itself
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+fn parse_key_code(key: &str) -> Result<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    let code = match key.to_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "space" => KeyCode::Char(' '),
+        "plus" => KeyCode::Char('+'),
+        "minus" => KeyCode::Char('-'),
+        lower => match lower.strip_prefix('f').map(|n| n.parse::<u8>()) {
+            Some(Ok(n)) if (1..=24).contains(&n) => KeyCode::F(n),
+            _ => return Err(eyre!("Unknown key '{}'", key)),
+        },
+    };
+    Ok(code)
+}
+
+impl FromStr for KeyChord {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        // A trailing '+' is the plus key itself, e.g. "+" or "ctrl++"
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if s == "+" => ("", "+"),
+            None => match s.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", s),
+            },
+        };
+        let mut parsed_modifiers = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => parsed_modifiers |= KeyModifiers::CONTROL,
+                "alt" | "meta" => parsed_modifiers |= KeyModifiers::ALT,
+                "shift" => parsed_modifiers |= KeyModifiers::SHIFT,
+                _ => return Err(eyre!("Unknown modifier '{}' in '{}'", modifier, s)),
+            }
+        }
+        Ok(KeyChord::new(parse_key_code(key)?, parsed_modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Bindings of a single scope, kept in definition order so generated help is stable
+type Bindings = Vec<(Action, Vec<KeyChord>)>;
+
+/// Default bindings of a scope, given as `(tab, mode, [(action, [chord])])`
+type DefaultBindings = (
+    &'static str,
+    &'static str,
+    &'static [(Action, &'static [&'static str])],
+);
+
+const DEFAULT_BINDINGS: &[DefaultBindings] = &[
+    (
+        "global",
+        "normal",
+        &[
+            (Action::Quit, &["q"]),
+            (Action::SelectSqlTab, &["1"]),
+            (Action::SelectFlightsqlTab, &["2"]),
+            (Action::SelectHistoryTab, &["3"]),
+            (Action::SelectLogsTab, &["4"]),
+            (Action::SelectContextTab, &["5"]),
+        ],
+    ),
+    (
+        "sql",
+        "normal",
+        &[
+            (Action::Edit, &["e"]),
+            (Action::ClearEditor, &["c"]),
+            (Action::RunQuery, &["enter"]),
+            (Action::SelectNextRow, &["down"]),
+            (Action::SelectPreviousRow, &["up"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+            (Action::PageDown, &["pagedown"]),
+            (Action::PageUp, &["pageup"]),
+            (Action::GoToRow, &[":"]),
+            (Action::SelectNextColumn, &["right", "l"]),
+            (Action::SelectPreviousColumn, &["left", "h"]),
+            (Action::FreezeColumns, &["f"]),
+            (Action::SortColumn, &["s"]),
+            (Action::FilterResults, &["w"]),
+            (Action::Search, &["/"]),
+            (Action::NextMatch, &["n"]),
+            (Action::PreviousMatch, &["N"]),
+            (Action::CopyCell, &["y"]),
+            (Action::CopyRow, &["Y"]),
+            (Action::CopyResults, &["ctrl+y"]),
+            (Action::NextCopyFormat, &["t"]),
+            (Action::ToggleChart, &["p"]),
+            (Action::EditChart, &["P"]),
+            (Action::OpenDetail, &["v"]),
+            (Action::ProfileResults, &["i"]),
+            (Action::ProfileTable, &["I"]),
+            (Action::Diff, &["D"]),
+            (Action::ExportSubstrait, &["x"]),
+            (Action::RunSubstraitFile, &["o"]),
+            (Action::OpenFunctions, &["F"]),
+            (Action::ExportTrace, &["T"]),
+        ],
+    ),
+    (
+        "sql",
+        "edit",
+        &[
+            (Action::ExitEdit, &["esc"]),
+            (Action::RunQuery, &["ctrl+enter"]),
+            (Action::PreviousWord, &["alt+left"]),
+            (Action::NextWord, &["alt+right"]),
+            (Action::DeleteWord, &["alt+backspace"]),
+        ],
+    ),
+    (
+        "flightsql",
+        "normal",
+        &[
+            (Action::Edit, &["e"]),
+            (Action::ClearEditor, &["c"]),
+            (Action::RunQuery, &["enter"]),
+            (Action::SelectNextRow, &["down"]),
+            (Action::SelectPreviousRow, &["up"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+            (Action::PageDown, &["pagedown"]),
+            (Action::PageUp, &["pageup"]),
+            (Action::GoToRow, &[":"]),
+            (Action::SelectNextColumn, &["right", "l"]),
+            (Action::SelectPreviousColumn, &["left", "h"]),
+            (Action::FreezeColumns, &["f"]),
+            (Action::SortColumn, &["s"]),
+            (Action::FilterResults, &["w"]),
+            (Action::Search, &["/"]),
+            (Action::NextMatch, &["n"]),
+            (Action::PreviousMatch, &["N"]),
+            (Action::CopyCell, &["y"]),
+            (Action::CopyRow, &["Y"]),
+            (Action::CopyResults, &["ctrl+y"]),
+            (Action::NextCopyFormat, &["t"]),
+            (Action::ToggleChart, &["p"]),
+            (Action::EditChart, &["P"]),
+            (Action::OpenDetail, &["v"]),
+            (Action::ProfileResults, &["i"]),
+            (Action::OpenConnections, &["C"]),
+            (Action::OpenMetadata, &["M"]),
+            (Action::PrepareQuery, &["b"]),
+            (Action::ExportSubstrait, &["x"]),
+            (Action::RunSubstraitFile, &["o"]),
+            (Action::RunSubstrait, &["S"]),
+        ],
+    ),
+    (
+        "flightsql",
+        "edit",
+        &[
+            (Action::ExitEdit, &["esc"]),
+            (Action::PreviousWord, &["alt+left"]),
+            (Action::NextWord, &["alt+right"]),
+            (Action::DeleteWord, &["alt+backspace"]),
+        ],
+    ),
+    (
+        "history",
+        "normal",
+        &[
+            (Action::SelectNextRow, &["down"]),
+            (Action::SelectPreviousRow, &["up"]),
+            (Action::MarkDiff, &["m"]),
+            (Action::Diff, &["d"]),
+        ],
+    ),
+    (
+        "logs",
+        "normal",
+        &[
+            (Action::LogsFocus, &["f"]),
+            (Action::LogsHide, &["h"]),
+            (Action::LogsNextTarget, &["down"]),
+            (Action::LogsPreviousTarget, &["up"]),
+            (Action::LogsShowMore, &["right"]),
+            (Action::LogsShowLess, &["left"]),
+            (Action::LogsCaptureMore, &["+"]),
+            (Action::LogsCaptureLess, &["-"]),
+            (Action::LogsToggleHidden, &["space"]),
+            (Action::LogsExitPageMode, &["esc"]),
+            (Action::LogsPageDown, &["pagedown"]),
+            (Action::LogsPageUp, &["pageup"]),
+        ],
+    ),
+    (
+        "context",
+        "normal",
+        &[
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+            (Action::ToggleExpand, &["space", "right", "l"]),
+            (Action::InsertName, &["enter"]),
+            (Action::PreviewTable, &["p"]),
+            (Action::RefreshMetadata, &["r"]),
+            (Action::OpenSettings, &["c"]),
+        ],
+    ),
+    (
+        "prompt",
+        "prompt",
+        &[
+            (Action::SubmitPrompt, &["enter"]),
+            (Action::CancelPrompt, &["esc"]),
+        ],
+    ),
+    (
+        "detail",
+        "detail",
+        &[
+            (Action::CloseDetail, &["esc", "v"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+            (Action::ToggleExpand, &["enter", "space"]),
+            (Action::PageDown, &["pagedown"]),
+            (Action::PageUp, &["pageup"]),
+        ],
+    ),
+    (
+        "profile",
+        "profile",
+        &[
+            (Action::CloseProfile, &["esc", "i"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+            (Action::PageDown, &["pagedown"]),
+            (Action::PageUp, &["pageup"]),
+            (Action::SelectNextColumn, &["right", "l"]),
+            (Action::SelectPreviousColumn, &["left", "h"]),
+        ],
+    ),
+    (
+        "connections",
+        "connections",
+        &[
+            (Action::Connect, &["enter"]),
+            (Action::CloseConnections, &["esc", "C"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+        ],
+    ),
+    (
+        "metadata",
+        "metadata",
+        &[
+            (Action::InsertName, &["enter"]),
+            (Action::ToggleExpand, &["space", "right", "l"]),
+            (Action::RefreshMetadata, &["r"]),
+            (Action::CloseMetadata, &["esc", "M"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+        ],
+    ),
+    (
+        "settings",
+        "settings",
+        &[
+            (Action::EditSetting, &["enter"]),
+            (Action::FilterSettings, &["/"]),
+            (Action::SaveSettings, &["s"]),
+            (Action::CloseSettings, &["esc", "c"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+        ],
+    ),
+    (
+        "functions",
+        "functions",
+        &[
+            (Action::InsertFunction, &["enter"]),
+            (Action::SearchFunctions, &["/"]),
+            (Action::CloseFunctions, &["esc", "F"]),
+            (Action::SelectNextRow, &["down", "j"]),
+            (Action::SelectPreviousRow, &["up", "k"]),
+            (Action::SelectFirstRow, &["g", "home"]),
+            (Action::SelectLastRow, &["G", "end"]),
+        ],
+    ),
+];
+
+/// Resolve a `(tab, mode)` pair from config naming. Returns `None` for tabs that are not
+/// compiled in (e.g. `flightsql` without the feature) so their bindings are ignored.
+fn scope_from_names(tab: &str, mode: &str) -> Option<Scope> {
+    let mode = Mode::iter().find(|m| m.as_str() == mode)?;
+    if tab == "global" {
+        return Some(Scope::Global);
+    }
+    if !mode.per_tab() {
+        return Some(Scope::Shared(mode));
+    }
+    SelectedTab::iter()
+        .find(|t| t.keymap_name() == tab)
+        .map(|t| Scope::Tab(t, mode))
+}
+
+fn parse_bindings(
+    scope: Scope,
+    config: &HashMap<String, KeyBindingConfig>,
+    bindings: &mut Bindings,
+) -> Result<()> {
+    // Sort so that errors are reported deterministically
+    let mut entries: Vec<_> = config.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, binding) in entries {
+        let action = Action::from_str(name)
+            .map_err(|_| eyre!("Unknown action '{}' in {} keymap", name, scope))?;
+        let chords = binding
+            .chords()
+            .into_iter()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>>>()?;
+        match bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, existing)) => *existing = chords,
+            None => bindings.push((action, chords)),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: HashMap<Scope, Bindings>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::try_from_config(&KeymapConfig::default()).expect("Default keymap should be valid")
+    }
+}
+
+impl Keymap {
+    /// Build the keymap from the defaults overlaid with user configuration, validating that
+    /// no key chord triggers more than one action in any tab and mode.
+    pub fn try_from_config(config: &KeymapConfig) -> Result<Self> {
+        let mut bindings: HashMap<Scope, Bindings> = HashMap::new();
+        for (tab, mode, defaults) in DEFAULT_BINDINGS {
+            if let Some(scope) = scope_from_names(tab, mode) {
+                let scope_bindings = defaults
+                    .iter()
+                    .map(|(action, chords)| {
+                        let chords = chords
+                            .iter()
+                            .map(|c| KeyChord::from_str(c))
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok((*action, chords))
+                    })
+                    .collect::<Result<Bindings>>()?;
+                bindings.insert(scope, scope_bindings);
+            }
+        }
+
+        let tab_configs: [(&str, &TabKeymapConfig); 5] = [
+            ("sql", &config.sql),
+            ("flightsql", &config.flightsql),
+            ("history", &config.history),
+            ("logs", &config.logs),
+            ("context", &config.context),
+        ];
+        parse_bindings(
+            Scope::Global,
+            &config.global,
+            bindings.entry(Scope::Global).or_default(),
+        )?;
+        for (tab, tab_config) in tab_configs {
+            for (mode, mode_config) in [("normal", &tab_config.normal), ("edit", &tab_config.edit)]
+            {
+                if let Some(scope) = scope_from_names(tab, mode) {
+                    parse_bindings(scope, mode_config, bindings.entry(scope).or_default())?;
+                }
+            }
+        }
+        let shared_configs = [
+            (Mode::Prompt, &config.prompt),
+            (Mode::Detail, &config.detail),
+            (Mode::Profile, &config.profile),
+            (Mode::Connections, &config.connections),
+            (Mode::Metadata, &config.metadata),
+            (Mode::Settings, &config.settings),
+            (Mode::Functions, &config.functions),
+        ];
+        for (mode, mode_config) in shared_configs {
+            let scope = Scope::Shared(mode);
+            parse_bindings(scope, mode_config, bindings.entry(scope).or_default())?;
+        }
+
+        let keymap = Self { bindings };
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    fn effective_bindings(
+        &self,
+        tab: SelectedTab,
+        mode: Mode,
+    ) -> impl Iterator<Item = &(Action, Vec<KeyChord>)> {
+        let scope = if mode.per_tab() {
+            Scope::Tab(tab, mode)
+        } else {
+            Scope::Shared(mode)
+        };
+        let tab_bindings = self.bindings.get(&scope).into_iter().flatten();
+        let global_bindings = match mode {
+            Mode::Normal => self.bindings.get(&Scope::Global),
+            _ => None,
+        };
+        tab_bindings.chain(global_bindings.into_iter().flatten())
+    }
+
+    fn check_conflicts(&self) -> Result<()> {
+        for tab in SelectedTab::iter() {
+            for mode in Mode::iter() {
+                let mut seen: HashMap<KeyChord, Action> = HashMap::new();
+                for (action, chords) in self.effective_bindings(tab, mode) {
+                    for chord in chords {
+                        match seen.get(chord) {
+                            Some(other) if other != action => {
+                                return Err(eyre!(
+                                    "Key '{}' is bound to both '{}' and '{}' in {} keymap",
+                                    chord,
+                                    other.name(),
+                                    action.name(),
+                                    if mode.per_tab() {
+                                        Scope::Tab(tab, mode)
+                                    } else {
+                                        Scope::Shared(mode)
+                                    }
+                                ));
+                            }
+                            _ => {
+                                seen.insert(*chord, *action);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The action bound to `key` in the given tab and mode, if any
+    pub fn action(&self, tab: SelectedTab, mode: Mode, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from(key);
+        self.effective_bindings(tab, mode)
+            .find(|(_, chords)| chords.contains(&chord))
+            .map(|(action, _)| *action)
+    }
+
+    /// The chords bound to `action` in the given tab and mode
+    pub fn chords(&self, tab: SelectedTab, mode: Mode, action: Action) -> Vec<KeyChord> {
+        self.effective_bindings(tab, mode)
+            .filter(|(a, _)| *a == action)
+            .flat_map(|(_, chords)| chords.iter().copied())
+            .collect()
+    }
+
+    /// Help entries such as `'e' to edit` for each of `actions` that is bound to a key
+    pub fn help(&self, tab: SelectedTab, mode: Mode, actions: &[Action]) -> Vec<String> {
+        actions
+            .iter()
+            .filter_map(|action| {
+                let chords = self.chords(tab, mode, *action);
+                if chords.is_empty() {
+                    None
+                } else {
+                    let keys: Vec<String> = chords.iter().map(|c| c.to_string()).collect();
+                    Some(format!("'{}' to {}", keys.join("/"), action.description()))
+                }
+            })
+            .collect()
+    }
+
+    /// The key used to select `tab`, as shown in the tab title
+    pub fn tab_key(&self, tab: SelectedTab) -> Option<KeyChord> {
+        self.bindings
+            .get(&Scope::Global)
+            .into_iter()
+            .flatten()
+            .find(|(action, _)| *action == Action::select_tab(tab))
+            .and_then(|(_, chords)| chords.first().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Action, KeyChord, Keymap, Mode};
+    use crate::{app::config::KeymapConfig, ui::SelectedTab};
+
+    #[test]
+    fn parse_key_chords_test() {
+        assert_eq!(
+            KeyChord::from_str("q").unwrap(),
+            KeyChord::new(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::from_str("Ctrl+Enter").unwrap(),
+            KeyChord::new(KeyCode::Enter, KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            KeyChord::from_str("+").unwrap(),
+            KeyChord::new(KeyCode::Char('+'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            KeyChord::from_str("alt++").unwrap(),
+            KeyChord::new(KeyCode::Char('+'), KeyModifiers::ALT)
+        );
+        assert_eq!(
+            KeyChord::from_str("f5").unwrap(),
+            KeyChord::new(KeyCode::F(5), KeyModifiers::NONE)
+        );
+        assert!(KeyChord::from_str("hyper+x").is_err());
+        assert!(KeyChord::from_str("nope").is_err());
+    }
+
+    #[test]
+    fn default_keymap_test() {
+        let keymap = Keymap::default();
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.action(SelectedTab::SQL, Mode::Normal, &q),
+            Some(Action::Quit)
+        );
+        // Global bindings do not apply while editing
+        assert_eq!(keymap.action(SelectedTab::SQL, Mode::Edit, &q), None);
+        let run = KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL);
+        assert_eq!(
+            keymap.action(SelectedTab::SQL, Mode::Edit, &run),
+            Some(Action::RunQuery)
+        );
+        assert_eq!(
+            keymap.help(
+                SelectedTab::SQL,
+                Mode::Normal,
+                &[Action::Edit, Action::RunQuery]
+            ),
+            vec!["'e' to edit", "'Enter' to run query"]
+        );
+    }
+
+    #[test]
+    fn keymap_overrides_test() {
+        let config: KeymapConfig = toml::from_str(
+            r#"
+            [global]
+            quit = ["ctrl+c", "Q"]
+
+            [sql.normal]
+            run_query = "r"
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::try_from_config(&config).unwrap();
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(SelectedTab::SQL, Mode::Normal, &q), None);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keymap.action(SelectedTab::History, Mode::Normal, &ctrl_c),
+            Some(Action::Quit)
+        );
+        let shift_q = KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action(SelectedTab::Logs, Mode::Normal, &shift_q),
+            Some(Action::Quit)
+        );
+        let r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.action(SelectedTab::SQL, Mode::Normal, &r),
+            Some(Action::RunQuery)
+        );
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(keymap.action(SelectedTab::SQL, Mode::Normal, &enter), None);
+    }
+
+    #[test]
+    fn keymap_conflicts_test() {
+        let config: KeymapConfig = toml::from_str(
+            r#"
+            [sql.normal]
+            clear_editor = "q"
+            "#,
+        )
+        .unwrap();
+        let err = Keymap::try_from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("'q'"), "{}", err);
+
+        let config: KeymapConfig = toml::from_str(
+            r#"
+            [sql.normal]
+            not_an_action = "x"
+            "#,
+        )
+        .unwrap();
+        assert!(Keymap::try_from_config(&config).is_err());
+    }
+}
@@ -15,9 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod clipboard;
 pub mod config;
 pub mod execution;
+#[cfg(feature = "flightsql")]
+pub mod federation;
 pub mod handlers;
+pub mod instrument;
+pub mod keymap;
+pub mod resources;
 pub mod state;
 
 use crate::cli::DftCli;
@@ -32,7 +38,7 @@ use ratatui::crossterm::{
     self, cursor, event,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{prelude::*, style::palette::tailwind, widgets::*};
+use ratatui::{prelude::*, widgets::*};
 use strum::IntoEnumIterator;
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
@@ -41,10 +47,20 @@ use tokio_util::sync::CancellationToken;
 
 use self::execution::ExecutionContext;
 use self::handlers::{app_event_handler, crossterm_event_handler};
+use self::state::diff::DiffResults;
+use self::state::metadata::Metadata;
+use self::state::profile::ProfileResults;
 use self::state::tabs::sql::Query;
+use self::state::view::FilteredResults;
 
+#[cfg(feature = "flightsql")]
+use self::execution::{FlightSQLConnection, FlightSQLProgress};
+#[cfg(feature = "flightsql")]
+use self::state::metadata::flightsql::PrimaryKeys;
 #[cfg(feature = "flightsql")]
 use self::state::tabs::flightsql::FlightSQLQuery;
+#[cfg(feature = "flightsql")]
+use self::state::tabs::flightsql::PreparedQuery;
 
 #[derive(Clone, Debug)]
 pub enum AppEvent {
@@ -62,10 +78,29 @@ pub enum AppEvent {
     Resize(u16, u16),
     ExecuteDDL(String),
     QueryResult(Query),
+    FilteredResults(FilteredResults),
+    ProfileResults(ProfileResults),
+    DiffResults(DiffResults),
+    /// The catalog of the session context, for the Context tab
+    Catalog(Metadata),
+    /// Rows previewed from a table of the catalog
+    TablePreview(Query),
     #[cfg(feature = "flightsql")]
-    EstablishFlightSQLConnection,
+    /// Connect to the configured FlightSQL connection at the index
+    EstablishFlightSQLConnection(usize),
     #[cfg(feature = "flightsql")]
     FlightSQLQueryResult(FlightSQLQuery),
+    #[cfg(feature = "flightsql")]
+    FlightSQLProgress(FlightSQLProgress),
+    #[cfg(feature = "flightsql")]
+    FlightSQLConnected(FlightSQLConnection),
+    #[cfg(feature = "flightsql")]
+    FlightSQLMetadata(Metadata),
+    #[cfg(feature = "flightsql")]
+    /// The primary keys of a table in the metadata browser, requested when it was expanded
+    FlightSQLPrimaryKeys(PrimaryKeys),
+    #[cfg(feature = "flightsql")]
+    FlightSQLPrepared(PreparedQuery),
 }
 
 pub struct App<'app> {
@@ -86,6 +121,16 @@ impl<'app> App<'app> {
         let task = tokio::spawn(async {});
         let streams_task = tokio::spawn(async {});
         let execution = ExecutionContext::new(state.config.execution.clone());
+        #[cfg(feature = "flightsql")]
+        execution.register_flightsql_tables(
+            state
+                .config
+                .flightsql
+                .connections()
+                .into_iter()
+                .cloned()
+                .collect(),
+        );
 
         Self {
             cli,
@@ -246,7 +291,7 @@ impl<'app> App<'app> {
     pub fn establish_flightsql_connection(&self) {
         let _ = self
             .app_event_tx
-            .send(AppEvent::EstablishFlightSQLConnection);
+            .send(AppEvent::EstablishFlightSQLConnection(0));
     }
 
     /// Dispatch to the appropriate event loop based on the command
@@ -269,8 +314,9 @@ impl<'app> App<'app> {
     }
 
     fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
-        let titles = ui::SelectedTab::iter().map(ui::SelectedTab::title);
-        let highlight_style = (Color::default(), tailwind::ORANGE.c500);
+        let titles =
+            ui::SelectedTab::iter().map(|tab| tab.title(&self.state.keymap, &self.state.theme));
+        let highlight_style = self.state.theme.tab_highlight;
         let selected_tab_index = self.state.tabs.selected as usize;
         Tabs::new(titles)
             .highlight_style(highlight_style)
@@ -286,13 +332,30 @@ impl Widget for &App<'_> {
     /// on every frame based on application state. There is no permanent widget object
     /// in memory.
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let vertical = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
-        let [header_area, inner_area] = vertical.areas(area);
-
-        let horizontal = Layout::horizontal([Constraint::Min(0)]);
-        let [tabs_area] = horizontal.areas(header_area);
+        let vertical = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]);
+        let [header_area, inner_area, status_area] = vertical.areas(area);
+
+        #[cfg(feature = "flightsql")]
+        let tabs_area = {
+            let status = ui::tabs::flightsql::connection_status(self);
+            let horizontal = Layout::horizontal([
+                Constraint::Min(0),
+                Constraint::Length(status.width() as u16),
+            ]);
+            let [tabs_area, status_area] = horizontal.areas(header_area);
+            status.right_aligned().render(status_area, buf);
+            tabs_area
+        };
+        #[cfg(not(feature = "flightsql"))]
+        let [tabs_area] = Layout::horizontal([Constraint::Min(0)]).areas(header_area);
         self.render_tabs(tabs_area, buf);
         self.state.tabs.selected.render(inner_area, buf, self);
+        ui::resources::status_line(&self.state.resources, &self.state.theme)
+            .render(status_area, buf);
     }
 }
 
@@ -324,6 +387,8 @@ pub async fn run_app(cli: cli::DftCli, state: state::AppState<'_>) -> Result<()>
                     break;
                 }
             }
+            #[cfg(feature = "flightsql")]
+            execution::close_prepared(&app.execution.flightsql_prepared).await;
             app.exit()?;
         }
     }
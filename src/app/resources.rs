@@ -0,0 +1,338 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Memory, spill and process resources used by the local session
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use datafusion::common::Result as DFResult;
+use datafusion::execution::disk_manager::DiskManagerConfig;
+use datafusion::execution::memory_pool::{
+    FairSpillPool, GreedyMemoryPool, MemoryConsumer, MemoryPool, MemoryReservation,
+    UnboundedMemoryPool,
+};
+use datafusion::execution::runtime_env::RuntimeConfig;
+use log::error;
+use tempfile::TempDir;
+
+use super::config::{MemoryConfig, MemoryPoolKind};
+
+/// How often the process statistics and spill files are read, which is slower than the memory
+/// pool as it touches the filesystem
+const PROCESS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Linux reports process CPU time in clock ticks of 1/100th of a second
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+/// Memory reserved by the consumers sharing a name, e.g. every partition of a sort
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsumerUsage {
+    pub consumers: usize,
+    pub reserved: usize,
+}
+
+/// Memory pool that records the reservations of each consumer of the pool it wraps
+#[derive(Debug)]
+pub struct TrackedMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    limit: Option<usize>,
+    consumers: Mutex<BTreeMap<String, ConsumerUsage>>,
+}
+
+impl TrackedMemoryPool {
+    pub fn new(inner: Arc<dyn MemoryPool>, limit: Option<usize>) -> Self {
+        Self {
+            inner,
+            limit,
+            consumers: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The registered consumers, by name
+    pub fn consumers(&self) -> BTreeMap<String, ConsumerUsage> {
+        self.consumers.lock().unwrap().clone()
+    }
+
+    fn update(&self, consumer: &MemoryConsumer, f: impl FnOnce(&mut ConsumerUsage)) {
+        let mut consumers = self.consumers.lock().unwrap();
+        let usage = consumers.entry(consumer.name().to_string()).or_default();
+        f(usage);
+        if usage.consumers == 0 {
+            consumers.remove(consumer.name());
+        }
+    }
+}
+
+impl MemoryPool for TrackedMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer);
+        self.update(consumer, |usage| usage.consumers += 1);
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer);
+        self.update(consumer, |usage| {
+            usage.consumers = usage.consumers.saturating_sub(1)
+        });
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        self.update(reservation.consumer(), |usage| usage.reserved += additional);
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink);
+        self.update(reservation.consumer(), |usage| {
+            usage.reserved = usage.reserved.saturating_sub(shrink)
+        });
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> DFResult<()> {
+        self.inner.try_grow(reservation, additional)?;
+        self.update(reservation.consumer(), |usage| usage.reserved += additional);
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner.reserved()
+    }
+}
+
+/// Decrements the number of active queries when the query it was started for finishes
+pub struct QueryGuard(Arc<AtomicUsize>);
+
+impl Drop for QueryGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The memory pool, spill directory and running queries of the local session
+#[derive(Debug)]
+pub struct ResourceMonitor {
+    pool: Arc<TrackedMemoryPool>,
+    /// Directory that the disk manager creates its spill files in, removed on exit
+    spill_dir: Option<TempDir>,
+    active_queries: Arc<AtomicUsize>,
+}
+
+impl ResourceMonitor {
+    pub fn new(config: &MemoryConfig) -> Self {
+        let limit = config.limit_bytes().unwrap_or_else(|e| {
+            error!("Error reading memory limit, memory is unlimited: {}", e);
+            None
+        });
+        let inner: Arc<dyn MemoryPool> = match (limit, config.pool) {
+            (Some(limit), MemoryPoolKind::Greedy) => Arc::new(GreedyMemoryPool::new(limit)),
+            (Some(limit), MemoryPoolKind::Fair) => Arc::new(FairSpillPool::new(limit)),
+            (None, _) => Arc::new(UnboundedMemoryPool::default()),
+        };
+        let spill_root = config.spill_dir.clone().unwrap_or_else(std::env::temp_dir);
+        let spill_dir = tempfile::Builder::new()
+            .prefix("dft-spill-")
+            .tempdir_in(&spill_root)
+            .map_err(|e| error!("Error creating spill directory in {:?}: {}", spill_root, e))
+            .ok();
+        Self {
+            pool: Arc::new(TrackedMemoryPool::new(inner, limit)),
+            spill_dir,
+            active_queries: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Runtime config using the tracked memory pool and spill directory
+    pub fn runtime_config(&self) -> RuntimeConfig {
+        let pool: Arc<dyn MemoryPool> = Arc::<TrackedMemoryPool>::clone(&self.pool);
+        let config = RuntimeConfig::new().with_memory_pool(pool);
+        match &self.spill_dir {
+            Some(dir) => config.with_disk_manager(DiskManagerConfig::new_specified(vec![dir
+                .path()
+                .to_path_buf()])),
+            None => config,
+        }
+    }
+
+    /// Count a query as active until the returned guard is dropped
+    pub fn start_query(&self) -> QueryGuard {
+        self.active_queries.fetch_add(1, Ordering::Relaxed);
+        QueryGuard(Arc::clone(&self.active_queries))
+    }
+
+    pub fn active_queries(&self) -> usize {
+        self.active_queries.load(Ordering::Relaxed)
+    }
+
+    pub fn pool(&self) -> &TrackedMemoryPool {
+        &self.pool
+    }
+
+    /// Bytes in the spill files currently on disk
+    pub fn spilled_bytes(&self) -> Option<usize> {
+        self.spill_dir.as_ref().map(|dir| dir_size(dir.path()))
+    }
+}
+
+fn dir_size(path: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len() as usize,
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// CPU time used by the process so far, in seconds
+fn process_cpu_time() -> Option<f64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name can contain spaces, so fields are counted from the end of it. `utime`
+    // and `stime` are the 14th and 15th fields.
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) / CLOCK_TICKS_PER_SECOND)
+}
+
+/// Resident set size of the process in bytes
+fn process_rss() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let rss = status.lines().find_map(|l| l.strip_prefix("VmRSS:"))?;
+    let kb: usize = rss.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Snapshot of the resources in use, refreshed on every tick
+#[derive(Clone, Debug, Default)]
+pub struct ResourceUsage {
+    pub reserved: usize,
+    pub limit: Option<usize>,
+    pub consumers: BTreeMap<String, ConsumerUsage>,
+    pub active_queries: usize,
+    /// `None` when spilling to disk is not tracked
+    pub spilled: Option<usize>,
+    /// Process statistics are only available on Linux
+    pub rss: Option<usize>,
+    /// Percentage of a single core used since the previous refresh
+    pub cpu: Option<f64>,
+    /// When the process statistics were last read, with the CPU time at the time
+    process_sample: Option<(Instant, Option<f64>)>,
+}
+
+impl ResourceUsage {
+    pub fn refresh(&mut self, monitor: &ResourceMonitor) {
+        let pool = monitor.pool();
+        self.reserved = pool.reserved();
+        self.limit = pool.limit();
+        self.consumers = pool.consumers();
+        self.active_queries = monitor.active_queries();
+
+        let now = Instant::now();
+        let previous = match self.process_sample {
+            Some((at, _)) if now.duration_since(at) < PROCESS_REFRESH_INTERVAL => return,
+            Some((at, cpu_time)) => Some((at, cpu_time)),
+            None => None,
+        };
+        let cpu_time = process_cpu_time();
+        self.cpu = match (previous, cpu_time) {
+            (Some((at, Some(previous))), Some(current)) => {
+                Some((current - previous) / now.duration_since(at).as_secs_f64() * 100.0)
+            }
+            _ => None,
+        };
+        self.rss = process_rss();
+        self.spilled = monitor.spilled_bytes();
+        self.process_sample = Some((now, cpu_time));
+    }
+
+    /// Fraction of the memory limit that is reserved
+    pub fn memory_ratio(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.reserved as f64 / limit as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::execution::memory_pool::{MemoryConsumer, MemoryPool};
+
+    use super::{ConsumerUsage, ResourceMonitor, ResourceUsage};
+    use crate::app::config::{MemoryConfig, MemoryPoolKind};
+
+    #[test]
+    fn resource_monitor_test() {
+        let config = MemoryConfig {
+            limit: Some("1KB".to_string()),
+            pool: MemoryPoolKind::Greedy,
+            spill_dir: None,
+        };
+        let monitor = ResourceMonitor::new(&config);
+        let pool: Arc<dyn MemoryPool> = monitor.runtime_config().memory_pool.unwrap();
+        let mut first = MemoryConsumer::new("ExternalSorter[0]").register(&pool);
+        let mut second = MemoryConsumer::new("ExternalSorter[0]").register(&pool);
+        first.try_grow(600).unwrap();
+        second.grow(100);
+        assert!(second.try_grow(400).is_err());
+
+        let query = monitor.start_query();
+        let mut usage = ResourceUsage::default();
+        usage.refresh(&monitor);
+        assert_eq!((usage.reserved, usage.limit), (700, Some(1024)));
+        assert_eq!(usage.active_queries, 1);
+        assert_eq!(usage.spilled, Some(0));
+        assert_eq!(
+            usage.consumers.get("ExternalSorter[0]"),
+            Some(&ConsumerUsage {
+                consumers: 2,
+                reserved: 700
+            })
+        );
+
+        drop(query);
+        drop(first);
+        second.shrink(50);
+        usage.refresh(&monitor);
+        assert_eq!(usage.reserved, 50);
+        assert_eq!(usage.active_queries, 0);
+        assert_eq!(
+            usage.consumers.get("ExternalSorter[0]"),
+            Some(&ConsumerUsage {
+                consumers: 1,
+                reserved: 50
+            })
+        );
+
+        drop(second);
+        usage.refresh(&monitor);
+        assert!(usage.consumers.is_empty());
+    }
+}
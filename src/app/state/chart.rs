@@ -0,0 +1,288 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use datafusion::arrow::{
+    array::{Array, AsArray, RecordBatch},
+    compute::cast,
+    datatypes::{DataType, Float64Type},
+    util::display::ArrayFormatter,
+};
+use strum::{EnumIter, EnumString, IntoStaticStr};
+
+use crate::app::config::DisplayConfig;
+use crate::ui::convert::{format_options, localize_timestamps};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum ChartKind {
+    Bar,
+    Line,
+    Scatter,
+}
+
+/// Which result columns are plotted and how. Columns are referred to by name so that the chart
+/// still applies when the query is run again.
+///
+/// Charts are written as `<kind> <x column> <y column>[,<y column>...]`, for example
+/// `line hour requests,errors`, with one series for every Y column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChartConfig {
+    pub kind: ChartKind,
+    pub x: String,
+    pub y: Vec<String>,
+}
+
+impl FromStr for ChartConfig {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split_whitespace();
+        let (Some(kind), Some(x), Some(y), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(eyre!(
+                "Expected '<bar|line|scatter> <x column> <y column>[,<y column>...]', got '{}'",
+                s
+            ));
+        };
+        let kind = ChartKind::from_str(kind).map_err(|_| eyre!("Unknown chart type '{}'", kind))?;
+        let y: Vec<String> = y
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        if y.is_empty() {
+            return Err(eyre!("At least one Y column is required"));
+        }
+        Ok(Self {
+            kind,
+            x: x.to_string(),
+            y,
+        })
+    }
+}
+
+impl Display for ChartConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind: &str = self.kind.into();
+        write!(f, "{} {} {}", kind, self.x, self.y.join(","))
+    }
+}
+
+/// A result column as numbers that can be plotted, with the formatted value of every row for
+/// labels. Temporal values are plotted by their underlying integer and categorical values by the
+/// order they first appear in.
+#[derive(Debug, PartialEq)]
+pub struct PlotColumn {
+    pub values: Vec<Option<f64>>,
+    pub labels: Vec<String>,
+}
+
+fn plot_column(batches: &[RecordBatch], name: &str, config: &DisplayConfig) -> Result<PlotColumn> {
+    let options = format_options(config);
+    let mut values = Vec::new();
+    let mut labels = Vec::new();
+    let mut categories: HashMap<String, f64> = HashMap::new();
+    for batch in batches {
+        let column = batch
+            .column_by_name(name)
+            .ok_or_else(|| eyre!("There is no '{}' column in the results", name))?;
+        let column = localize_timestamps(column, config);
+        let formatter = ArrayFormatter::try_new(column.as_ref(), &options)?;
+        let start = labels.len();
+        labels.extend((0..column.len()).map(|i| formatter.value(i).to_string()));
+
+        let data_type = column.data_type();
+        if data_type.is_numeric() || data_type.is_temporal() {
+            let numbers = if data_type.is_temporal() {
+                cast(&cast(&column, &DataType::Int64)?, &DataType::Float64)?
+            } else {
+                cast(&column, &DataType::Float64)?
+            };
+            values.extend(numbers.as_primitive::<Float64Type>().iter());
+        } else {
+            for (i, label) in labels[start..].iter().enumerate() {
+                if column.is_null(i) {
+                    values.push(None);
+                } else {
+                    let next = categories.len() as f64;
+                    values.push(Some(*categories.entry(label.clone()).or_insert(next)));
+                }
+            }
+        }
+    }
+    Ok(PlotColumn { values, labels })
+}
+
+/// The height bar chart values are scaled to, so that fractional values keep their proportions
+/// when drawn as whole numbers
+pub const BAR_SCALE: f64 = 1000.0;
+
+/// The columns plotted by a chart. They are computed when the chart or the results it plots
+/// change, so that drawing the chart doesn't touch every row on each frame.
+#[derive(Debug, PartialEq)]
+pub struct ChartData {
+    pub x: PlotColumn,
+    pub ys: Vec<(String, PlotColumn)>,
+    /// For bar charts, the height of each row's bars relative to the largest value, up to
+    /// [`BAR_SCALE`]
+    pub bar_heights: Vec<Vec<u64>>,
+}
+
+fn bar_heights(ys: &[(String, PlotColumn)], rows: usize) -> Vec<Vec<u64>> {
+    let max = ys
+        .iter()
+        .flat_map(|(_, y)| y.values.iter().flatten())
+        .fold(0.0, |max: f64, v| max.max(*v));
+    (0..rows)
+        .map(|row| {
+            ys.iter()
+                .map(|(_, y)| match y.values[row] {
+                    Some(v) if max > 0.0 => (v / max * BAR_SCALE).round() as u64,
+                    _ => 0,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl ChartData {
+    pub fn try_new(
+        batches: &[RecordBatch],
+        chart: &ChartConfig,
+        config: &DisplayConfig,
+    ) -> Result<Self> {
+        let x = plot_column(batches, &chart.x, config)?;
+        let ys = chart
+            .y
+            .iter()
+            .map(|name| Ok((name.clone(), plot_column(batches, name, config)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if chart.kind == ChartKind::Bar {
+            for (name, y) in &ys {
+                let negative = y.values.iter().position(|v| v.is_some_and(|v| v < 0.0));
+                if let Some(row) = negative {
+                    return Err(eyre!(
+                        "Bar charts can't show negative values, '{}' is {} in row {}",
+                        name,
+                        y.labels[row],
+                        row + 1
+                    ));
+                }
+            }
+        }
+        let bar_heights = match chart.kind {
+            ChartKind::Bar => bar_heights(&ys, x.values.len()),
+            _ => Vec::new(),
+        };
+        Ok(Self { x, ys, bar_heights })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{ArrayRef, Date32Array, Float64Array, RecordBatch, StringArray};
+
+    use super::{plot_column, ChartConfig, ChartData, ChartKind, PlotColumn};
+    use crate::app::config::DisplayConfig;
+
+    #[test]
+    fn parse_chart_config_test() {
+        let chart = ChartConfig::from_str(" Line  hour requests,errors ").unwrap();
+        assert_eq!(
+            chart,
+            ChartConfig {
+                kind: ChartKind::Line,
+                x: "hour".to_string(),
+                y: vec!["requests".to_string(), "errors".to_string()],
+            }
+        );
+        assert_eq!(chart.to_string(), "line hour requests,errors");
+        assert!(ChartConfig::from_str("pie a b").is_err());
+        assert!(ChartConfig::from_str("bar a").is_err());
+        assert!(ChartConfig::from_str("bar a b c").is_err());
+    }
+
+    #[test]
+    fn plot_column_test() {
+        let day: ArrayRef = Arc::new(Date32Array::from(vec![Some(0), Some(1), None]));
+        let region: ArrayRef = Arc::new(StringArray::from(vec!["eu", "us", "eu"]));
+        let total: ArrayRef = Arc::new(Float64Array::from(vec![1.5, 2.0, 3.0]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("day", day), ("region", region), ("total", total)])
+                .unwrap();
+        let batches = [batch];
+        let config = DisplayConfig::default();
+
+        assert_eq!(
+            plot_column(&batches, "day", &config).unwrap(),
+            PlotColumn {
+                values: vec![Some(0.0), Some(1.0), None],
+                labels: vec!["1970-01-01".into(), "1970-01-02".into(), "NULL".into()],
+            }
+        );
+        assert_eq!(
+            plot_column(&batches, "region", &config).unwrap().values,
+            vec![Some(0.0), Some(1.0), Some(0.0)]
+        );
+        assert_eq!(
+            plot_column(&batches, "total", &config).unwrap().values,
+            vec![Some(1.5), Some(2.0), Some(3.0)]
+        );
+        assert!(plot_column(&batches, "missing", &config).is_err());
+    }
+
+    #[test]
+    fn chart_data_test() {
+        let region: ArrayRef = Arc::new(StringArray::from(vec!["eu", "us"]));
+        let change: ArrayRef = Arc::new(Float64Array::from(vec![1.5, -2.0]));
+        let batches =
+            [RecordBatch::try_from_iter(vec![("region", region), ("change", change)]).unwrap()];
+        let config = DisplayConfig::default();
+
+        let line = ChartConfig::from_str("line region change").unwrap();
+        let data = ChartData::try_new(&batches, &line, &config).unwrap();
+        assert_eq!(data.x.labels, vec!["eu", "us"]);
+        assert_eq!(data.ys[0].1.values, vec![Some(1.5), Some(-2.0)]);
+        assert!(data.bar_heights.is_empty());
+
+        let bar = ChartConfig {
+            kind: ChartKind::Bar,
+            ..line
+        };
+        let error = ChartData::try_new(&batches, &bar, &config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bar charts can't show negative values, 'change' is -2.0 in row 2"
+        );
+
+        let share: ArrayRef = Arc::new(Float64Array::from(vec![Some(0.25), None, Some(0.5)]));
+        let batches = [RecordBatch::try_from_iter(vec![("share", share)]).unwrap()];
+        let bar = ChartConfig::from_str("bar share share").unwrap();
+        let data = ChartData::try_new(&batches, &bar, &config).unwrap();
+        assert_eq!(data.bar_heights, vec![vec![500], vec![0], vec![1000]]);
+    }
+}
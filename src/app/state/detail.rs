@@ -0,0 +1,320 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use color_eyre::Result;
+use datafusion::arrow::{
+    array::{Array, ArrayRef, AsArray, RecordBatch},
+    datatypes::DataType,
+    util::display::{ArrayFormatter, FormatOptions},
+};
+
+use crate::{
+    app::config::DisplayConfig,
+    ui::convert::{format_options, localize_timestamps},
+};
+
+/// A value shown in the record detail view. Struct, List and Map values have a child for each
+/// of their fields, items or entries.
+#[derive(Debug)]
+pub struct DetailNode {
+    label: String,
+    value: String,
+    summary: Option<String>,
+    is_null: bool,
+    children: Vec<DetailNode>,
+    expanded: bool,
+}
+
+impl DetailNode {
+    fn try_new(
+        label: String,
+        array: &ArrayRef,
+        idx: usize,
+        config: &DisplayConfig,
+        options: &FormatOptions,
+    ) -> Result<Self> {
+        let array = localize_timestamps(array, config);
+        let value = ArrayFormatter::try_new(array.as_ref(), options)?
+            .value(idx)
+            .to_string();
+        let mut node = Self {
+            label,
+            value,
+            summary: None,
+            is_null: array.is_null(idx),
+            children: Vec::new(),
+            expanded: false,
+        };
+        if node.is_null {
+            return Ok(node);
+        }
+
+        match array.data_type() {
+            DataType::Struct(fields) => {
+                let array = array.as_struct();
+                for (field, column) in fields.iter().zip(array.columns()) {
+                    let child = Self::try_new(field.name().clone(), column, idx, config, options)?;
+                    node.children.push(child);
+                }
+                node.summary = Some(format!("Struct ({} fields)", fields.len()));
+            }
+            DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(_, _) => {
+                let items = match array.data_type() {
+                    DataType::List(_) => array.as_list::<i32>().value(idx),
+                    DataType::LargeList(_) => array.as_list::<i64>().value(idx),
+                    _ => array.as_fixed_size_list().value(idx),
+                };
+                for i in 0..items.len() {
+                    let child = Self::try_new(format!("[{}]", i), &items, i, config, options)?;
+                    node.children.push(child);
+                }
+                node.summary = Some(format!("List ({} items)", items.len()));
+            }
+            DataType::Map(_, _) => {
+                let entries = array.as_map().value(idx);
+                let keys = ArrayFormatter::try_new(entries.column(0).as_ref(), options)?;
+                for i in 0..entries.len() {
+                    let label = keys.value(i).to_string();
+                    let child = Self::try_new(label, entries.column(1), i, config, options)?;
+                    node.children.push(child);
+                }
+                node.summary = Some(format!("Map ({} entries)", entries.len()));
+            }
+            _ => {}
+        }
+        Ok(node)
+    }
+
+    /// Column name, field name, list index or map key of the value
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The full formatted value
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Short description of nested values, shown in the tree in place of the value
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.is_null
+    }
+
+    pub fn has_children(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    pub fn expanded(&self) -> bool {
+        self.expanded
+    }
+}
+
+/// Pushes the visible nodes of the tree with their depth, in display order
+fn visible_nodes<'a>(
+    nodes: &'a [DetailNode],
+    depth: usize,
+    out: &mut Vec<(usize, &'a DetailNode)>,
+) {
+    for node in nodes {
+        out.push((depth, node));
+        if node.expanded {
+            visible_nodes(&node.children, depth + 1, out);
+        }
+    }
+}
+
+/// The `n`th visible node of the tree, counting down `n` as nodes are passed
+fn nth_visible_mut<'a>(nodes: &'a mut [DetailNode], n: &mut usize) -> Option<&'a mut DetailNode> {
+    for node in nodes {
+        if *n == 0 {
+            return Some(node);
+        }
+        *n -= 1;
+        if node.expanded {
+            if let Some(found) = nth_visible_mut(&mut node.children, n) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Vertical view of a single result row, listing every column with its full value
+#[derive(Debug)]
+pub struct RecordDetail {
+    row: usize,
+    nodes: Vec<DetailNode>,
+    selected: usize,
+    value_scroll: u16,
+}
+
+impl RecordDetail {
+    /// Build the view of row `idx` of `batch`, which is row `row` of the results
+    pub fn try_new(
+        batch: &RecordBatch,
+        idx: usize,
+        row: usize,
+        config: &DisplayConfig,
+    ) -> Result<Self> {
+        let options = format_options(config);
+        let nodes = batch
+            .schema_ref()
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| {
+                DetailNode::try_new(field.name().clone(), column, idx, config, &options)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            row,
+            nodes,
+            selected: 0,
+            value_scroll: 0,
+        })
+    }
+
+    /// The row of the results that is shown
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// The visible nodes with their depth in the tree
+    pub fn lines(&self) -> Vec<(usize, &DetailNode)> {
+        let mut lines = Vec::new();
+        visible_nodes(&self.nodes, 0, &mut lines);
+        lines
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_node(&self) -> Option<&DetailNode> {
+        self.lines().get(self.selected).map(|(_, node)| *node)
+    }
+
+    /// How far the full value of the selected node has been scrolled
+    pub fn value_scroll(&self) -> u16 {
+        self.value_scroll
+    }
+
+    fn select(&mut self, line: usize) {
+        self.selected = line.min(self.lines().len().saturating_sub(1));
+        self.value_scroll = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(usize::MAX);
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        let mut n = self.selected;
+        if let Some(node) = nth_visible_mut(&mut self.nodes, &mut n) {
+            if node.has_children() {
+                node.expanded = !node.expanded;
+            }
+        }
+    }
+
+    pub fn scroll_value_down(&mut self, lines: u16) {
+        self.value_scroll = self.value_scroll.saturating_add(lines);
+    }
+
+    pub fn scroll_value_up(&mut self, lines: u16) {
+        self.value_scroll = self.value_scroll.saturating_sub(lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::{
+        array::{ArrayRef, Int32Array, ListArray, RecordBatch, StringArray, StructArray},
+        datatypes::{DataType, Field, Int32Type},
+    };
+
+    use super::RecordDetail;
+    use crate::app::config::DisplayConfig;
+
+    #[test]
+    fn nested_record_detail_test() {
+        let id: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
+        let tags: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            Some(vec![Some(2), None]),
+        ]));
+        let name: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        let user: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("name", DataType::Utf8, false)),
+            name,
+        )]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("id", id), ("tags", tags), ("user", user)]).unwrap();
+
+        let mut detail = RecordDetail::try_new(&batch, 1, 1, &DisplayConfig::default()).unwrap();
+        let lines: Vec<_> = detail
+            .lines()
+            .iter()
+            .map(|(d, n)| (*d, n.label()))
+            .collect();
+        assert_eq!(lines, vec![(0, "id"), (0, "tags"), (0, "user")]);
+
+        detail.select_next();
+        assert_eq!(detail.selected_node().unwrap().value(), "[2, NULL]");
+        assert_eq!(
+            detail.selected_node().unwrap().summary(),
+            Some("List (2 items)")
+        );
+        detail.toggle_expanded();
+        detail.select_last();
+        detail.toggle_expanded();
+        let lines: Vec<_> = detail
+            .lines()
+            .iter()
+            .map(|(d, n)| (*d, n.label(), n.value(), n.is_null()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (0, "id", "2", false),
+                (0, "tags", "[2, NULL]", false),
+                (1, "[0]", "2", false),
+                (1, "[1]", "NULL", true),
+                (0, "user", "{name: b}", false),
+                (1, "name", "b", false),
+            ]
+        );
+    }
+}
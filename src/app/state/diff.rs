@@ -0,0 +1,579 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use datafusion::{
+    arrow::{
+        array::{AsArray, RecordBatch, UInt32Array},
+        compute::{cast, take_record_batch},
+        datatypes::{DataType, Int64Type},
+    },
+    dataframe::DataFrame,
+    functions::{core::expr_fn::coalesce, math::expr_fn::abs, string::expr_fn::concat_ws},
+    functions_aggregate::expr_fn::{count, sum},
+    logical_expr::{binary_expr, when, JoinType, Operator},
+    prelude::{ident, lit, Expr},
+};
+use log::info;
+
+use crate::app::state::tabs::history::{Context, HistoryQuery};
+
+/// Name of the column holding how a row differs
+const STATUS_COLUMN: &str = "diff";
+const CHANGED_COLUMN: &str = "changed columns";
+const LEFT_ONLY: &str = "left only";
+const RIGHT_ONLY: &str = "right only";
+const CHANGED: &str = "changed";
+const UNCHANGED: &str = "unchanged";
+/// Name of the column holding how many more times a row is on one side than the other
+const COPIES_COLUMN: &str = "copies";
+
+/// One side of a diff, either an entry of the query history (`#3`) or a table name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffSource {
+    /// 1-based index into the query history, as shown on the History tab
+    History(usize),
+    Table(String),
+}
+
+impl FromStr for DiffSource {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix('#') {
+            Some(entry) => entry
+                .parse()
+                .map(DiffSource::History)
+                .map_err(|_| eyre!("Invalid history entry '{}'", s)),
+            None => Ok(DiffSource::Table(s.to_string())),
+        }
+    }
+}
+
+impl Display for DiffSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffSource::History(entry) => write!(f, "#{}", entry),
+            DiffSource::Table(table) => write!(f, "{}", table),
+        }
+    }
+}
+
+/// What is read for one side of a diff
+#[derive(Clone, Debug)]
+pub enum DiffInput {
+    /// A query that is run again in the context it was run in, on the FlightSQL connection it
+    /// was run on if recorded
+    Query(Context, String, Option<String>),
+    Table(String),
+}
+
+impl DiffSource {
+    pub fn input(&self, history: &[HistoryQuery]) -> Result<DiffInput> {
+        match self {
+            DiffSource::History(entry) => entry
+                .checked_sub(1)
+                .and_then(|idx| history.get(idx))
+                .map(|q| DiffInput::Query(*q.context(), q.sql().clone(), q.connection().clone()))
+                .ok_or_else(|| eyre!("There is no history entry #{}", entry)),
+            DiffSource::Table(table) => Ok(DiffInput::Table(table.clone())),
+        }
+    }
+}
+
+/// The two sides of a diff and the key columns rows are matched on. Diffs are written as
+/// `<left> <right> [<key>[,<key>...]]`, for example `#1 #2 id` or `orders orders_v2`. Without
+/// keys whole rows are compared, so changed rows show up as only in the left and right sides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffSpec {
+    pub left: DiffSource,
+    pub right: DiffSource,
+    pub keys: Vec<String>,
+}
+
+impl FromStr for DiffSpec {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split_whitespace();
+        let (Some(left), Some(right), keys, None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(eyre!(
+                "Expected '<left> <right> [<key>[,<key>...]]', got '{}'",
+                s
+            ));
+        };
+        let keys = keys
+            .unwrap_or_default()
+            .split(',')
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            left: left.parse()?,
+            right: right.parse()?,
+            keys,
+        })
+    }
+}
+
+impl Display for DiffSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.left, self.right)?;
+        if !self.keys.is_empty() {
+            write!(f, " {}", self.keys.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of rows of each kind in a diff
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    pub left_only: usize,
+    pub right_only: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+impl Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} left only, {} right only, {} changed, {} unchanged",
+            self.left_only, self.right_only, self.changed, self.unchanged
+        )
+    }
+}
+
+/// Rows that differ between the two sides of a diff, sent back from the task that computes it
+#[derive(Clone, Debug)]
+pub struct DiffResults {
+    pub spec: DiffSpec,
+    pub summary: DiffSummary,
+    pub batches: Vec<RecordBatch>,
+    pub elapsed: Duration,
+}
+
+fn side_column(column: &str, side: &str) -> String {
+    format!("{} ({})", column, side)
+}
+
+fn column_names(df: &DataFrame) -> Vec<String> {
+    df.schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect()
+}
+
+/// Rename every column of `df` to `<column> (<side>)`, adding a marker column so that rows
+/// missing from this side of an outer join can be told apart from rows with NULL values
+fn with_side(df: DataFrame, side: &str) -> Result<DataFrame> {
+    let mut columns: Vec<Expr> = column_names(&df)
+        .iter()
+        .map(|c| ident(c).alias(side_column(c, side)))
+        .collect();
+    columns.push(lit(true).alias(side));
+    Ok(df.select(columns)?)
+}
+
+/// Count the rows of each status in the differing rows, given the number of rows on the left
+/// to work out how many are unchanged
+fn summarize(batches: &[RecordBatch], left_rows: usize) -> Result<DiffSummary> {
+    let mut summary = DiffSummary::default();
+    for batch in batches {
+        let statuses = batch
+            .column_by_name(STATUS_COLUMN)
+            .ok_or_else(|| eyre!("Diff results have no '{}' column", STATUS_COLUMN))?
+            .as_string::<i32>();
+        for status in statuses.iter() {
+            match status {
+                Some(LEFT_ONLY) => summary.left_only += 1,
+                Some(RIGHT_ONLY) => summary.right_only += 1,
+                Some(CHANGED) => summary.changed += 1,
+                _ => {}
+            }
+        }
+    }
+    // Every row on the left is either only on the left, changed or unchanged
+    summary.unchanged = left_rows.saturating_sub(summary.left_only + summary.changed);
+    Ok(summary)
+}
+
+/// Full outer join of both sides on `keys`, keeping the rows that are only on one side or
+/// have a different value in any other column. Each side is run once, as the join is cached
+/// before the differing rows are collected.
+async fn diff_by_key(
+    left: DataFrame,
+    right: DataFrame,
+    keys: &[String],
+) -> Result<(DiffSummary, Vec<RecordBatch>)> {
+    let left_columns = column_names(&left);
+    let right_columns = column_names(&right);
+    for key in keys {
+        if !left_columns.contains(key) || !right_columns.contains(key) {
+            return Err(eyre!("Key column '{}' must be on both sides", key));
+        }
+    }
+    let compared: Vec<&String> = left_columns
+        .iter()
+        .filter(|c| !keys.contains(c) && right_columns.contains(c))
+        .collect();
+
+    let on = keys
+        .iter()
+        .map(|k| ident(side_column(k, "left")).eq(ident(side_column(k, "right"))));
+    let joined =
+        with_side(left, "left")?.join_on(with_side(right, "right")?, JoinType::Full, on)?;
+
+    let differs = |c: &String| {
+        binary_expr(
+            ident(side_column(c, "left")),
+            Operator::IsDistinctFrom,
+            ident(side_column(c, "right")),
+        )
+    };
+    let any_changed = compared
+        .iter()
+        .map(|c| differs(c))
+        .reduce(Expr::or)
+        .unwrap_or(lit(false));
+    let status = when(ident("right").is_null(), lit(LEFT_ONLY))
+        .when(ident("left").is_null(), lit(RIGHT_ONLY))
+        .when(any_changed, lit(CHANGED))
+        .otherwise(lit(UNCHANGED))?;
+    let changed_columns = if compared.is_empty() {
+        lit("")
+    } else {
+        let names = compared
+            .iter()
+            .map(|c| when(differs(c), lit(c.as_str())).end())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        concat_ws(lit(", "), names)
+    };
+    let with_status = joined.with_column(STATUS_COLUMN, status)?.cache().await?;
+    let left_rows = with_status
+        .clone()
+        .filter(ident("left").is_not_null())?
+        .count()
+        .await?;
+
+    let mut columns = vec![
+        ident(STATUS_COLUMN),
+        when(ident(STATUS_COLUMN).eq(lit(CHANGED)), changed_columns)
+            .end()?
+            .alias(CHANGED_COLUMN),
+    ];
+    for key in keys {
+        let values = vec![
+            ident(side_column(key, "left")),
+            ident(side_column(key, "right")),
+        ];
+        columns.push(coalesce(values).alias(key));
+    }
+    for column in left_columns.iter().filter(|c| !keys.contains(c)) {
+        columns.push(ident(side_column(column, "left")));
+        if right_columns.contains(column) {
+            columns.push(ident(side_column(column, "right")));
+        }
+    }
+    for column in right_columns
+        .iter()
+        .filter(|c| !keys.contains(c) && !left_columns.contains(c))
+    {
+        columns.push(ident(side_column(column, "right")));
+    }
+    let sort = keys.iter().map(|k| ident(k).sort(true, true)).collect();
+    let batches = with_status
+        .filter(ident(STATUS_COLUMN).not_eq(lit(UNCHANGED)))?
+        .select(columns)?
+        .sort(sort)?
+        .collect()
+        .await?;
+    Ok((summarize(&batches, left_rows)?, batches))
+}
+
+/// Repeat each row of `batch` as many times as its `copies` column, which is then dropped
+fn repeat_rows(batch: &RecordBatch) -> Result<RecordBatch> {
+    let copies = batch
+        .column_by_name(COPIES_COLUMN)
+        .ok_or_else(|| eyre!("Diff results have no '{}' column", COPIES_COLUMN))?
+        .as_primitive::<Int64Type>();
+    let indices: UInt32Array = copies
+        .iter()
+        .enumerate()
+        .flat_map(|(row, copies)| (0..copies.unwrap_or(0)).map(move |_| row as u32))
+        .collect();
+    let mut batch = take_record_batch(batch, &indices)?;
+    batch.remove_column(batch.schema().index_of(COPIES_COLUMN)?);
+    Ok(batch)
+}
+
+/// The sum of the counts in `column` of `df`
+async fn total(df: DataFrame, column: &str) -> Result<usize> {
+    let batches = df
+        .aggregate(vec![], vec![sum(ident(column))])?
+        .collect()
+        .await?;
+    let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+        return Ok(0);
+    };
+    let sum = cast(batch.column(0), &DataType::Int64)?;
+    Ok(sum.as_primitive::<Int64Type>().value(0).max(0) as usize)
+}
+
+/// Compare whole rows, keeping the rows of each side that are not on the other. Rows are
+/// compared as multisets: each side is grouped by every compared column and counted, so a row
+/// that is on the left twice and on the right once is shown once as only on the left. Each side
+/// is run once, as its counts are cached.
+async fn diff_rows(left: DataFrame, right: DataFrame) -> Result<(DiffSummary, Vec<RecordBatch>)> {
+    let right_columns = column_names(&right);
+    let compared: Vec<String> = column_names(&left)
+        .into_iter()
+        .filter(|c| right_columns.contains(c))
+        .collect();
+    if compared.is_empty() {
+        return Err(eyre!("The two sides have no columns in common"));
+    }
+    if compared.len() < right_columns.len() || compared.len() < left.schema().fields().len() {
+        info!("Only comparing the common columns {}", compared.join(", "));
+    }
+    let columns: Vec<Expr> = compared.iter().map(ident).collect();
+
+    // The number of times each distinct row is on a side, with the columns renamed by side
+    let counted = |df: DataFrame, side: &str| -> Result<DataFrame> {
+        let mut renamed: Vec<Expr> = compared
+            .iter()
+            .map(|c| ident(c).alias(side_column(c, side)))
+            .collect();
+        renamed.push(ident(side));
+        Ok(df
+            .aggregate(columns.clone(), vec![count(lit(1)).alias(side)])?
+            .select(renamed)?)
+    };
+    let left = counted(left, "left")?.cache().await?;
+    let right = counted(right, "right")?.cache().await?;
+    let left_rows = total(left.clone(), "left").await?;
+
+    // NULLs are matched like any other value, as they are when grouping
+    let on = compared.iter().map(|c| {
+        binary_expr(
+            ident(side_column(c, "left")),
+            Operator::IsNotDistinctFrom,
+            ident(side_column(c, "right")),
+        )
+    });
+    let difference =
+        coalesce(vec![ident("left"), lit(0_i64)]) - coalesce(vec![ident("right"), lit(0_i64)]);
+    let mut selected = vec![
+        when(difference.clone().gt(lit(0_i64)), lit(LEFT_ONLY))
+            .otherwise(lit(RIGHT_ONLY))?
+            .alias(STATUS_COLUMN),
+        abs(difference.clone()).alias(COPIES_COLUMN),
+    ];
+    for column in &compared {
+        let values = vec![
+            ident(side_column(column, "left")),
+            ident(side_column(column, "right")),
+        ];
+        selected.push(coalesce(values).alias(column));
+    }
+    let mut sort = vec![ident(STATUS_COLUMN).sort(true, true)];
+    sort.extend(columns.iter().map(|c| c.clone().sort(true, true)));
+    let batches = left
+        .join_on(right, JoinType::Full, on)?
+        .filter(difference.not_eq(lit(0_i64)))?
+        .select(selected)?
+        .sort(sort)?
+        .collect()
+        .await?
+        .iter()
+        .map(repeat_rows)
+        .collect::<Result<Vec<_>>>()?;
+    Ok((summarize(&batches, left_rows)?, batches))
+}
+
+/// Find the rows that differ between `left` and `right`, matching rows on `keys` when given
+pub async fn diff_frames(
+    left: DataFrame,
+    right: DataFrame,
+    keys: &[String],
+) -> Result<(DiffSummary, Vec<RecordBatch>)> {
+    if keys.is_empty() {
+        diff_rows(left, right).await
+    } else {
+        diff_by_key(left, right, keys).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use datafusion::arrow::{
+        array::{ArrayRef, Int32Array, RecordBatch, StringArray},
+        util::pretty::pretty_format_batches,
+    };
+    use datafusion::prelude::SessionContext;
+
+    use super::{diff_frames, DiffSource, DiffSpec, DiffSummary};
+
+    #[test]
+    fn parse_diff_spec_test() {
+        let spec = DiffSpec::from_str("#1  orders_v2 id,region").unwrap();
+        assert_eq!(
+            spec,
+            DiffSpec {
+                left: DiffSource::History(1),
+                right: DiffSource::Table("orders_v2".to_string()),
+                keys: vec!["id".to_string(), "region".to_string()],
+            }
+        );
+        assert_eq!(spec.to_string(), "#1 orders_v2 id,region");
+        assert!(DiffSpec::from_str("#1 #2").unwrap().keys.is_empty());
+        assert!(DiffSpec::from_str("#x #2").is_err());
+        assert!(DiffSpec::from_str("orders").is_err());
+    }
+
+    fn batch(ids: Vec<i32>, names: Vec<Option<&str>>) -> RecordBatch {
+        let ids: ArrayRef = Arc::new(Int32Array::from(ids));
+        let names: ArrayRef = Arc::new(StringArray::from(names));
+        RecordBatch::try_from_iter(vec![("id", ids), ("name", names)]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn diff_frames_test() {
+        let ctx = SessionContext::new();
+        let left = batch(
+            vec![1, 2, 3, 4],
+            vec![Some("a"), Some("b"), None, Some("d")],
+        );
+        let right = batch(
+            vec![2, 3, 4, 5],
+            vec![Some("b"), Some("c"), Some("d"), Some("e")],
+        );
+
+        let (summary, batches) = diff_frames(
+            ctx.read_batch(left.clone()).unwrap(),
+            ctx.read_batch(right.clone()).unwrap(),
+            &["id".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            DiffSummary {
+                left_only: 1,
+                right_only: 1,
+                changed: 1,
+                unchanged: 2,
+            }
+        );
+        let expected = [
+            "+------------+-----------------+----+-------------+--------------+",
+            "| diff       | changed columns | id | name (left) | name (right) |",
+            "+------------+-----------------+----+-------------+--------------+",
+            "| left only  |                 | 1  | a           |              |",
+            "| changed    | name            | 3  |             | c            |",
+            "| right only |                 | 5  |             | e            |",
+            "+------------+-----------------+----+-------------+--------------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+
+        let (summary, batches) = diff_frames(
+            ctx.read_batch(left).unwrap(),
+            ctx.read_batch(right).unwrap(),
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            DiffSummary {
+                left_only: 2,
+                right_only: 2,
+                changed: 0,
+                unchanged: 2,
+            }
+        );
+        let expected = [
+            "+------------+----+------+",
+            "| diff       | id | name |",
+            "+------------+----+------+",
+            "| left only  | 1  | a    |",
+            "| left only  | 3  |      |",
+            "| right only | 3  | c    |",
+            "| right only | 5  | e    |",
+            "+------------+----+------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn diff_duplicated_rows_test() {
+        let ctx = SessionContext::new();
+        let left = batch(
+            vec![1, 1, 1, 2, 3],
+            vec![Some("a"), Some("a"), Some("a"), None, None],
+        );
+        let right = batch(vec![1, 2, 2, 3], vec![Some("a"), None, None, None]);
+
+        let (summary, batches) = diff_frames(
+            ctx.read_batch(left).unwrap(),
+            ctx.read_batch(right).unwrap(),
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            DiffSummary {
+                left_only: 2,
+                right_only: 1,
+                changed: 0,
+                unchanged: 3,
+            }
+        );
+        let expected = [
+            "+------------+----+------+",
+            "| diff       | id | name |",
+            "+------------+----+------+",
+            "| left only  | 1  | a    |",
+            "| left only  | 1  | a    |",
+            "| right only | 2  |      |",
+            "+------------+----+------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reference of the scalar, aggregate, window and table functions registered with the session
+
+use std::collections::BTreeSet;
+
+use datafusion::{
+    arrow::datatypes::DataType,
+    common::Result as DFResult,
+    execution::session_state::SessionState,
+    logical_expr::{BuiltInWindowFunction, Signature, TypeSignature, Volatility},
+};
+use strum::IntoEnumIterator;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FunctionKind {
+    Scalar,
+    Aggregate,
+    Window,
+    Table,
+}
+
+impl FunctionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FunctionKind::Scalar => "scalar",
+            FunctionKind::Aggregate => "aggregate",
+            FunctionKind::Window => "window",
+            FunctionKind::Table => "table",
+        }
+    }
+}
+
+/// A function, with the argument types it accepts and what it returns for them
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub kind: FunctionKind,
+    /// The argument lists accepted, e.g. `Utf8, Int64`
+    pub signatures: Vec<String>,
+    /// The return types for the argument types in the signatures, where they can be worked out
+    /// without the arguments themselves
+    pub return_type: Option<String>,
+    pub volatility: Option<Volatility>,
+    pub aliases: Vec<String>,
+}
+
+fn join_types(types: &[DataType], delimiter: &str) -> String {
+    types
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(delimiter)
+}
+
+/// The argument lists of a signature, as `information_schema` would show them
+fn signature_text(signature: &TypeSignature) -> Vec<String> {
+    match signature {
+        TypeSignature::Variadic(types) => vec![format!("{}, ..", join_types(types, "/"))],
+        TypeSignature::Uniform(count, types) => {
+            vec![vec![join_types(types, "/"); *count].join(", ")]
+        }
+        TypeSignature::Numeric(count) => vec![vec!["Numeric"; *count].join(", ")],
+        TypeSignature::Exact(types) => vec![join_types(types, ", ")],
+        TypeSignature::Any(count) => vec![vec!["Any"; *count].join(", ")],
+        TypeSignature::UserDefined => vec!["..".to_string()],
+        TypeSignature::VariadicAny => vec!["Any, ..".to_string()],
+        TypeSignature::OneOf(signatures) => signatures.iter().flat_map(signature_text).collect(),
+        TypeSignature::ArraySignature(signature) => vec![signature.to_string()],
+    }
+}
+
+/// Argument types that a signature accepts, for working out its return types
+fn example_arguments(signature: &TypeSignature) -> Vec<Vec<DataType>> {
+    match signature {
+        TypeSignature::Exact(types) => vec![types.clone()],
+        TypeSignature::Uniform(count, types) => {
+            types.iter().map(|t| vec![t.clone(); *count]).collect()
+        }
+        TypeSignature::Variadic(types) => types.iter().map(|t| vec![t.clone()]).collect(),
+        TypeSignature::Numeric(count) => vec![
+            vec![DataType::Int64; *count],
+            vec![DataType::Float64; *count],
+        ],
+        TypeSignature::Any(0) => vec![vec![]],
+        TypeSignature::OneOf(signatures) => signatures.iter().flat_map(example_arguments).collect(),
+        _ => vec![],
+    }
+}
+
+fn function_info(
+    name: &str,
+    kind: FunctionKind,
+    signature: &Signature,
+    aliases: &[String],
+    return_type: impl Fn(&[DataType]) -> DFResult<DataType>,
+) -> FunctionInfo {
+    let return_types: BTreeSet<String> = example_arguments(&signature.type_signature)
+        .iter()
+        .filter_map(|args| return_type(args).ok())
+        .map(|t| t.to_string())
+        .collect();
+    FunctionInfo {
+        name: name.to_string(),
+        kind,
+        signatures: signature_text(&signature.type_signature),
+        return_type: (!return_types.is_empty())
+            .then(|| return_types.into_iter().collect::<Vec<_>>().join(" / ")),
+        volatility: Some(signature.volatility),
+        aliases: aliases.to_vec(),
+    }
+}
+
+/// Every function registered with `state`, including DataFusion's built in window functions, by
+/// name. Aliases are listed with the function they refer to.
+pub fn session_functions(state: &SessionState) -> Vec<FunctionInfo> {
+    let mut functions = Vec::new();
+    // Functions are registered under their aliases too
+    for (name, f) in state.scalar_functions() {
+        if name == f.name() {
+            functions.push(function_info(
+                name,
+                FunctionKind::Scalar,
+                f.signature(),
+                f.aliases(),
+                |args| f.inner().return_type(args),
+            ));
+        }
+    }
+    for (name, f) in state.aggregate_functions() {
+        if name == f.name() {
+            functions.push(function_info(
+                name,
+                FunctionKind::Aggregate,
+                f.signature(),
+                f.aliases(),
+                |args| f.return_type(args),
+            ));
+        }
+    }
+    for (name, f) in state.window_functions() {
+        if name == f.name() {
+            functions.push(function_info(
+                name,
+                FunctionKind::Window,
+                f.signature(),
+                f.aliases(),
+                |args| f.return_type(args),
+            ));
+        }
+    }
+    for f in BuiltInWindowFunction::iter() {
+        functions.push(function_info(
+            &f.name().to_lowercase(),
+            FunctionKind::Window,
+            &f.signature(),
+            &[],
+            |args| f.return_type(args),
+        ));
+    }
+    for name in state.table_functions().keys() {
+        functions.push(FunctionInfo {
+            name: name.clone(),
+            kind: FunctionKind::Table,
+            signatures: Vec::new(),
+            return_type: None,
+            volatility: None,
+            aliases: Vec::new(),
+        });
+    }
+    functions.sort_by(|a, b| (&a.name, a.kind).cmp(&(&b.name, b.kind)));
+    functions
+}
+
+impl FunctionInfo {
+    /// Text inserted into the editor to call the function, and the number of characters the
+    /// cursor is moved back by so that it is left between the parentheses
+    pub fn template(&self) -> (String, usize) {
+        match self.kind {
+            FunctionKind::Window => (format!("{}() OVER ()", self.name), 9),
+            _ => (format!("{}()", self.name), 1),
+        }
+    }
+
+    fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.name.to_lowercase().contains(&filter)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.to_lowercase().contains(&filter))
+    }
+}
+
+/// Browser of the functions of the session, searched by name or alias
+#[derive(Clone, Debug, Default)]
+pub struct FunctionBrowser {
+    functions: Vec<FunctionInfo>,
+    filter: Option<String>,
+    /// Index of the selected function among those matching the filter
+    selected: usize,
+}
+
+impl FunctionBrowser {
+    pub fn new(state: &SessionState) -> Self {
+        Self {
+            functions: session_functions(state),
+            ..Default::default()
+        }
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        self.filter = filter;
+        self.selected = 0;
+    }
+
+    /// The functions matching the filter
+    pub fn filtered(&self) -> Vec<&FunctionInfo> {
+        self.functions
+            .iter()
+            .filter(|f| match &self.filter {
+                Some(filter) => f.matches(filter),
+                None => true,
+            })
+            .collect()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_function(&self) -> Option<&FunctionInfo> {
+        self.filtered().get(self.selected).copied()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.filtered().len().saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(usize::MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::{
+        arrow::datatypes::Schema,
+        common::Result as DFResult,
+        datasource::{empty::EmptyTable, function::TableFunctionImpl, TableProvider},
+        logical_expr::{Expr, Volatility},
+        prelude::SessionContext,
+    };
+
+    use super::{FunctionBrowser, FunctionKind};
+
+    struct EmptyFunction;
+
+    impl TableFunctionImpl for EmptyFunction {
+        fn call(&self, _args: &[Expr]) -> DFResult<Arc<dyn TableProvider>> {
+            Ok(Arc::new(EmptyTable::new(Arc::new(Schema::empty()))))
+        }
+    }
+
+    #[test]
+    fn function_browser_test() {
+        let ctx = SessionContext::new();
+        ctx.register_udtf("empty", Arc::new(EmptyFunction));
+        let mut browser = FunctionBrowser::new(&ctx.state());
+        let kinds = [
+            FunctionKind::Scalar,
+            FunctionKind::Aggregate,
+            FunctionKind::Window,
+            FunctionKind::Table,
+        ];
+        for kind in kinds {
+            assert!(browser.filtered().iter().any(|f| f.kind == kind));
+        }
+
+        browser.set_filter(Some("CHARACTER_LENGTH".to_string()));
+        let f = browser.selected_function().unwrap();
+        assert_eq!(
+            (f.name.as_str(), f.kind, f.volatility),
+            (
+                "character_length",
+                FunctionKind::Scalar,
+                Some(Volatility::Immutable)
+            )
+        );
+        assert!(f.aliases.iter().any(|a| a == "length"));
+
+        browser.set_filter(Some("abs".to_string()));
+        let f = browser.selected_function().unwrap();
+        assert_eq!(f.signatures, vec!["Any"]);
+        assert_eq!(f.template(), ("abs()".to_string(), 1));
+
+        browser.set_filter(Some("row_number".to_string()));
+        let f = browser.selected_function().unwrap();
+        assert_eq!(f.return_type.as_deref(), Some("UInt64"));
+        assert_eq!(f.template(), ("row_number() OVER ()".to_string(), 9));
+    }
+}
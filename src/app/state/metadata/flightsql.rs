@@ -0,0 +1,364 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The tree of objects on a FlightSQL server, built from the results of its metadata commands
+
+use arrow_flight::{sql::SqlInfo, IpcMessage};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use datafusion::arrow::{
+    array::{Array, AsArray, RecordBatch},
+    compute::cast,
+    datatypes::{DataType, Schema, UInt32Type},
+    util::display::ArrayFormatter,
+};
+
+use super::{expand_single_path, Metadata, MetadataNode};
+use crate::{app::config::DisplayConfig, ui::convert::format_options};
+
+/// The batches returned by each of the FlightSQL metadata commands. Commands the server doesn't
+/// support are left empty.
+#[derive(Clone, Debug, Default)]
+pub struct MetadataBatches {
+    pub catalogs: Vec<RecordBatch>,
+    pub schemas: Vec<RecordBatch>,
+    /// Tables, with their schemas
+    pub tables: Vec<RecordBatch>,
+    pub types: Vec<RecordBatch>,
+    pub sql_info: Vec<RecordBatch>,
+}
+
+/// The catalog, schema and name of a table on the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableRef {
+    pub catalog: String,
+    pub schema: String,
+    pub table: String,
+}
+
+/// The primary keys of a table, sent back from the task that requests them
+#[derive(Clone, Debug)]
+pub struct PrimaryKeys {
+    pub table: TableRef,
+    pub batches: Vec<RecordBatch>,
+}
+
+impl MetadataNode {
+    /// The child labelled `label`, added with `detail` if there isn't one
+    fn child(&mut self, label: &str, detail: &str) -> &mut MetadataNode {
+        let idx = match self.children.iter().position(|c| c.label == label) {
+            Some(idx) => idx,
+            None => {
+                self.children.push(MetadataNode::new(label, detail));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[idx]
+    }
+}
+
+/// The values of the string column `name` of `batch`, with nulls as empty strings
+fn strings(batch: &RecordBatch, name: &str) -> Result<Vec<String>> {
+    let column = batch
+        .column_by_name(name)
+        .ok_or_else(|| eyre!("Missing '{}' column in metadata", name))?;
+    let column = cast(column, &DataType::Utf8)?;
+    Ok(column
+        .as_string::<i32>()
+        .iter()
+        .map(|v| v.unwrap_or_default().to_string())
+        .collect())
+}
+
+/// Catalogs and schemas without a name are listed under this label
+const UNNAMED: &str = "(default)";
+
+fn label(name: &str) -> &str {
+    if name.is_empty() {
+        UNNAMED
+    } else {
+        name
+    }
+}
+
+/// The name a table is referred to by, qualified with its catalog and schema when they have one
+fn qualified_name(catalog: &str, schema: &str, table: &str) -> String {
+    [catalog, schema, table]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Build a catalog > schema > table > column tree. Primary key columns are marked once the keys
+/// of their table are loaded.
+fn object_tree(batches: &MetadataBatches) -> Result<Vec<MetadataNode>> {
+    let mut root = MetadataNode::new("", "");
+    for batch in &batches.catalogs {
+        for catalog in strings(batch, "catalog_name")? {
+            root.child(label(&catalog), "catalog");
+        }
+    }
+    for batch in &batches.schemas {
+        let catalogs = strings(batch, "catalog_name")?;
+        let schemas = strings(batch, "db_schema_name")?;
+        for (catalog, schema) in catalogs.iter().zip(&schemas) {
+            root.child(label(catalog), "catalog")
+                .child(label(schema), "schema");
+        }
+    }
+
+    for batch in &batches.tables {
+        let catalogs = strings(batch, "catalog_name")?;
+        let schemas = strings(batch, "db_schema_name")?;
+        let tables = strings(batch, "table_name")?;
+        let types = strings(batch, "table_type")?;
+        let table_schemas = batch
+            .column_by_name("table_schema")
+            .map(|c| c.as_binary::<i32>());
+        for (i, (((catalog, schema), table), table_type)) in catalogs
+            .iter()
+            .zip(&schemas)
+            .zip(&tables)
+            .zip(&types)
+            .enumerate()
+        {
+            let node = root
+                .child(label(catalog), "catalog")
+                .child(label(schema), "schema")
+                .child(table, table_type);
+            node.name = Some(qualified_name(catalog, schema, table));
+            let Some(table_schemas) = table_schemas.filter(|s| !s.is_null(i)) else {
+                continue;
+            };
+            let table_schema =
+                Schema::try_from(IpcMessage(table_schemas.value(i).to_vec().into()))?;
+            for field in table_schema.fields() {
+                let mut detail = field.data_type().to_string();
+                if !field.is_nullable() {
+                    detail.push_str(" not null");
+                }
+                let mut column = MetadataNode::new(field.name().clone(), detail);
+                column.name = Some(field.name().clone());
+                node.children.push(column);
+            }
+            node.pending_keys = Some(TableRef {
+                catalog: catalog.clone(),
+                schema: schema.clone(),
+                table: table.clone(),
+            });
+        }
+    }
+    Ok(root.children)
+}
+
+fn types_node(batches: &[RecordBatch]) -> Result<Option<MetadataNode>> {
+    let mut node = MetadataNode::new("Types", "");
+    for batch in batches {
+        for type_name in strings(batch, "type_name")? {
+            let mut child = MetadataNode::new(type_name.clone(), "");
+            child.name = Some(type_name);
+            node.children.push(child);
+        }
+    }
+    node.detail = format!("{} types", node.children.len());
+    Ok(node.has_children().then_some(node))
+}
+
+fn sql_info_node(batches: &[RecordBatch], config: &DisplayConfig) -> Result<Option<MetadataNode>> {
+    let options = format_options(config);
+    let mut node = MetadataNode::new("Server info", "");
+    for batch in batches {
+        let names = batch
+            .column_by_name("info_name")
+            .ok_or_else(|| eyre!("Missing 'info_name' column in SQL info"))?;
+        let names = cast(names, &DataType::UInt32)?;
+        let values = batch
+            .column_by_name("value")
+            .ok_or_else(|| eyre!("Missing 'value' column in SQL info"))?;
+        let formatter = ArrayFormatter::try_new(values.as_ref(), &options)?;
+        for (i, code) in names.as_primitive::<UInt32Type>().iter().enumerate() {
+            let code = code.unwrap_or_default();
+            let name = SqlInfo::try_from(code as i32)
+                .map(|info| info.as_str_name().to_string())
+                .unwrap_or_else(|_| code.to_string());
+            let value = formatter.value(i).to_string();
+            node.children.push(MetadataNode::new(name, value));
+        }
+    }
+    node.detail = format!("{} entries", node.children.len());
+    Ok(node.has_children().then_some(node))
+}
+
+/// Take the tables that are expanded but whose primary keys haven't been requested
+fn take_pending_keys(nodes: &mut [MetadataNode], out: &mut Vec<TableRef>) {
+    for node in nodes.iter_mut().filter(|n| n.expanded) {
+        out.extend(node.pending_keys.take());
+        take_pending_keys(&mut node.children, out);
+    }
+}
+
+fn find_table<'a>(nodes: &'a mut [MetadataNode], name: &str) -> Option<&'a mut MetadataNode> {
+    for node in nodes {
+        if node.has_children() && node.name.as_deref() == Some(name) {
+            return Some(node);
+        }
+        if node.name.is_none() {
+            if let Some(found) = find_table(&mut node.children, name) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+impl Metadata {
+    /// The catalogs, schemas, tables and columns of a FlightSQL server, with its data types and
+    /// server info
+    pub fn try_new(batches: &MetadataBatches, config: &DisplayConfig) -> Result<Self> {
+        let mut nodes = object_tree(batches)?;
+        expand_single_path(&mut nodes);
+        nodes.extend(types_node(&batches.types)?);
+        nodes.extend(sql_info_node(&batches.sql_info, config)?);
+        Ok(Self::new(nodes))
+    }
+
+    /// The tables whose columns are shown but whose primary keys haven't been requested yet,
+    /// which are then counted as requested
+    pub fn take_pending_keys(&mut self) -> Vec<TableRef> {
+        let mut tables = Vec::new();
+        take_pending_keys(&mut self.nodes, &mut tables);
+        tables
+    }
+
+    /// Mark the primary key columns of a table
+    pub fn set_primary_keys(&mut self, keys: &PrimaryKeys) -> Result<()> {
+        let mut columns = Vec::new();
+        for batch in &keys.batches {
+            columns.extend(strings(batch, "column_name")?);
+        }
+        let name = qualified_name(&keys.table.catalog, &keys.table.schema, &keys.table.table);
+        if let Some(table) = find_table(&mut self.nodes, &name) {
+            table.pending_keys = None;
+            for column in &mut table.children {
+                if columns.contains(&column.label) {
+                    column.detail.push_str(", primary key");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_flight::sql::{
+        metadata::{GetCatalogsBuilder, GetTablesBuilder, SqlInfoDataBuilder},
+        SqlInfo,
+    };
+    use std::sync::Arc;
+
+    use datafusion::arrow::{
+        array::{ArrayRef, RecordBatch, StringArray},
+        datatypes::{DataType, Field, Schema},
+    };
+
+    use super::{Metadata, MetadataBatches, PrimaryKeys, TableRef};
+    use crate::app::config::DisplayConfig;
+
+    #[test]
+    fn metadata_tree_test() {
+        let mut catalogs = GetCatalogsBuilder::new();
+        catalogs.append("main");
+        let mut tables = GetTablesBuilder::new(
+            None::<String>,
+            None::<String>,
+            None::<String>,
+            [""; 0],
+            true,
+        );
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        tables
+            .append("main", "public", "users", "TABLE", &schema)
+            .unwrap();
+        let mut sql_info = SqlInfoDataBuilder::new();
+        sql_info.append(SqlInfo::FlightSqlServerName, "server");
+        let batches = MetadataBatches {
+            catalogs: vec![catalogs.build().unwrap()],
+            tables: vec![tables.build().unwrap()],
+            sql_info: vec![sql_info
+                .build()
+                .unwrap()
+                .record_batch(Vec::<u32>::new())
+                .unwrap()],
+            ..Default::default()
+        };
+
+        let mut metadata = Metadata::try_new(&batches, &DisplayConfig::default()).unwrap();
+        let lines: Vec<_> = metadata
+            .lines()
+            .iter()
+            .map(|(d, n)| (*d, n.label(), n.detail()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (0, "main", "catalog"),
+                (1, "public", "schema"),
+                (2, "users", "TABLE"),
+                (0, "Server info", "1 entries"),
+            ]
+        );
+
+        metadata.select_next();
+        metadata.select_next();
+        assert_eq!(
+            metadata.selected_node().unwrap().name(),
+            Some("main.public.users")
+        );
+        metadata.toggle_expanded();
+        let users = TableRef {
+            catalog: "main".to_string(),
+            schema: "public".to_string(),
+            table: "users".to_string(),
+        };
+        assert_eq!(metadata.take_pending_keys(), vec![users.clone()]);
+        assert!(metadata.take_pending_keys().is_empty());
+        let columns: ArrayRef = Arc::new(StringArray::from(vec!["id"]));
+        let keys = PrimaryKeys {
+            table: users,
+            batches: vec![RecordBatch::try_from_iter(vec![("column_name", columns)]).unwrap()],
+        };
+        metadata.set_primary_keys(&keys).unwrap();
+        metadata.select_next();
+        let column = metadata.selected_node().unwrap();
+        assert_eq!(
+            (column.label(), column.detail(), column.name()),
+            ("id", "Int64 not null, primary key", Some("id"))
+        );
+        metadata.select_last();
+        metadata.toggle_expanded();
+        let (_, info) = metadata.lines().last().copied().unwrap();
+        assert_eq!(
+            (info.label(), info.detail()),
+            ("FLIGHT_SQL_SERVER_NAME", "{string_value=server}")
+        );
+    }
+}
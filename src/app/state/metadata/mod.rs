@@ -0,0 +1,233 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Navigable trees of database objects, built from the catalog of the session context or from
+//! the results of a FlightSQL server's metadata commands
+
+#[cfg(feature = "flightsql")]
+pub mod flightsql;
+pub mod session;
+
+/// A catalog, schema, table or column. Tables and columns have the name that is inserted into
+/// the editor when they are picked, and `properties` such as the location of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetadataNode {
+    label: String,
+    detail: String,
+    name: Option<String>,
+    properties: Vec<(String, String)>,
+    children: Vec<MetadataNode>,
+    expanded: bool,
+    /// A FlightSQL table whose primary keys haven't been requested yet
+    #[cfg(feature = "flightsql")]
+    pending_keys: Option<flightsql::TableRef>,
+}
+
+impl MetadataNode {
+    fn new(label: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            detail: detail.into(),
+            name: None,
+            properties: Vec::new(),
+            children: Vec::new(),
+            expanded: false,
+            #[cfg(feature = "flightsql")]
+            pending_keys: None,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Type of the object, e.g. the table type or column data type
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    /// The name to insert into the editor
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Named properties of the object, e.g. the location and file format of a table
+    pub fn properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+
+    pub fn has_children(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    pub fn expanded(&self) -> bool {
+        self.expanded
+    }
+}
+
+/// Pushes the visible nodes of the tree with their depth, in display order
+fn visible_nodes<'a>(
+    nodes: &'a [MetadataNode],
+    depth: usize,
+    out: &mut Vec<(usize, &'a MetadataNode)>,
+) {
+    for node in nodes {
+        out.push((depth, node));
+        if node.expanded {
+            visible_nodes(&node.children, depth + 1, out);
+        }
+    }
+}
+
+/// The `n`th visible node of the tree, counting down `n` as nodes are passed
+fn nth_visible_mut<'a>(
+    nodes: &'a mut [MetadataNode],
+    n: &mut usize,
+) -> Option<&'a mut MetadataNode> {
+    for node in nodes {
+        if *n == 0 {
+            return Some(node);
+        }
+        *n -= 1;
+        if node.expanded {
+            if let Some(found) = nth_visible_mut(&mut node.children, n) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Expand down to the tables when there is only one catalog and schema
+fn expand_single_path(nodes: &mut [MetadataNode]) {
+    let mut level = nodes;
+    while let [only] = level {
+        if !only.has_children() || only.name.is_some() {
+            break;
+        }
+        only.expanded = true;
+        level = only.children.as_mut_slice();
+    }
+}
+
+/// Expand the nodes that were expanded in `previous`, matching nodes by their label
+fn copy_expanded(nodes: &mut [MetadataNode], previous: &[MetadataNode]) {
+    for node in nodes {
+        if let Some(prev) = previous.iter().find(|p| p.label == node.label) {
+            node.expanded = prev.expanded;
+            copy_expanded(&mut node.children, &prev.children);
+        }
+    }
+}
+
+/// Navigable tree of catalogs, schemas, tables and columns, from the session context or a
+/// FlightSQL server. `message` is shown in place of the tree while it is loading or if it
+/// couldn't be loaded.
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    nodes: Vec<MetadataNode>,
+    selected: usize,
+    message: Option<String>,
+}
+
+impl Metadata {
+    fn new(nodes: Vec<MetadataNode>) -> Self {
+        Self {
+            nodes,
+            selected: 0,
+            message: None,
+        }
+    }
+
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            message: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The visible nodes with their depth in the tree
+    pub fn lines(&self) -> Vec<(usize, &MetadataNode)> {
+        let mut lines = Vec::new();
+        visible_nodes(&self.nodes, 0, &mut lines);
+        lines
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_node(&self) -> Option<&MetadataNode> {
+        self.lines().get(self.selected).map(|(_, node)| *node)
+    }
+
+    /// The selected table, or the table of the selected column
+    pub fn selected_table(&self) -> Option<&MetadataNode> {
+        let lines = self.lines();
+        let (depth, node) = *lines.get(self.selected)?;
+        if node.has_children() {
+            return node.name.is_some().then_some(node);
+        }
+        let (_, parent) = lines[..self.selected]
+            .iter()
+            .rev()
+            .find(|(d, _)| *d < depth)?;
+        (node.name.is_some() && parent.name.is_some()).then_some(*parent)
+    }
+
+    fn select(&mut self, line: usize) {
+        self.selected = line.min(self.lines().len().saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(usize::MAX);
+    }
+
+    /// Keep the expanded nodes and selection of `previous` when the tree is reloaded
+    pub fn restore_view(&mut self, previous: &Metadata) {
+        if previous.message.is_some() {
+            return;
+        }
+        copy_expanded(&mut self.nodes, &previous.nodes);
+        self.select(previous.selected);
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        let mut n = self.selected;
+        if let Some(node) = nth_visible_mut(&mut self.nodes, &mut n) {
+            if node.has_children() {
+                node.expanded = !node.expanded;
+            }
+        }
+    }
+}
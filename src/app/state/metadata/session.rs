@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The tree of catalogs, schemas, tables and columns registered with the session context
+
+use color_eyre::Result;
+use datafusion::{
+    common::{utils::quote_identifier, TableReference},
+    datasource::{listing::ListingTable, TableProvider},
+    logical_expr::TableType,
+    prelude::SessionContext,
+};
+#[cfg(feature = "deltalake")]
+use deltalake::DeltaTable;
+
+use super::{expand_single_path, Metadata, MetadataNode};
+#[cfg(feature = "flightsql")]
+use crate::app::federation::{FlightSQLTable, RemoteSource};
+
+/// The location and format of tables whose data is read from outside the session, if `table`
+/// is one
+fn external_properties(table: &dyn TableProvider) -> Option<Vec<(String, String)>> {
+    let table = table.as_any();
+    if let Some(listing) = table.downcast_ref::<ListingTable>() {
+        let paths: Vec<String> = listing
+            .table_paths()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        return Some(vec![
+            ("Location".to_string(), paths.join(", ")),
+            ("Format".to_string(), listing.options().format.get_ext()),
+        ]);
+    }
+    #[cfg(feature = "deltalake")]
+    if let Some(delta) = table.downcast_ref::<DeltaTable>() {
+        return Some(vec![
+            ("Location".to_string(), delta.table_uri()),
+            ("Format".to_string(), "delta".to_string()),
+        ]);
+    }
+    #[cfg(feature = "flightsql")]
+    if let Some(remote) = table.downcast_ref::<FlightSQLTable>() {
+        let source = match remote.source() {
+            RemoteSource::Table(table) => ("Remote table".to_string(), table.clone()),
+            RemoteSource::Query(query) => ("Remote query".to_string(), query.clone()),
+        };
+        return Some(vec![
+            ("Location".to_string(), remote.url().to_string()),
+            ("Format".to_string(), "FlightSQL".to_string()),
+            source,
+        ]);
+    }
+    None
+}
+
+/// A table and its columns. Tables are either external, with the location and format of their
+/// data, or typed by the session, and views and external tables have their definitions.
+fn table_node(label: &str, name: String, table: &dyn TableProvider) -> MetadataNode {
+    let (table_type, mut properties) = match external_properties(table) {
+        Some(properties) => ("external", properties),
+        None => {
+            let table_type = match table.table_type() {
+                TableType::Base => "base table",
+                TableType::View => "view",
+                TableType::Temporary => "temporary",
+            };
+            (table_type, Vec::new())
+        }
+    };
+    if let Some(definition) = table.get_table_definition() {
+        properties.push(("Definition".to_string(), definition.to_string()));
+    }
+    let mut node = MetadataNode::new(label, table_type);
+    node.name = Some(name);
+    node.properties = properties;
+    for field in table.schema().fields() {
+        let mut detail = field.data_type().to_string();
+        if !field.is_nullable() {
+            detail.push_str(" not null");
+        }
+        let mut column = MetadataNode::new(field.name().clone(), detail);
+        column.name = Some(quote_identifier(field.name()).to_string());
+        node.children.push(column);
+    }
+    node
+}
+
+impl Metadata {
+    /// The catalogs, schemas, tables and columns registered with `ctx`, in name order. Tables in
+    /// the default catalog and schema are named without qualification.
+    pub async fn from_session(ctx: &SessionContext) -> Result<Self> {
+        let config = ctx.copied_config();
+        let defaults = &config.options().catalog;
+        let mut nodes = Vec::new();
+        let mut catalog_names = ctx.catalog_names();
+        catalog_names.sort();
+        for catalog_name in catalog_names {
+            let Some(catalog) = ctx.catalog(&catalog_name) else {
+                continue;
+            };
+            let mut catalog_node = MetadataNode::new(catalog_name.clone(), "catalog");
+            let mut schema_names = catalog.schema_names();
+            schema_names.sort();
+            for schema_name in schema_names {
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                let mut schema_node = MetadataNode::new(schema_name.clone(), "schema");
+                let mut table_names = schema.table_names();
+                table_names.sort();
+                for table_name in table_names {
+                    let Some(table) = schema.table(&table_name).await? else {
+                        continue;
+                    };
+                    let reference = if catalog_name == defaults.default_catalog
+                        && schema_name == defaults.default_schema
+                    {
+                        TableReference::bare(table_name.as_str())
+                    } else {
+                        TableReference::full(
+                            catalog_name.as_str(),
+                            schema_name.as_str(),
+                            table_name.as_str(),
+                        )
+                    };
+                    schema_node.children.push(table_node(
+                        &table_name,
+                        reference.to_quoted_string(),
+                        table.as_ref(),
+                    ));
+                }
+                catalog_node.children.push(schema_node);
+            }
+            nodes.push(catalog_node);
+        }
+        expand_single_path(&mut nodes);
+        Ok(Self::new(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use crate::app::state::metadata::Metadata;
+
+    #[tokio::test]
+    async fn session_catalog_test() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE users (id INT NOT NULL, name VARCHAR)")
+            .await
+            .unwrap();
+        ctx.sql("CREATE VIEW ids AS SELECT id FROM users")
+            .await
+            .unwrap();
+
+        let mut catalog = Metadata::from_session(&ctx).await.unwrap();
+        let lines: Vec<_> = catalog
+            .lines()
+            .iter()
+            .map(|(d, n)| (*d, n.label(), n.detail()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (0, "datafusion", "catalog"),
+                (1, "public", "schema"),
+                (2, "ids", "view"),
+                (2, "users", "base table"),
+            ]
+        );
+        catalog.select_next();
+        catalog.select_next();
+        let view = catalog.selected_node().unwrap();
+        assert_eq!(view.name(), Some("ids"));
+        assert_eq!(
+            view.properties(),
+            [(
+                "Definition".to_string(),
+                "CREATE VIEW ids AS SELECT id FROM users".to_string()
+            )]
+        );
+
+        // Reloading keeps the expanded tables and selection
+        catalog.select_next();
+        catalog.toggle_expanded();
+        let mut reloaded = Metadata::from_session(&ctx).await.unwrap();
+        reloaded.restore_view(&catalog);
+        let users = reloaded.selected_node().unwrap();
+        assert_eq!((users.label(), users.expanded()), ("users", true));
+        reloaded.select_next();
+        let column = reloaded.selected_node().unwrap();
+        assert_eq!(
+            (column.label(), column.detail(), column.name()),
+            ("id", "Int32 not null", Some("id"))
+        );
+    }
+}
@@ -15,16 +15,29 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod chart;
+pub mod detail;
+pub mod diff;
+pub mod functions;
+pub mod metadata;
+pub mod profile;
+pub mod prompt;
+pub mod results;
+pub mod settings;
 pub mod tabs;
+pub mod view;
 
-use crate::app::config::get_data_dir;
+use crate::app::config::{get_data_dir, CopyFormat};
+use crate::app::keymap::Keymap;
+use crate::app::resources::ResourceUsage;
 use crate::app::state::tabs::sql::SQLTabState;
 use crate::cli;
+use crate::ui::theme::Theme;
 use crate::ui::SelectedTab;
 use log::{debug, error, info};
 use std::path::PathBuf;
 
-use self::tabs::{history::HistoryTabState, logs::LogsTabState};
+use self::tabs::{context::ContextTabState, history::HistoryTabState, logs::LogsTabState};
 
 use super::config::AppConfig;
 #[cfg(feature = "flightsql")]
@@ -46,6 +59,10 @@ impl Default for Tabs {
 #[derive(Debug)]
 pub struct AppState<'app> {
     pub config: AppConfig,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    /// Format that rows and results are copied to the clipboard in
+    pub copy_format: CopyFormat,
     pub should_quit: bool,
     pub data_dir: PathBuf,
     pub sql_tab: SQLTabState<'app>,
@@ -53,10 +70,13 @@ pub struct AppState<'app> {
     pub flightsql_tab: FlightSQLTabState<'app>,
     pub logs_tab: LogsTabState,
     pub history_tab: HistoryTabState,
+    pub context_tab: ContextTabState<'app>,
+    /// Memory, spill and process usage, refreshed on every tick
+    pub resources: ResourceUsage,
     pub tabs: Tabs,
 }
 
-pub fn initialize(args: &cli::DftCli) -> AppState {
+pub fn initialize(args: &cli::DftCli) -> AppState<'_> {
     debug!("Initializing state");
     let data_dir = get_data_dir();
     let config_path = args.get_config();
@@ -85,6 +105,17 @@ pub fn initialize(args: &cli::DftCli) -> AppState {
         AppConfig::default()
     };
 
+    let keymap = match Keymap::try_from_config(&config.keymap) {
+        Ok(keymap) => keymap,
+        Err(err) => {
+            error!("Error loading keymap, using defaults: {}", err);
+            Keymap::default()
+        }
+    };
+
+    let theme = Theme::from_config(&config.display.theme);
+    let copy_format = config.interaction.clipboard.format;
+
     let tabs = Tabs::default();
 
     let sql_tab_state = SQLTabState::new();
@@ -92,9 +123,13 @@ pub fn initialize(args: &cli::DftCli) -> AppState {
     let flightsql_tab_state = FlightSQLTabState::new();
     let logs_tab_state = LogsTabState::default();
     let history_tab_state = HistoryTabState::default();
+    let context_tab_state = ContextTabState::default();
 
     AppState {
         config,
+        keymap,
+        theme,
+        copy_format,
         data_dir,
         tabs,
         sql_tab: sql_tab_state,
@@ -102,6 +137,8 @@ pub fn initialize(args: &cli::DftCli) -> AppState {
         flightsql_tab: flightsql_tab_state,
         logs_tab: logs_tab_state,
         history_tab: history_tab_state,
+        context_tab: context_tab_state,
+        resources: ResourceUsage::default(),
         should_quit: false,
     }
 }
@@ -0,0 +1,485 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use color_eyre::Result;
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray, Int64Array, RecordBatch, StringArray},
+        compute::cast,
+        datatypes::{DataType, Float64Type, Int64Type},
+        util::display::ArrayFormatter,
+    },
+    dataframe::DataFrame,
+    functions_aggregate::expr_fn::{approx_distinct, avg, count, count_distinct, max, min, stddev},
+    prelude::{cast as cast_expr, col, ident, lit, Expr},
+};
+use log::debug;
+
+use crate::{
+    app::{
+        config::{DisplayConfig, ProfileConfig},
+        state::results::ResultsState,
+    },
+    ui::{
+        convert::{format_options, localize_timestamps},
+        SelectedTab,
+    },
+};
+
+/// Characters used to draw histograms, from the smallest to the largest bucket
+const HISTOGRAM_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Profile of the columns of a table or of query results, sent back from the task that
+/// computes it. The profile has a row for each column of the source.
+#[derive(Clone, Debug)]
+pub struct ProfileResults {
+    pub tab: SelectedTab,
+    /// The profiled table, or `None` for the results shown on the tab
+    pub table: Option<String>,
+    pub rows: usize,
+    /// Number of rows the most frequent values and histograms were computed from, if not all of
+    /// them
+    pub sampled: Option<usize>,
+    pub batch: RecordBatch,
+}
+
+/// The column profile view of a tab, navigable like the results table
+#[derive(Debug)]
+pub struct Profile {
+    table: Option<String>,
+    rows: usize,
+    sampled: Option<usize>,
+    batches: Vec<RecordBatch>,
+    state: RefCell<ResultsState>,
+}
+
+impl Profile {
+    pub fn new(results: ProfileResults) -> Self {
+        let batches = vec![results.batch];
+        let state = RefCell::new(ResultsState::new(&batches));
+        Self {
+            table: results.table,
+            rows: results.rows,
+            sampled: results.sampled,
+            batches,
+            state,
+        }
+    }
+
+    pub fn table(&self) -> Option<&str> {
+        self.table.as_deref()
+    }
+
+    /// Number of rows in the profiled source
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of rows the most frequent values and histograms were computed from, if not all of
+    /// them
+    pub fn sampled(&self) -> Option<usize> {
+        self.sampled
+    }
+
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+
+    pub fn state(&self) -> &RefCell<ResultsState> {
+        &self.state
+    }
+}
+
+/// Draw `counts` as a line of bars scaled to the largest count, leaving empty buckets blank
+fn histogram(counts: &[u64]) -> String {
+    let largest = counts.iter().copied().max().unwrap_or_default();
+    counts
+        .iter()
+        .map(|&count| match count {
+            0 => ' ',
+            _ => HISTOGRAM_BARS[(count * (HISTOGRAM_BARS.len() as u64 - 1) / largest) as usize],
+        })
+        .collect()
+}
+
+fn format_float(value: f64) -> String {
+    format!("{:.4}", value)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The first row of the results of an aggregate, as a batch of one row
+async fn single_row(df: DataFrame) -> Result<Option<RecordBatch>> {
+    let batches = df.collect().await?;
+    Ok(batches
+        .into_iter()
+        .find(|b| b.num_rows() > 0)
+        .map(|b| b.slice(0, 1)))
+}
+
+fn int_value(array: &ArrayRef) -> Result<Option<i64>> {
+    let array = cast(array, &DataType::Int64)?;
+    let array = array.as_primitive::<Int64Type>();
+    Ok((!array.is_null(0)).then(|| array.value(0)))
+}
+
+fn float_value(array: &ArrayRef) -> Result<Option<f64>> {
+    let array = cast(array, &DataType::Float64)?;
+    let array = array.as_primitive::<Float64Type>();
+    Ok((!array.is_null(0)).then(|| array.value(0)))
+}
+
+/// Statistics of a single column
+#[derive(Debug, Default)]
+struct ColumnProfile {
+    name: String,
+    data_type: String,
+    nulls: i64,
+    distinct: Option<i64>,
+    min: Option<String>,
+    max: Option<String>,
+    mean: Option<String>,
+    stddev: Option<String>,
+    top_values: Option<String>,
+    histogram: Option<String>,
+}
+
+/// Aggregates computing the statistics of a column: the non-null count, then the distinct count,
+/// minimum and maximum of types that can be compared, then the mean and standard deviation of
+/// numbers. With `counts_only`, just the non-null count.
+fn column_aggregates(
+    index: usize,
+    name: &str,
+    data_type: &DataType,
+    config: &ProfileConfig,
+    counts_only: bool,
+) -> Vec<Expr> {
+    let column = ident(name);
+    let mut aggregates = vec![count(column.clone())];
+    if !counts_only && !data_type.is_nested() {
+        let distinct = if config.approximate_distinct {
+            approx_distinct(column.clone())
+        } else {
+            count_distinct(column.clone())
+        };
+        aggregates.extend([distinct, min(column.clone()), max(column.clone())]);
+        if data_type.is_numeric() {
+            let value = cast_expr(column, DataType::Float64);
+            aggregates.extend([avg(value.clone()), stddev(value)]);
+        }
+    }
+    // Columns are aliased by position as their names can clash once prefixed
+    aggregates
+        .into_iter()
+        .enumerate()
+        .map(|(i, aggregate)| aggregate.alias(format!("column_{}_{}", index, i)))
+        .collect()
+}
+
+/// Compute the number of rows and the statistics of every column in a single aggregate,
+/// returning the aggregated values of each column
+async fn aggregate_columns(
+    df: &DataFrame,
+    fields: &[(String, DataType)],
+    config: &ProfileConfig,
+    counts_only: bool,
+) -> Result<(usize, Vec<Vec<ArrayRef>>)> {
+    let mut aggregates = vec![count(lit(1)).alias("rows")];
+    let mut lengths = Vec::new();
+    for (index, (name, data_type)) in fields.iter().enumerate() {
+        let column = column_aggregates(index, name, data_type, config, counts_only);
+        lengths.push(column.len());
+        aggregates.extend(column);
+    }
+    let Some(stats) = single_row(df.clone().aggregate(vec![], aggregates)?).await? else {
+        return Ok((0, Vec::new()));
+    };
+    let rows = int_value(stats.column(0))?.unwrap_or_default() as usize;
+    let mut offset = 1;
+    let columns = lengths
+        .into_iter()
+        .map(|len| {
+            let column = stats.columns()[offset..offset + len].to_vec();
+            offset += len;
+            column
+        })
+        .collect();
+    Ok((rows, columns))
+}
+
+/// Profile a column from its aggregated `stats`, scanning `df` again for its most common values
+/// and histogram. `df` may be limited to a sample of the rows the stats were computed from.
+async fn profile_column(
+    df: &DataFrame,
+    name: &str,
+    data_type: &DataType,
+    rows: usize,
+    stats: &[ArrayRef],
+    config: &ProfileConfig,
+    display: &DisplayConfig,
+) -> Result<ColumnProfile> {
+    let column = ident(name);
+    let numeric = data_type.is_numeric();
+    let mut profile = ColumnProfile {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        ..Default::default()
+    };
+    let Some(non_null) = stats.first() else {
+        return Ok(profile);
+    };
+    profile.nulls = rows as i64 - int_value(non_null)?.unwrap_or_default();
+    // Only the null count is known of types that can't be compared, e.g. nested types
+    if stats.len() < 4 {
+        return Ok(profile);
+    }
+    profile.distinct = int_value(&stats[1])?;
+    let options = format_options(display);
+    for (idx, value) in [(2, &mut profile.min), (3, &mut profile.max)] {
+        let array = localize_timestamps(&stats[idx], display);
+        if !array.is_null(0) {
+            let formatter = ArrayFormatter::try_new(array.as_ref(), &options)?;
+            *value = Some(formatter.value(0).to_string());
+        }
+    }
+    if numeric && stats.len() == 6 {
+        profile.mean = float_value(&stats[4])?.map(format_float);
+        profile.stddev = float_value(&stats[5])?.map(format_float);
+    }
+
+    let non_null = df.clone().filter(column.clone().is_not_null())?;
+    if config.top_values > 0 {
+        let top = non_null
+            .clone()
+            .aggregate(vec![column.clone()], vec![count(lit(1)).alias("count")])?
+            .sort(vec![
+                col("count").sort(false, false),
+                column.clone().sort(true, false),
+            ])?
+            .limit(0, Some(config.top_values))?
+            .collect()
+            .await;
+        match top {
+            Ok(batches) => {
+                let mut values = Vec::new();
+                for batch in batches {
+                    let array = localize_timestamps(batch.column(0), display);
+                    let formatter = ArrayFormatter::try_new(array.as_ref(), &options)?;
+                    let counts = cast(batch.column(1), &DataType::Int64)?;
+                    let counts = counts.as_primitive::<Int64Type>();
+                    for i in 0..batch.num_rows() {
+                        values.push(format!("{} ({})", formatter.value(i), counts.value(i)));
+                    }
+                }
+                profile.top_values = Some(values.join(", "));
+            }
+            Err(e) => debug!("Unable to find the top values of {}: {}", name, e),
+        }
+    }
+
+    let low = float_value(&stats[2]).ok().flatten();
+    let high = float_value(&stats[3]).ok().flatten();
+    if let (true, Some(low), Some(high)) = (numeric && config.histogram_bins > 0, low, high) {
+        let bins = config.histogram_bins;
+        let mut counts = vec![0; bins];
+        if low < high {
+            let width = (high - low) / bins as f64;
+            let value: Expr = cast_expr(column, DataType::Float64);
+            let bucket = cast_expr((value - lit(low)) / lit(width), DataType::Int64);
+            let buckets = non_null
+                .aggregate(vec![bucket.alias("bucket")], vec![count(lit(1))])?
+                .collect()
+                .await?;
+            for batch in buckets {
+                let bucket = cast(batch.column(0), &DataType::Int64)?;
+                let counted = cast(batch.column(1), &DataType::Int64)?;
+                let values = bucket
+                    .as_primitive::<Int64Type>()
+                    .iter()
+                    .zip(counted.as_primitive::<Int64Type>().iter());
+                for (bucket, count) in values {
+                    // The largest value is the upper bound of the last bucket
+                    let bucket = (bucket.unwrap_or_default().max(0) as usize).min(bins - 1);
+                    counts[bucket] += count.unwrap_or_default() as u64;
+                }
+            }
+        } else {
+            // Every value is the same
+            counts[0] = (rows as i64 - profile.nulls) as u64;
+        }
+        profile.histogram = Some(histogram(&counts));
+    }
+    Ok(profile)
+}
+
+/// Profile every column of `df`, returning the number of rows and a batch with a row of
+/// statistics for each column. The statistics of every column are computed in one aggregate,
+/// then each column's most common values and histogram with further aggregates, which only read
+/// the first `sample_rows` rows if given.
+pub async fn profile_columns(
+    df: DataFrame,
+    sample_rows: Option<usize>,
+    config: &ProfileConfig,
+    display: &DisplayConfig,
+) -> Result<(usize, RecordBatch)> {
+    let fields: Vec<_> = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| (f.name().clone(), f.data_type().clone()))
+        .collect();
+    let (rows, stats) = match aggregate_columns(&df, &fields, config, false).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            debug!("Unable to compute statistics, counting nulls only: {}", e);
+            aggregate_columns(&df, &fields, config, true).await?
+        }
+    };
+    let sample = match sample_rows {
+        Some(sample_rows) => df.limit(0, Some(sample_rows))?,
+        None => df,
+    };
+    let mut profiles = Vec::new();
+    for ((name, data_type), stats) in fields.iter().zip(&stats) {
+        let profile = profile_column(&sample, name, data_type, rows, stats, config, display);
+        profiles.push(profile.await?);
+    }
+
+    let null_fraction = |p: &ColumnProfile| match rows {
+        0 => "-".to_string(),
+        rows => format!("{:.1}%", p.nulls as f64 * 100.0 / rows as f64),
+    };
+    let distinct = if config.approximate_distinct {
+        "distinct (approx)"
+    } else {
+        "distinct"
+    };
+    let strings = |f: fn(&ColumnProfile) -> Option<String>| -> ArrayRef {
+        Arc::new(profiles.iter().map(f).collect::<StringArray>())
+    };
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        ("column", strings(|p| Some(p.name.clone()))),
+        ("type", strings(|p| Some(p.data_type.clone()))),
+        (
+            "nulls",
+            Arc::new(Int64Array::from_iter_values(
+                profiles.iter().map(|p| p.nulls),
+            )),
+        ),
+        (
+            "null %",
+            Arc::new(
+                profiles
+                    .iter()
+                    .map(|p| Some(null_fraction(p)))
+                    .collect::<StringArray>(),
+            ),
+        ),
+        (
+            distinct,
+            Arc::new(profiles.iter().map(|p| p.distinct).collect::<Int64Array>()),
+        ),
+        ("min", strings(|p| p.min.clone())),
+        ("max", strings(|p| p.max.clone())),
+        ("mean", strings(|p| p.mean.clone())),
+        ("stddev", strings(|p| p.stddev.clone())),
+        ("top values", strings(|p| p.top_values.clone())),
+        ("histogram", strings(|p| p.histogram.clone())),
+    ];
+    Ok((rows, RecordBatch::try_from_iter(columns)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::{
+        array::{Array, ArrayRef, AsArray, Int32Array, ListArray, RecordBatch, StringArray},
+        compute::cast,
+        datatypes::{DataType, Int32Type},
+    };
+    use datafusion::prelude::SessionContext;
+
+    use super::{histogram, profile_columns};
+    use crate::app::config::{DisplayConfig, ProfileConfig};
+
+    #[test]
+    fn histogram_test() {
+        assert_eq!(histogram(&[1, 0, 4, 8]), "▁ ▄█");
+        assert_eq!(histogram(&[]), "");
+    }
+
+    #[tokio::test]
+    async fn profile_columns_test() {
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(2), None]));
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "b", "b"]));
+        let tags: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            None,
+            Some(vec![]),
+            None,
+        ]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("id", ids), ("Name", names), ("tags", tags)]).unwrap();
+        let df = SessionContext::new().read_batch(batch).unwrap();
+
+        let config = ProfileConfig::default();
+        let display = DisplayConfig::default();
+        let (rows, profile) = profile_columns(df.clone(), None, &config, &display)
+            .await
+            .unwrap();
+        assert_eq!(rows, 4);
+        let value = |column: &str, row: usize| {
+            let column = profile.column_by_name(column).unwrap();
+            let column = cast(column, &DataType::Utf8).unwrap();
+            let column = column.as_string::<i32>();
+            (!column.is_null(row)).then(|| column.value(row).to_string())
+        };
+        assert_eq!(value("column", 1).as_deref(), Some("Name"));
+        assert_eq!(value("nulls", 0).as_deref(), Some("1"));
+        assert_eq!(value("null %", 0).as_deref(), Some("25.0%"));
+        assert_eq!(value("distinct", 0).as_deref(), Some("2"));
+        assert_eq!(value("min", 0).as_deref(), Some("1"));
+        assert_eq!(value("max", 1).as_deref(), Some("b"));
+        assert_eq!(value("mean", 0).as_deref(), Some("1.6667"));
+        assert_eq!(value("mean", 1), None);
+        assert_eq!(value("top values", 1).as_deref(), Some("b (3), a (1)"));
+        assert_eq!(value("histogram", 0).as_deref(), Some("▄        █"));
+        // Lists can't be compared, so only their nulls are counted
+        assert_eq!(value("nulls", 2).as_deref(), Some("2"));
+        assert_eq!(value("distinct", 2), None);
+        assert_eq!(value("min", 2), None);
+
+        // Only the most common values and histograms are limited to the sample
+        let (rows, sampled) = profile_columns(df, Some(2), &config, &display)
+            .await
+            .unwrap();
+        assert_eq!(rows, 4);
+        let value = |column: &str, row: usize| {
+            let column = sampled.column_by_name(column).unwrap();
+            let column = cast(column, &DataType::Utf8).unwrap();
+            column.as_string::<i32>().value(row).to_string()
+        };
+        assert_eq!(value("nulls", 0), "1");
+        assert_eq!(value("distinct", 1), "2");
+        assert_eq!(value("top values", 1), "a (1), b (1)");
+    }
+}
@@ -0,0 +1,102 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::crossterm::event::KeyEvent;
+use tui_textarea::{CursorMove, TextArea};
+
+/// What a prompt's input is used for once it is submitted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptKind {
+    GoToRow,
+    Filter,
+    Search,
+    Chart,
+    ProfileTable,
+    Diff,
+    Parameters,
+    FilterSettings,
+    SettingValue,
+    SearchFunctions,
+    ExportTrace,
+    #[cfg(feature = "substrait")]
+    ExportSubstrait,
+    #[cfg(feature = "substrait")]
+    RunSubstrait,
+}
+
+impl PromptKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptKind::GoToRow => "Go to row",
+            PromptKind::Filter => "Filter (WHERE)",
+            PromptKind::Search => "Search",
+            PromptKind::Chart => "Chart (<bar|line|scatter> <x> <y>[,<y>...])",
+            PromptKind::ProfileTable => "Profile table",
+            PromptKind::Diff => {
+                "Diff (<left> <right> [<key>,...], sides are tables or #history entries)"
+            }
+            PromptKind::Parameters => "Parameters (<value>,..., NULL for null)",
+            PromptKind::FilterSettings => "Filter settings",
+            PromptKind::SettingValue => "Value",
+            PromptKind::SearchFunctions => "Search functions",
+            PromptKind::ExportTrace => "Save query trace to",
+            #[cfg(feature = "substrait")]
+            PromptKind::ExportSubstrait => "Save Substrait plan to",
+            #[cfg(feature = "substrait")]
+            PromptKind::RunSubstrait => "Run Substrait plan file",
+        }
+    }
+}
+
+/// Single line input shown in place of a tab's help line
+#[derive(Debug)]
+pub struct Prompt<'app> {
+    kind: PromptKind,
+    input: TextArea<'app>,
+}
+
+impl<'app> Prompt<'app> {
+    pub fn new(kind: PromptKind) -> Self {
+        Self {
+            kind,
+            input: TextArea::default(),
+        }
+    }
+
+    /// A prompt that starts with `value` as its input, e.g. to edit a previous value
+    pub fn with_value(kind: PromptKind, value: String) -> Self {
+        let mut input = TextArea::new(vec![value]);
+        input.move_cursor(CursorMove::End);
+        Self { kind, input }
+    }
+
+    pub fn kind(&self) -> PromptKind {
+        self.kind
+    }
+
+    pub fn input(&self) -> &TextArea<'app> {
+        &self.input
+    }
+
+    pub fn value(&self) -> String {
+        self.input.lines().join("")
+    }
+
+    pub fn update(&mut self, key: KeyEvent) {
+        self.input.input(key);
+    }
+}
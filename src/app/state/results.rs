@@ -0,0 +1,331 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datafusion::arrow::array::RecordBatch;
+
+/// Selection and scroll position of a results table.
+///
+/// Only the rows inside the viewport are formatted when rendering, so the state keeps the
+/// first row of every batch to be able to find the batch holding any row without scanning.
+#[derive(Debug, Default)]
+pub struct ResultsState {
+    batch_offsets: Vec<usize>,
+    row_count: usize,
+    selected: Option<usize>,
+    offset: usize,
+    viewport_rows: usize,
+    column_count: usize,
+    selected_column: usize,
+    column_offset: usize,
+    frozen_columns: usize,
+    column_widths: Option<Vec<u16>>,
+    search: Option<String>,
+}
+
+impl ResultsState {
+    pub fn new(batches: &[RecordBatch]) -> Self {
+        let mut batch_offsets = Vec::with_capacity(batches.len());
+        let mut row_count = 0;
+        for batch in batches {
+            batch_offsets.push(row_count);
+            row_count += batch.num_rows();
+        }
+        let column_count = batches.first().map_or(0, |b| b.num_columns());
+        Self {
+            batch_offsets,
+            row_count,
+            column_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// The first row shown in the viewport
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn viewport_rows(&self) -> usize {
+        self.viewport_rows
+    }
+
+    /// The batch index and the row within that batch of the absolute `row`
+    pub fn locate(&self, row: usize) -> Option<(usize, usize)> {
+        if row >= self.row_count {
+            return None;
+        }
+        let batch = self.batch_offsets.partition_point(|start| *start <= row) - 1;
+        Some((batch, row - self.batch_offsets[batch]))
+    }
+
+    pub fn select(&mut self, row: usize) {
+        if self.row_count == 0 {
+            self.selected = None;
+        } else {
+            self.selected = Some(row.min(self.row_count - 1));
+            self.scroll_to_selected();
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        let next = self.selected.map_or(0, |s| s.saturating_add(1));
+        self.select(next);
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.selected.map_or(0, |s| s.saturating_sub(1));
+        self.select(previous);
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(self.row_count.saturating_sub(1));
+    }
+
+    pub fn page_down(&mut self) {
+        let page = self.viewport_rows.max(1);
+        let next = self.selected.map_or(0, |s| s.saturating_add(page));
+        self.select(next);
+    }
+
+    pub fn page_up(&mut self) {
+        let page = self.viewport_rows.max(1);
+        let previous = self.selected.map_or(0, |s| s.saturating_sub(page));
+        self.select(previous);
+    }
+
+    /// Called on render with the number of rows that fit in the table so that the selected
+    /// row is kept in view.
+    pub fn set_viewport_rows(&mut self, rows: usize) {
+        self.viewport_rows = rows;
+        self.scroll_to_selected();
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.column_count
+    }
+
+    pub fn selected_column(&self) -> usize {
+        self.selected_column
+    }
+
+    /// Number of data columns, counted from the left, that stay visible when scrolling
+    pub fn frozen_columns(&self) -> usize {
+        self.frozen_columns
+    }
+
+    pub fn column_widths(&self) -> &Option<Vec<u16>> {
+        &self.column_widths
+    }
+
+    pub fn set_column_widths(&mut self, widths: Vec<u16>) {
+        self.column_widths = Some(widths);
+    }
+
+    pub fn select_next_column(&mut self) {
+        if self.selected_column + 1 < self.column_count {
+            self.selected_column += 1;
+        }
+    }
+
+    pub fn select_previous_column(&mut self) {
+        self.selected_column = self.selected_column.saturating_sub(1);
+    }
+
+    /// Freeze all columns up to and including the selected column, or unfreeze them if the
+    /// selected column is already frozen.
+    pub fn toggle_freeze_columns(&mut self) {
+        if self.selected_column < self.frozen_columns {
+            self.frozen_columns = 0;
+        } else {
+            self.frozen_columns = self.selected_column + 1;
+        }
+    }
+
+    /// Keep the column selection, sizing and search of `previous` when the same results are
+    /// shown in a different order or filtered
+    pub fn keep_columns_from(&mut self, previous: &ResultsState) {
+        if previous.column_count == self.column_count {
+            self.selected_column = previous.selected_column;
+            self.column_offset = previous.column_offset;
+            self.frozen_columns = previous.frozen_columns;
+            self.column_widths.clone_from(&previous.column_widths);
+            self.search.clone_from(&previous.search);
+        }
+    }
+
+    /// Term that matching cells are highlighted for
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+
+    pub fn set_search(&mut self, search: Option<String>) {
+        self.search = search;
+    }
+
+    /// Select the cell of a search match, scrolling it into view
+    pub fn select_cell(&mut self, row: usize, column: usize) {
+        self.select(row);
+        self.selected_column = column.min(self.column_count.saturating_sub(1));
+    }
+
+    /// The data columns to show in `available` width after the row number column, scrolling
+    /// horizontally so that the selected column is visible. Frozen columns come first.
+    pub fn visible_columns(&mut self, available: u16) -> Vec<usize> {
+        let Some(widths) = &self.column_widths else {
+            return (0..self.column_count).collect();
+        };
+        // Each column is followed by a single space of padding
+        let width = |col: usize| widths.get(col).copied().unwrap_or(0).saturating_add(1);
+        let frozen = self.frozen_columns.min(self.column_count);
+        let mut remaining = available;
+        let mut columns = Vec::new();
+        for col in 0..frozen {
+            if width(col) > remaining {
+                break;
+            }
+            remaining -= width(col);
+            columns.push(col);
+        }
+
+        self.column_offset = self.column_offset.max(frozen);
+        if self.selected_column >= frozen {
+            if self.selected_column < self.column_offset {
+                self.column_offset = self.selected_column;
+            }
+            // Scroll right until the selected column fits after the offset
+            while self.column_offset < self.selected_column
+                && (self.column_offset..=self.selected_column)
+                    .map(width)
+                    .fold(0u16, |acc, w| acc.saturating_add(w))
+                    > remaining
+            {
+                self.column_offset += 1;
+            }
+        }
+
+        for col in self.column_offset..self.column_count {
+            if width(col) > remaining && col != self.column_offset {
+                break;
+            }
+            remaining = remaining.saturating_sub(width(col));
+            columns.push(col);
+        }
+        columns
+    }
+
+    fn scroll_to_selected(&mut self) {
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if self.viewport_rows > 0 && selected >= self.offset + self.viewport_rows {
+                self.offset = selected + 1 - self.viewport_rows;
+            }
+        }
+        let max_offset = self.row_count.saturating_sub(self.viewport_rows.max(1));
+        self.offset = self.offset.min(max_offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{ArrayRef, Int32Array, RecordBatch};
+
+    use super::ResultsState;
+
+    fn batch(rows: i32) -> RecordBatch {
+        let a: ArrayRef = Arc::new(Int32Array::from((0..rows).collect::<Vec<_>>()));
+        RecordBatch::try_from_iter(vec![("a", a)]).unwrap()
+    }
+
+    #[test]
+    fn locate_rows_test() {
+        let state = ResultsState::new(&[batch(3), batch(0), batch(2)]);
+        assert_eq!(state.row_count(), 5);
+        assert_eq!(state.locate(0), Some((0, 0)));
+        assert_eq!(state.locate(2), Some((0, 2)));
+        assert_eq!(state.locate(3), Some((2, 0)));
+        assert_eq!(state.locate(4), Some((2, 1)));
+        assert_eq!(state.locate(5), None);
+    }
+
+    #[test]
+    fn horizontal_scroll_test() {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1]));
+        let batch =
+            RecordBatch::try_from_iter((0..6).map(|i| (i.to_string(), Arc::clone(&a)))).unwrap();
+        let mut state = ResultsState::new(&[batch]);
+        state.set_column_widths(vec![4; 6]);
+        // Each column takes 5 cells including padding
+        assert_eq!(state.visible_columns(16), vec![0, 1, 2]);
+        for _ in 0..4 {
+            state.select_next_column();
+        }
+        assert_eq!(state.visible_columns(16), vec![2, 3, 4]);
+        state.select_previous_column();
+        state.select_previous_column();
+        state.select_previous_column();
+        assert_eq!(state.visible_columns(16), vec![1, 2, 3]);
+
+        // Freezing the first column keeps it visible while scrolling
+        state.select_previous_column();
+        state.toggle_freeze_columns();
+        assert_eq!(state.frozen_columns(), 1);
+        for _ in 0..5 {
+            state.select_next_column();
+        }
+        assert_eq!(state.selected_column(), 5);
+        assert_eq!(state.visible_columns(16), vec![0, 4, 5]);
+        state.toggle_freeze_columns();
+        assert_eq!(state.frozen_columns(), 6);
+        state.select_first();
+        state.select_previous_column();
+        state.toggle_freeze_columns();
+        assert_eq!(state.frozen_columns(), 0);
+    }
+
+    #[test]
+    fn scroll_with_selection_test() {
+        let mut state = ResultsState::new(&[batch(100)]);
+        state.set_viewport_rows(10);
+        assert_eq!(state.selected(), None);
+        state.select_next();
+        assert_eq!(state.selected(), Some(0));
+        state.page_down();
+        assert_eq!((state.selected(), state.offset()), (Some(10), 1));
+        state.select_last();
+        assert_eq!((state.selected(), state.offset()), (Some(99), 90));
+        state.page_up();
+        assert_eq!((state.selected(), state.offset()), (Some(89), 89));
+        state.select(1_000);
+        assert_eq!(state.selected(), Some(99));
+        state.select_first();
+        assert_eq!((state.selected(), state.offset()), (Some(0), 0));
+    }
+}
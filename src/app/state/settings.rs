@@ -0,0 +1,230 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The options of the session config, with DataFusion's defaults, for the settings editor of the
+//! Context tab
+
+use std::collections::{BTreeMap, HashMap};
+
+use datafusion::config::ConfigOptions;
+
+/// An option of the session config
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting {
+    pub key: String,
+    pub value: Option<String>,
+    pub default: Option<String>,
+    pub description: &'static str,
+}
+
+impl Setting {
+    /// Whether the value differs from DataFusion's default
+    pub fn modified(&self) -> bool {
+        self.value != self.default
+    }
+
+    /// The namespace the option is grouped under, e.g. `execution` for
+    /// `datafusion.execution.batch_size`
+    pub fn namespace(&self) -> &str {
+        self.key.split('.').nth(1).unwrap_or_default()
+    }
+
+    fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.key.to_lowercase().contains(&filter)
+            || self.description.to_lowercase().contains(&filter)
+    }
+}
+
+/// Every option of `options`, in DataFusion's order, with its default
+pub fn settings(options: &ConfigOptions) -> Vec<Setting> {
+    let defaults: HashMap<String, Option<String>> = ConfigOptions::new()
+        .entries()
+        .into_iter()
+        .map(|entry| (entry.key, entry.value))
+        .collect();
+    options
+        .entries()
+        .into_iter()
+        .map(|entry| Setting {
+            default: defaults.get(&entry.key).cloned().flatten(),
+            key: entry.key,
+            value: entry.value,
+            description: entry.description,
+        })
+        .collect()
+}
+
+/// A line of the settings editor, either the header of a namespace or one of its options
+#[derive(Debug, PartialEq)]
+pub enum SettingsRow<'a> {
+    Namespace(&'a str),
+    Setting(&'a Setting),
+}
+
+/// The session config as shown in the settings editor, filtered by key or description. Values
+/// set in the editor are kept so they can be saved to the config file.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    settings: Vec<Setting>,
+    filter: Option<String>,
+    changes: BTreeMap<String, String>,
+    /// Index of the selected option among those matching the filter
+    selected: usize,
+}
+
+impl Settings {
+    pub fn new(options: &ConfigOptions) -> Self {
+        Self {
+            settings: settings(options),
+            ..Default::default()
+        }
+    }
+
+    /// Read the options again after the session config has changed
+    pub fn refresh(&mut self, options: &ConfigOptions) {
+        self.settings = settings(options);
+        self.select(self.selected);
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        self.filter = filter;
+        self.selected = 0;
+    }
+
+    /// The options matching the filter
+    pub fn filtered(&self) -> Vec<&Setting> {
+        self.settings
+            .iter()
+            .filter(|s| match &self.filter {
+                Some(f) => s.matches(f),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// The options that differ from DataFusion's defaults
+    pub fn modified(&self) -> Vec<&Setting> {
+        self.settings.iter().filter(|s| s.modified()).collect()
+    }
+
+    /// The rows shown, with the options of each namespace under its header, and the index of the
+    /// row of the selected option
+    pub fn rows(&self) -> (Vec<SettingsRow<'_>>, Option<usize>) {
+        let mut rows = Vec::new();
+        let mut selected = None;
+        for (i, setting) in self.filtered().into_iter().enumerate() {
+            match rows.last() {
+                Some(SettingsRow::Setting(previous))
+                    if previous.namespace() == setting.namespace() => {}
+                _ => rows.push(SettingsRow::Namespace(setting.namespace())),
+            }
+            if i == self.selected {
+                selected = Some(rows.len());
+            }
+            rows.push(SettingsRow::Setting(setting));
+        }
+        (rows, selected)
+    }
+
+    pub fn selected_setting(&self) -> Option<&Setting> {
+        self.filtered().get(self.selected).copied()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.filtered().len().saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(usize::MAX);
+    }
+
+    /// Values set in the editor, by key
+    pub fn changes(&self) -> &BTreeMap<String, String> {
+        &self.changes
+    }
+
+    pub fn record_change(&mut self, key: String, value: String) {
+        self.changes.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::config::ConfigOptions;
+
+    use super::{Settings, SettingsRow};
+
+    #[test]
+    fn settings_test() {
+        let mut options = ConfigOptions::new();
+        options
+            .set("datafusion.execution.batch_size", "1024")
+            .unwrap();
+        let mut settings = Settings::new(&options);
+        let modified: Vec<_> = settings
+            .modified()
+            .iter()
+            .map(|s| (s.key.as_str(), s.value.as_deref(), s.default.as_deref()))
+            .collect();
+        assert_eq!(
+            modified,
+            vec![(
+                "datafusion.execution.batch_size",
+                Some("1024"),
+                Some("8192")
+            )]
+        );
+
+        settings.set_filter(Some("PRUNING".to_string()));
+        settings.select_last();
+        let (rows, selected) = settings.rows();
+        assert_eq!(rows[0], SettingsRow::Namespace("execution"));
+        assert!(rows.iter().all(|row| match row {
+            SettingsRow::Setting(s) => format!("{} {}", s.key, s.description)
+                .to_lowercase()
+                .contains("pruning"),
+            SettingsRow::Namespace(_) => true,
+        }));
+        assert_eq!(selected, Some(rows.len() - 1));
+
+        options.set("datafusion.optimizer.max_passes", "5").unwrap();
+        settings.refresh(&options);
+        settings.set_filter(Some("max_passes".to_string()));
+        let setting = settings.selected_setting().unwrap();
+        assert_eq!(
+            (setting.value.as_deref(), setting.modified()),
+            (Some("5"), true)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::cell::RefCell;
+
+use crate::app::state::{
+    metadata::Metadata,
+    prompt::{Prompt, PromptKind},
+    results::ResultsState,
+    settings::Settings,
+    tabs::sql::Query,
+};
+
+/// The catalog browser of the Context tab, with the rows previewed from the last table picked,
+/// and the session config editor while it is open
+#[derive(Debug, Default)]
+pub struct ContextTabState<'app> {
+    catalog: Option<Metadata>,
+    preview: Option<Query>,
+    preview_state: Option<RefCell<ResultsState>>,
+    settings: Settings,
+    show_settings: bool,
+    prompt: Option<Prompt<'app>>,
+}
+
+impl<'app> ContextTabState<'app> {
+    pub fn catalog(&self) -> &Option<Metadata> {
+        &self.catalog
+    }
+
+    pub fn catalog_mut(&mut self) -> &mut Option<Metadata> {
+        &mut self.catalog
+    }
+
+    /// Show a newly loaded catalog, keeping the expanded nodes and selection of the current one
+    pub fn set_catalog(&mut self, mut catalog: Metadata) {
+        if let Some(previous) = &self.catalog {
+            catalog.restore_view(previous);
+        }
+        self.catalog = Some(catalog);
+    }
+
+    pub fn preview(&self) -> &Option<Query> {
+        &self.preview
+    }
+
+    pub fn preview_state(&self) -> &Option<RefCell<ResultsState>> {
+        &self.preview_state
+    }
+
+    pub fn set_preview(&mut self, preview: Query) {
+        self.preview_state = preview
+            .results()
+            .as_ref()
+            .map(|batches| RefCell::new(ResultsState::new(batches)));
+        self.preview = Some(preview);
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    pub fn show_settings(&self) -> bool {
+        self.show_settings
+    }
+
+    pub fn set_show_settings(&mut self, show: bool) {
+        self.show_settings = show;
+    }
+
+    pub fn prompt(&self) -> &Option<Prompt<'app>> {
+        &self.prompt
+    }
+
+    pub fn prompt_mut(&mut self) -> &mut Option<Prompt<'app>> {
+        &mut self.prompt
+    }
+
+    pub fn open_prompt_with_value(&mut self, kind: PromptKind, value: String) {
+        self.prompt = Some(Prompt::with_value(kind, value));
+    }
+
+    /// Close the prompt, returning it so that its input can be acted on
+    pub fn close_prompt(&mut self) -> Option<Prompt<'app>> {
+        self.prompt.take()
+    }
+}
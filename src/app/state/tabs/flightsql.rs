@@ -16,25 +16,39 @@
 // under the License.
 
 use core::cell::RefCell;
+use std::fmt::Display;
 use std::time::Duration;
 
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
 use datafusion::arrow::array::RecordBatch;
+use log::info;
 use ratatui::crossterm::event::KeyEvent;
-use ratatui::style::palette::tailwind;
-use ratatui::style::Style;
-use ratatui::widgets::TableState;
 use tui_textarea::TextArea;
 
-use crate::app::execution::ExecutionStats;
+use crate::app::config::DisplayConfig;
+use crate::app::execution::{ExecutionStats, FlightSQLProgress, FlightSQLStats};
+use crate::app::state::chart::{ChartConfig, ChartData};
+use crate::app::state::detail::RecordDetail;
+use crate::app::state::metadata::Metadata;
+use crate::app::state::profile::Profile;
+use crate::app::state::prompt::{Prompt, PromptKind};
+use crate::app::state::results::ResultsState;
+use crate::app::state::view::{find_match, ResultsView};
 
 #[derive(Clone, Debug)]
 pub struct FlightSQLQuery {
     sql: String,
+    /// Name of the connection the query was run on
+    connection: Option<String>,
     results: Option<Vec<RecordBatch>>,
     num_rows: Option<usize>,
     error: Option<String>,
+    /// Rows changed by a statement that doesn't return rows
+    affected_rows: Option<i64>,
     execution_time: Duration,
     execution_stats: Option<ExecutionStats>,
+    flightsql_stats: Option<FlightSQLStats>,
 }
 
 impl FlightSQLQuery {
@@ -48,11 +62,14 @@ impl FlightSQLQuery {
     ) -> Self {
         Self {
             sql,
+            connection: None,
             results,
             num_rows,
             error,
+            affected_rows: None,
             execution_time,
             execution_stats,
+            flightsql_stats: None,
         }
     }
 
@@ -60,6 +77,14 @@ impl FlightSQLQuery {
         &self.sql
     }
 
+    pub fn connection(&self) -> &Option<String> {
+        &self.connection
+    }
+
+    pub fn set_connection(&mut self, connection: Option<String>) {
+        self.connection = connection;
+    }
+
     pub fn set_results(&mut self, results: Option<Vec<RecordBatch>>) {
         self.results = results;
     }
@@ -84,6 +109,14 @@ impl FlightSQLQuery {
         &self.error
     }
 
+    pub fn affected_rows(&self) -> Option<i64> {
+        self.affected_rows
+    }
+
+    pub fn set_affected_rows(&mut self, affected_rows: Option<i64>) {
+        self.affected_rows = affected_rows;
+    }
+
     pub fn set_execution_time(&mut self, execution_time: Duration) {
         self.execution_time = execution_time;
     }
@@ -99,6 +132,48 @@ impl FlightSQLQuery {
     pub fn set_execution_stats(&mut self, stats: Option<ExecutionStats>) {
         self.execution_stats = stats;
     }
+
+    /// How the results were fetched from the server
+    pub fn flightsql_stats(&self) -> &Option<FlightSQLStats> {
+        &self.flightsql_stats
+    }
+
+    pub fn set_flightsql_stats(&mut self, stats: Option<FlightSQLStats>) {
+        self.flightsql_stats = stats;
+    }
+}
+
+/// A statement prepared from the editor, with the name and type of each of its parameters and
+/// the values last bound to them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreparedQuery {
+    pub sql: String,
+    pub parameters: Vec<(String, String)>,
+    pub values: String,
+}
+
+/// State of the connection to the selected FlightSQL server
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    #[default]
+    Disconnected,
+    Connecting,
+    /// Connected, with how long connecting and authenticating took
+    Connected(Duration),
+    Failed(String),
+}
+
+impl Display for ConnectionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionStatus::Disconnected => write!(f, "disconnected"),
+            ConnectionStatus::Connecting => write!(f, "connecting"),
+            ConnectionStatus::Connected(latency) => {
+                write!(f, "connected ({}ms)", latency.as_millis())
+            }
+            ConnectionStatus::Failed(_) => write!(f, "failed"),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -106,33 +181,349 @@ pub struct FlightSQLTabState<'app> {
     editor: TextArea<'app>,
     editor_editable: bool,
     query: Option<FlightSQLQuery>,
-    query_results_state: Option<RefCell<TableState>>,
+    query_results_state: Option<RefCell<ResultsState>>,
+    results_view: ResultsView,
+    prompt: Option<Prompt<'app>>,
+    detail: Option<RecordDetail>,
+    profile: Option<Profile>,
+    chart: Option<ChartConfig>,
+    chart_data: Option<Result<ChartData>>,
+    show_chart: bool,
+    progress: Option<FlightSQLProgress>,
+    /// Index of the selected connection in the configured connections
+    connection: usize,
+    connection_status: ConnectionStatus,
+    /// Incremented on every connection attempt so that the results of earlier attempts can be
+    /// told apart and ignored
+    connection_attempt: usize,
+    /// The connection highlighted in the connection picker, when it is open
+    connection_picker: Option<usize>,
+    /// Objects on the server, kept until the connection changes
+    metadata: Option<Metadata>,
+    show_metadata: bool,
+    prepared: Option<PreparedQuery>,
 }
 
 impl<'app> FlightSQLTabState<'app> {
     pub fn new() -> Self {
         let empty_text = vec!["Enter a query here.".to_string()];
         // TODO: Enable vim mode from config?
-        let mut textarea = TextArea::new(empty_text);
-        textarea.set_style(Style::default().fg(tailwind::WHITE));
+        let textarea = TextArea::new(empty_text);
 
         Self {
             editor: textarea,
             editor_editable: false,
             query: None,
             query_results_state: None,
+            results_view: ResultsView::default(),
+            prompt: None,
+            detail: None,
+            profile: None,
+            chart: None,
+            chart_data: None,
+            show_chart: false,
+            progress: None,
+            connection: 0,
+            connection_status: ConnectionStatus::Disconnected,
+            connection_attempt: 0,
+            connection_picker: None,
+            metadata: None,
+            show_metadata: false,
+            prepared: None,
         }
     }
 
-    pub fn query_results_state(&self) -> &Option<RefCell<TableState>> {
+    pub fn query_results_state(&self) -> &Option<RefCell<ResultsState>> {
         &self.query_results_state
     }
 
     pub fn refresh_query_results_state(&mut self) {
-        self.query_results_state = Some(RefCell::new(TableState::default()));
+        let batches = self.displayed_results().unwrap_or_default();
+        self.query_results_state = Some(RefCell::new(ResultsState::new(batches)));
+    }
+
+    /// The query results as sorted and filtered by the results view
+    pub fn displayed_results(&self) -> Option<&[RecordBatch]> {
+        self.query
+            .as_ref()
+            .and_then(|q| q.results().as_deref())
+            .map(|r| self.results_view.batches(r))
+    }
+
+    pub fn results_view(&self) -> &ResultsView {
+        &self.results_view
+    }
+
+    fn refresh_results_view_state(&mut self) {
+        let previous = self.query_results_state.take();
+        self.refresh_query_results_state();
+        if let (Some(previous), Some(state)) = (previous, &self.query_results_state) {
+            state.borrow_mut().keep_columns_from(&previous.borrow());
+        }
+    }
+
+    pub fn sort_by_selected_column(&mut self, config: &DisplayConfig) -> Result<()> {
+        let (Some(results), Some(state)) = (
+            self.query.as_ref().and_then(|q| q.results().as_deref()),
+            &self.query_results_state,
+        ) else {
+            return Err(eyre!("No results to sort"));
+        };
+        let column = state.borrow().selected_column();
+        self.results_view.cycle_sort(column, results)?;
+        self.refresh_results_view_state();
+        self.refresh_chart(config);
+        Ok(())
+    }
+
+    /// Show only the rows of the results in `filter`, or all of them if `None`
+    pub fn set_filter(
+        &mut self,
+        filter: Option<(String, Vec<RecordBatch>)>,
+        config: &DisplayConfig,
+    ) -> Result<()> {
+        let Some(results) = self.query.as_ref().and_then(|q| q.results().as_deref()) else {
+            return Err(eyre!("No results to filter"));
+        };
+        self.results_view.set_filter(filter, results)?;
+        self.refresh_results_view_state();
+        self.refresh_chart(config);
+        Ok(())
+    }
+
+    /// Select the next (or previous) cell matching the search term
+    pub fn find_next_match(&self, forward: bool, config: &DisplayConfig) -> Result<()> {
+        let (Some(results), Some(state)) = (self.displayed_results(), &self.query_results_state)
+        else {
+            return Err(eyre!("No results to search"));
+        };
+        let mut state = state.borrow_mut();
+        let Some(term) = state.search().map(str::to_string) else {
+            return Err(eyre!("No search term"));
+        };
+        match find_match(results, &term, state.selected(), forward, config)? {
+            Some((row, column)) => state.select_cell(row, column),
+            None => info!("No results match '{}'", term),
+        }
+        Ok(())
+    }
+
+    pub fn prompt(&self) -> &Option<Prompt<'app>> {
+        &self.prompt
+    }
+
+    pub fn prompt_mut(&mut self) -> &mut Option<Prompt<'app>> {
+        &mut self.prompt
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt::new(kind));
+    }
+
+    pub fn open_prompt_with_value(&mut self, kind: PromptKind, value: String) {
+        self.prompt = Some(Prompt::with_value(kind, value));
+    }
+
+    /// Close the prompt, returning it so that its input can be acted on
+    pub fn close_prompt(&mut self) -> Option<Prompt<'app>> {
+        self.prompt.take()
+    }
+
+    pub fn chart(&self) -> &Option<ChartConfig> {
+        &self.chart
+    }
+
+    /// Whether the chart is shown in place of the results table
+    pub fn show_chart(&self) -> bool {
+        self.show_chart && self.chart.is_some()
+    }
+
+    /// The columns plotted by the chart, or why they can't be
+    pub fn chart_data(&self) -> Option<&Result<ChartData>> {
+        self.chart_data.as_ref()
+    }
+
+    /// Plot the results with `chart`, or go back to the results table if `None`
+    pub fn set_chart(&mut self, chart: Option<ChartConfig>, config: &DisplayConfig) {
+        self.show_chart = chart.is_some();
+        self.chart = chart;
+        self.refresh_chart(config);
+    }
+
+    /// Compute the plotted columns from the displayed results
+    fn refresh_chart(&mut self, config: &DisplayConfig) {
+        let data = self.chart.as_ref().map(|chart| {
+            let batches = self.displayed_results().unwrap_or_default();
+            ChartData::try_new(batches, chart, config)
+        });
+        self.chart_data = data;
+    }
+
+    pub fn toggle_chart(&mut self) {
+        self.show_chart = !self.show_chart;
+    }
+
+    /// The current chart, or a line chart of the selected column against the first column, to
+    /// start editing from
+    pub fn chart_suggestion(&self) -> String {
+        if let Some(chart) = &self.chart {
+            return chart.to_string();
+        }
+        let (Some(batch), Some(state)) = (
+            self.displayed_results().and_then(|r| r.first()),
+            &self.query_results_state,
+        ) else {
+            return String::new();
+        };
+        let fields = batch.schema_ref().fields();
+        let y = state.borrow().selected_column().max(1);
+        match (fields.first(), fields.get(y)) {
+            (Some(x), Some(y)) => format!("line {} {}", x.name(), y.name()),
+            _ => String::new(),
+        }
+    }
+
+    pub fn detail(&self) -> &Option<RecordDetail> {
+        &self.detail
+    }
+
+    pub fn detail_mut(&mut self) -> &mut Option<RecordDetail> {
+        &mut self.detail
+    }
+
+    /// Open the detail view of the selected row of the results
+    pub fn open_detail(&mut self, config: &DisplayConfig) -> Result<()> {
+        let batches = self.displayed_results().unwrap_or_default();
+        let Some(state) = &self.query_results_state else {
+            return Err(eyre!("No results to show"));
+        };
+        let state = state.borrow();
+        let row = state.selected().ok_or_else(|| eyre!("No row selected"))?;
+        let (batch, idx) = state
+            .locate(row)
+            .ok_or_else(|| eyre!("Row {} is not in the results", row))?;
+        self.detail = Some(RecordDetail::try_new(&batches[batch], idx, row, config)?);
+        Ok(())
+    }
+
+    pub fn profile(&self) -> &Option<Profile> {
+        &self.profile
+    }
+
+    pub fn profile_mut(&mut self) -> &mut Option<Profile> {
+        &mut self.profile
+    }
+
+    pub fn set_profile(&mut self, profile: Option<Profile>) {
+        self.profile = profile;
+    }
+
+    /// What has been fetched so far while a query is running
+    pub fn progress(&self) -> &Option<FlightSQLProgress> {
+        &self.progress
+    }
+
+    pub fn set_progress(&mut self, progress: Option<FlightSQLProgress>) {
+        self.progress = progress;
+    }
+
+    pub fn connection(&self) -> usize {
+        self.connection
+    }
+
+    pub fn connection_status(&self) -> &ConnectionStatus {
+        &self.connection_status
+    }
+
+    /// Start connecting to the connection at `index`, returning the attempt's number
+    pub fn start_connecting(&mut self, index: usize) -> usize {
+        self.connection = index;
+        self.connection_status = ConnectionStatus::Connecting;
+        self.metadata = None;
+        self.prepared = None;
+        self.connection_attempt += 1;
+        self.connection_attempt
+    }
+
+    /// Record the outcome of connection `attempt`. Returns false if a newer attempt has been
+    /// started since.
+    pub fn finish_connecting(&mut self, attempt: usize, status: ConnectionStatus) -> bool {
+        if attempt != self.connection_attempt {
+            return false;
+        }
+        self.connection_status = status;
+        true
+    }
+
+    pub fn metadata(&self) -> &Option<Metadata> {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Option<Metadata> {
+        &mut self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: Option<Metadata>) {
+        self.metadata = metadata;
+    }
+
+    pub fn prepared(&self) -> &Option<PreparedQuery> {
+        &self.prepared
+    }
+
+    /// Set the statement that was prepared, keeping the values bound to the previous statement
+    /// if it was prepared from the same SQL
+    pub fn set_prepared(&mut self, mut prepared: PreparedQuery) {
+        if let Some(previous) = self.prepared.take() {
+            if previous.sql == prepared.sql {
+                prepared.values = previous.values;
+            }
+        }
+        self.prepared = Some(prepared);
+    }
+
+    pub fn set_prepared_values(&mut self, values: String) {
+        if let Some(prepared) = self.prepared.as_mut() {
+            prepared.values = values;
+        }
+    }
+
+    pub fn show_metadata(&self) -> bool {
+        self.show_metadata
+    }
+
+    pub fn set_show_metadata(&mut self, show: bool) {
+        self.show_metadata = show;
+    }
+
+    /// Insert `text` at the cursor of the editor
+    pub fn insert_into_editor(&mut self, text: &str) {
+        self.clear_placeholder();
+        self.editor.insert_str(text);
+    }
+
+    pub fn connection_picker(&self) -> Option<usize> {
+        self.connection_picker
+    }
+
+    pub fn open_connection_picker(&mut self) {
+        self.connection_picker = Some(self.connection);
+    }
+
+    pub fn close_connection_picker(&mut self) {
+        self.connection_picker = None;
+    }
+
+    /// Move the picker's highlight by `offset`, staying within `count` connections
+    pub fn move_connection_picker(&mut self, offset: isize, count: usize) {
+        if let Some(selected) = self.connection_picker.as_mut() {
+            *selected = selected
+                .saturating_add_signed(offset)
+                .min(count.saturating_sub(1));
+        }
     }
 
-    pub fn editor(&self) -> TextArea {
+    pub fn editor(&self) -> TextArea<'_> {
         // TODO: Figure out how to do this without clone. Probably need logic in handler to make
         // updates to the Widget and then pass a ref
         self.editor.clone()
@@ -150,9 +541,7 @@ impl<'app> FlightSQLTabState<'app> {
     }
 
     pub fn clear_editor(&mut self) {
-        let mut textarea = TextArea::new(vec!["".to_string()]);
-        textarea.set_style(Style::default().fg(tailwind::WHITE));
-        self.editor = textarea;
+        self.editor = TextArea::new(vec!["".to_string()]);
     }
 
     pub fn update_editor_content(&mut self, key: KeyEvent) {
@@ -173,6 +562,9 @@ impl<'app> FlightSQLTabState<'app> {
 
     pub fn set_query(&mut self, query: FlightSQLQuery) {
         self.query = Some(query);
+        self.results_view = ResultsView::default();
+        self.detail = None;
+        self.progress = None;
     }
 
     pub fn query(&self) -> &Option<FlightSQLQuery> {
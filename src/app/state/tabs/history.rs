@@ -21,8 +21,11 @@ use std::time::Duration;
 use ratatui::widgets::TableState;
 
 use crate::app::execution::ExecutionStats;
+#[cfg(feature = "flightsql")]
+use crate::app::execution::FlightSQLStats;
+use crate::app::state::chart::ChartConfig;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Context {
     Local,
     FlightSQL,
@@ -40,9 +43,15 @@ impl Context {
 #[derive(Debug)]
 pub struct HistoryQuery {
     context: Context,
+    /// Name of the FlightSQL connection the query was run on
+    connection: Option<String>,
     sql: String,
     execution_time: Duration,
     execution_stats: Option<ExecutionStats>,
+    /// How the results of a FlightSQL query were fetched
+    #[cfg(feature = "flightsql")]
+    flightsql_stats: Option<FlightSQLStats>,
+    chart: Option<ChartConfig>,
 }
 
 impl HistoryQuery {
@@ -54,9 +63,13 @@ impl HistoryQuery {
     ) -> Self {
         Self {
             context,
+            connection: None,
             sql,
             execution_time,
             execution_stats,
+            #[cfg(feature = "flightsql")]
+            flightsql_stats: None,
+            chart: None,
         }
     }
     pub fn sql(&self) -> &String {
@@ -79,15 +92,51 @@ impl HistoryQuery {
         }
     }
 
+    #[cfg(feature = "flightsql")]
+    pub fn flightsql_stats(&self) -> &Option<FlightSQLStats> {
+        &self.flightsql_stats
+    }
+
+    #[cfg(feature = "flightsql")]
+    pub fn set_flightsql_stats(&mut self, stats: Option<FlightSQLStats>) {
+        self.flightsql_stats = stats;
+    }
+
     pub fn context(&self) -> &Context {
         &self.context
     }
+
+    pub fn connection(&self) -> &Option<String> {
+        &self.connection
+    }
+
+    pub fn set_connection(&mut self, connection: Option<String>) {
+        self.connection = connection;
+    }
+
+    /// The context, with the connection for FlightSQL queries
+    pub fn context_label(&self) -> String {
+        match &self.connection {
+            Some(connection) => format!("{} ({})", self.context.as_str(), connection),
+            None => self.context.as_str().to_string(),
+        }
+    }
+
+    /// The chart the query's results were last plotted with
+    pub fn chart(&self) -> &Option<ChartConfig> {
+        &self.chart
+    }
+
+    pub fn set_chart(&mut self, chart: Option<ChartConfig>) {
+        self.chart = chart;
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct HistoryTabState {
     history: Vec<HistoryQuery>,
     history_table_state: Option<RefCell<TableState>>,
+    diff_mark: Option<usize>,
 }
 
 impl HistoryTabState {
@@ -95,6 +144,7 @@ impl HistoryTabState {
         Self {
             history: Vec::new(),
             history_table_state: None,
+            diff_mark: None,
         }
     }
 
@@ -106,10 +156,68 @@ impl HistoryTabState {
         self.history.push(query)
     }
 
+    /// The most recent run of `sql` in `context`
+    fn latest_mut(&mut self, context: Context, sql: &str) -> Option<&mut HistoryQuery> {
+        self.history
+            .iter_mut()
+            .rev()
+            .find(|q| q.context == context && q.sql == sql)
+    }
+
+    /// Add a run of a query, keeping the chart of its previous run so that its results are
+    /// plotted the same way. Returns that chart.
+    pub fn add_run_to_history(&mut self, mut query: HistoryQuery) -> Option<ChartConfig> {
+        let chart = self
+            .latest_mut(query.context, &query.sql)
+            .and_then(|q| q.chart.clone());
+        query.set_chart(chart.clone());
+        self.add_to_history(query);
+        chart
+    }
+
+    /// Keep `chart` with the most recent run of `sql` in `context`
+    pub fn set_chart(&mut self, context: Context, sql: &str, chart: Option<ChartConfig>) {
+        if let Some(query) = self.latest_mut(context, sql) {
+            query.set_chart(chart);
+        }
+    }
+
     pub fn history_table_state(&self) -> &Option<RefCell<TableState>> {
         &self.history_table_state
     }
 
+    /// The entry marked as the left side of a diff
+    pub fn diff_mark(&self) -> Option<usize> {
+        self.diff_mark
+    }
+
+    /// Mark the selected entry as the left side of a diff, or clear the mark if it is already
+    /// marked
+    pub fn toggle_diff_mark(&mut self) {
+        let selected = self
+            .history_table_state
+            .as_ref()
+            .and_then(|s| s.borrow().selected());
+        self.diff_mark = match self.diff_mark {
+            mark if mark == selected => None,
+            _ => selected,
+        };
+    }
+
+    /// The diff of the marked entry with the selected one, as entered in the diff prompt
+    pub fn diff_suggestion(&self) -> String {
+        let selected = self
+            .history_table_state
+            .as_ref()
+            .and_then(|s| s.borrow().selected());
+        let entries: Vec<String> = [self.diff_mark, selected]
+            .into_iter()
+            .flatten()
+            .map(|idx| format!("#{} ", idx + 1))
+            .collect();
+        entries.concat()
+    }
+
     pub fn refresh_history_table_state(&mut self) {
         self.history_table_state = Some(RefCell::new(TableState::default()));
     }
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod context;
 #[cfg(feature = "flightsql")]
 pub mod flightsql;
 pub mod history;
@@ -18,14 +18,23 @@
 use core::cell::RefCell;
 use std::time::Duration;
 
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
 use datafusion::arrow::array::RecordBatch;
+use log::info;
 use ratatui::crossterm::event::KeyEvent;
-use ratatui::style::palette::tailwind;
-use ratatui::style::Style;
-use ratatui::widgets::TableState;
-use tui_textarea::TextArea;
+use tui_textarea::{CursorMove, TextArea};
 
+use crate::app::config::DisplayConfig;
 use crate::app::execution::ExecutionStats;
+use crate::app::state::chart::{ChartConfig, ChartData};
+use crate::app::state::detail::RecordDetail;
+use crate::app::state::diff::DiffSummary;
+use crate::app::state::functions::FunctionBrowser;
+use crate::app::state::profile::Profile;
+use crate::app::state::prompt::{Prompt, PromptKind};
+use crate::app::state::results::ResultsState;
+use crate::app::state::view::{find_match, ResultsView};
 
 #[derive(Clone, Debug)]
 pub struct Query {
@@ -106,32 +115,247 @@ pub struct SQLTabState<'app> {
     editor: TextArea<'app>,
     editor_editable: bool,
     query: Option<Query>,
-    query_results_state: Option<RefCell<TableState>>,
+    query_results_state: Option<RefCell<ResultsState>>,
+    results_view: ResultsView,
+    prompt: Option<Prompt<'app>>,
+    detail: Option<RecordDetail>,
+    profile: Option<Profile>,
+    functions: Option<FunctionBrowser>,
+    diff: Option<DiffSummary>,
+    chart: Option<ChartConfig>,
+    chart_data: Option<Result<ChartData>>,
+    show_chart: bool,
 }
 
 impl<'app> SQLTabState<'app> {
     pub fn new() -> Self {
         let empty_text = vec!["Enter a query here.".to_string()];
         // TODO: Enable vim mode from config?
-        let mut textarea = TextArea::new(empty_text);
-        textarea.set_style(Style::default().fg(tailwind::WHITE));
+        let textarea = TextArea::new(empty_text);
         Self {
             editor: textarea,
             editor_editable: false,
             query: None,
             query_results_state: None,
+            results_view: ResultsView::default(),
+            prompt: None,
+            detail: None,
+            profile: None,
+            functions: None,
+            diff: None,
+            chart: None,
+            chart_data: None,
+            show_chart: false,
         }
     }
 
-    pub fn query_results_state(&self) -> &Option<RefCell<TableState>> {
+    pub fn query_results_state(&self) -> &Option<RefCell<ResultsState>> {
         &self.query_results_state
     }
 
     pub fn refresh_query_results_state(&mut self) {
-        self.query_results_state = Some(RefCell::new(TableState::default()));
+        let batches = self.displayed_results().unwrap_or_default();
+        self.query_results_state = Some(RefCell::new(ResultsState::new(batches)));
     }
 
-    pub fn editor(&self) -> TextArea {
+    /// The query results as sorted and filtered by the results view
+    pub fn displayed_results(&self) -> Option<&[RecordBatch]> {
+        self.query
+            .as_ref()
+            .and_then(|q| q.results().as_deref())
+            .map(|r| self.results_view.batches(r))
+    }
+
+    pub fn results_view(&self) -> &ResultsView {
+        &self.results_view
+    }
+
+    fn refresh_results_view_state(&mut self) {
+        let previous = self.query_results_state.take();
+        self.refresh_query_results_state();
+        if let (Some(previous), Some(state)) = (previous, &self.query_results_state) {
+            state.borrow_mut().keep_columns_from(&previous.borrow());
+        }
+    }
+
+    pub fn sort_by_selected_column(&mut self, config: &DisplayConfig) -> Result<()> {
+        let (Some(results), Some(state)) = (
+            self.query.as_ref().and_then(|q| q.results().as_deref()),
+            &self.query_results_state,
+        ) else {
+            return Err(eyre!("No results to sort"));
+        };
+        let column = state.borrow().selected_column();
+        self.results_view.cycle_sort(column, results)?;
+        self.refresh_results_view_state();
+        self.refresh_chart(config);
+        Ok(())
+    }
+
+    /// Show only the rows of the results in `filter`, or all of them if `None`
+    pub fn set_filter(
+        &mut self,
+        filter: Option<(String, Vec<RecordBatch>)>,
+        config: &DisplayConfig,
+    ) -> Result<()> {
+        let Some(results) = self.query.as_ref().and_then(|q| q.results().as_deref()) else {
+            return Err(eyre!("No results to filter"));
+        };
+        self.results_view.set_filter(filter, results)?;
+        self.refresh_results_view_state();
+        self.refresh_chart(config);
+        Ok(())
+    }
+
+    /// Select the next (or previous) cell matching the search term
+    pub fn find_next_match(&self, forward: bool, config: &DisplayConfig) -> Result<()> {
+        let (Some(results), Some(state)) = (self.displayed_results(), &self.query_results_state)
+        else {
+            return Err(eyre!("No results to search"));
+        };
+        let mut state = state.borrow_mut();
+        let Some(term) = state.search().map(str::to_string) else {
+            return Err(eyre!("No search term"));
+        };
+        match find_match(results, &term, state.selected(), forward, config)? {
+            Some((row, column)) => state.select_cell(row, column),
+            None => info!("No results match '{}'", term),
+        }
+        Ok(())
+    }
+
+    pub fn prompt(&self) -> &Option<Prompt<'app>> {
+        &self.prompt
+    }
+
+    pub fn prompt_mut(&mut self) -> &mut Option<Prompt<'app>> {
+        &mut self.prompt
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt::new(kind));
+    }
+
+    pub fn open_prompt_with_value(&mut self, kind: PromptKind, value: String) {
+        self.prompt = Some(Prompt::with_value(kind, value));
+    }
+
+    /// Close the prompt, returning it so that its input can be acted on
+    pub fn close_prompt(&mut self) -> Option<Prompt<'app>> {
+        self.prompt.take()
+    }
+
+    pub fn chart(&self) -> &Option<ChartConfig> {
+        &self.chart
+    }
+
+    /// Whether the chart is shown in place of the results table
+    pub fn show_chart(&self) -> bool {
+        self.show_chart && self.chart.is_some()
+    }
+
+    /// The columns plotted by the chart, or why they can't be
+    pub fn chart_data(&self) -> Option<&Result<ChartData>> {
+        self.chart_data.as_ref()
+    }
+
+    /// Plot the results with `chart`, or go back to the results table if `None`
+    pub fn set_chart(&mut self, chart: Option<ChartConfig>, config: &DisplayConfig) {
+        self.show_chart = chart.is_some();
+        self.chart = chart;
+        self.refresh_chart(config);
+    }
+
+    /// Compute the plotted columns from the displayed results
+    fn refresh_chart(&mut self, config: &DisplayConfig) {
+        let data = self.chart.as_ref().map(|chart| {
+            let batches = self.displayed_results().unwrap_or_default();
+            ChartData::try_new(batches, chart, config)
+        });
+        self.chart_data = data;
+    }
+
+    pub fn toggle_chart(&mut self) {
+        self.show_chart = !self.show_chart;
+    }
+
+    /// The current chart, or a line chart of the selected column against the first column, to
+    /// start editing from
+    pub fn chart_suggestion(&self) -> String {
+        if let Some(chart) = &self.chart {
+            return chart.to_string();
+        }
+        let (Some(batch), Some(state)) = (
+            self.displayed_results().and_then(|r| r.first()),
+            &self.query_results_state,
+        ) else {
+            return String::new();
+        };
+        let fields = batch.schema_ref().fields();
+        let y = state.borrow().selected_column().max(1);
+        match (fields.first(), fields.get(y)) {
+            (Some(x), Some(y)) => format!("line {} {}", x.name(), y.name()),
+            _ => String::new(),
+        }
+    }
+
+    pub fn detail(&self) -> &Option<RecordDetail> {
+        &self.detail
+    }
+
+    pub fn detail_mut(&mut self) -> &mut Option<RecordDetail> {
+        &mut self.detail
+    }
+
+    /// Open the detail view of the selected row of the results
+    pub fn open_detail(&mut self, config: &DisplayConfig) -> Result<()> {
+        let batches = self.displayed_results().unwrap_or_default();
+        let Some(state) = &self.query_results_state else {
+            return Err(eyre!("No results to show"));
+        };
+        let state = state.borrow();
+        let row = state.selected().ok_or_else(|| eyre!("No row selected"))?;
+        let (batch, idx) = state
+            .locate(row)
+            .ok_or_else(|| eyre!("Row {} is not in the results", row))?;
+        self.detail = Some(RecordDetail::try_new(&batches[batch], idx, row, config)?);
+        Ok(())
+    }
+
+    pub fn profile(&self) -> &Option<Profile> {
+        &self.profile
+    }
+
+    pub fn profile_mut(&mut self) -> &mut Option<Profile> {
+        &mut self.profile
+    }
+
+    pub fn set_profile(&mut self, profile: Option<Profile>) {
+        self.profile = profile;
+    }
+
+    pub fn functions(&self) -> &Option<FunctionBrowser> {
+        &self.functions
+    }
+
+    pub fn functions_mut(&mut self) -> &mut Option<FunctionBrowser> {
+        &mut self.functions
+    }
+
+    pub fn set_functions(&mut self, functions: Option<FunctionBrowser>) {
+        self.functions = functions;
+    }
+
+    /// Summary of the diff whose rows are shown as the query results
+    pub fn diff(&self) -> Option<DiffSummary> {
+        self.diff
+    }
+
+    pub fn set_diff(&mut self, diff: Option<DiffSummary>) {
+        self.diff = diff;
+    }
+
+    pub fn editor(&self) -> TextArea<'_> {
         // TODO: Figure out how to do this without clone. Probably need logic in handler to make
         // updates to the Widget and then pass a ref
         self.editor.clone()
@@ -148,10 +372,21 @@ impl<'app> SQLTabState<'app> {
         }
     }
 
+    pub fn insert_into_editor(&mut self, text: &str) {
+        self.clear_placeholder();
+        self.editor.insert_str(text);
+    }
+
+    /// Insert `text` at the cursor, leaving the cursor `back` characters before the end of it
+    pub fn insert_template(&mut self, text: &str, back: usize) {
+        self.insert_into_editor(text);
+        for _ in 0..back {
+            self.editor.move_cursor(CursorMove::Back);
+        }
+    }
+
     pub fn clear_editor(&mut self) {
-        let mut textarea = TextArea::new(vec!["".to_string()]);
-        textarea.set_style(Style::default().fg(tailwind::WHITE));
-        self.editor = textarea;
+        self.editor = TextArea::new(vec!["".to_string()]);
     }
 
     pub fn update_editor_content(&mut self, key: KeyEvent) {
@@ -172,6 +407,9 @@ impl<'app> SQLTabState<'app> {
 
     pub fn set_query(&mut self, query: Query) {
         self.query = Some(query);
+        self.results_view = ResultsView::default();
+        self.detail = None;
+        self.diff = None;
     }
 
     pub fn query(&self) -> &Option<Query> {
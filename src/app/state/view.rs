@@ -0,0 +1,272 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use color_eyre::Result;
+use datafusion::arrow::{
+    array::RecordBatch,
+    compute::{concat_batches, sort_to_indices, take_record_batch, SortOptions},
+    util::display::ArrayFormatter,
+};
+
+use crate::{
+    app::config::DisplayConfig,
+    ui::{
+        convert::{format_options, localize_timestamps},
+        SelectedTab,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortColumn {
+    pub column: usize,
+    pub descending: bool,
+}
+
+/// Results of filtering a tab's query results, sent back from the task that runs the filter
+#[derive(Clone, Debug)]
+pub struct FilteredResults {
+    pub tab: SelectedTab,
+    /// The query whose results were filtered, so that stale filters can be ignored
+    pub sql: String,
+    pub filter: String,
+    pub batches: Vec<RecordBatch>,
+}
+
+/// Sorted and filtered view of query results. The query results themselves are never changed,
+/// the view keeps its own batches which are shown in their place.
+#[derive(Debug, Default)]
+pub struct ResultsView {
+    filter: Option<String>,
+    filtered: Option<Vec<RecordBatch>>,
+    sort: Option<SortColumn>,
+    batches: Option<Vec<RecordBatch>>,
+}
+
+impl ResultsView {
+    /// The batches to show for `results`
+    pub fn batches<'a>(&'a self, results: &'a [RecordBatch]) -> &'a [RecordBatch] {
+        self.batches.as_deref().unwrap_or(results)
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    pub fn sort(&self) -> Option<SortColumn> {
+        self.sort
+    }
+
+    pub fn set_filter(
+        &mut self,
+        filter: Option<(String, Vec<RecordBatch>)>,
+        results: &[RecordBatch],
+    ) -> Result<()> {
+        (self.filter, self.filtered) = filter.unzip();
+        self.refresh(results)
+    }
+
+    /// Sort ascending by `column`, then descending, then go back to the unsorted order
+    pub fn cycle_sort(&mut self, column: usize, results: &[RecordBatch]) -> Result<()> {
+        self.sort = match self.sort {
+            Some(SortColumn {
+                column: c,
+                descending: false,
+            }) if c == column => Some(SortColumn {
+                column,
+                descending: true,
+            }),
+            Some(SortColumn {
+                column: c,
+                descending: true,
+            }) if c == column => None,
+            _ => Some(SortColumn {
+                column,
+                descending: false,
+            }),
+        };
+        self.refresh(results)
+    }
+
+    fn refresh(&mut self, results: &[RecordBatch]) -> Result<()> {
+        let base = self.filtered.as_deref().unwrap_or(results);
+        self.batches = match (self.sort, base.first()) {
+            (Some(sort), Some(first)) => {
+                let batch = concat_batches(first.schema_ref(), base)?;
+                let options = SortOptions {
+                    descending: sort.descending,
+                    nulls_first: sort.descending,
+                };
+                let indices = sort_to_indices(batch.column(sort.column), Some(options), None)?;
+                Some(vec![take_record_batch(&batch, &indices)?])
+            }
+            _ => self.filtered.clone(),
+        };
+        Ok(())
+    }
+}
+
+/// Find the next (or previous) row after `start` with a value containing `term`, ignoring case
+/// and wrapping around the end of the results. Returns the row and the matching column.
+pub fn find_match(
+    batches: &[RecordBatch],
+    term: &str,
+    start: Option<usize>,
+    forward: bool,
+    config: &DisplayConfig,
+) -> Result<Option<(usize, usize)>> {
+    let term = term.to_lowercase();
+    let options = format_options(config);
+    // The first and last matches overall are used when wrapping around
+    let mut first = None;
+    let mut last = None;
+    let mut after = None;
+    let mut before = None;
+    let mut row = 0;
+    for batch in batches {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| localize_timestamps(c, config))
+            .collect::<Vec<_>>();
+        let formatters = columns
+            .iter()
+            .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for idx in 0..batch.num_rows() {
+            let column = formatters
+                .iter()
+                .position(|f| f.value(idx).to_string().to_lowercase().contains(&term));
+            if let Some(column) = column {
+                let found = Some((row, column));
+                first = first.or(found);
+                last = found;
+                match start {
+                    Some(start) if row < start => before = found,
+                    Some(start) if row > start => after = after.or(found),
+                    _ => {}
+                }
+            }
+            row += 1;
+        }
+    }
+    let found = match (start, forward) {
+        (None, true) => first,
+        (None, false) => last,
+        (Some(_), true) => after.or(first),
+        (Some(_), false) => before.or(last),
+    };
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+
+    use super::{find_match, ResultsView};
+    use crate::app::config::DisplayConfig;
+
+    fn batches() -> Vec<RecordBatch> {
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![Some(3), None, Some(1)]));
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["Carol", "Alice", "Bob"]));
+        let first = RecordBatch::try_from_iter(vec![("id", ids), ("name", names)]).unwrap();
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![2]));
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["alicia"]));
+        let second = RecordBatch::try_from_iter(vec![("id", ids), ("name", names)]).unwrap();
+        vec![first, second]
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<Option<i32>> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                let ids = b.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+                ids.iter().collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sort_results_view_test() {
+        let results = batches();
+        let mut view = ResultsView::default();
+        assert_eq!(
+            ids(view.batches(&results)),
+            vec![Some(3), None, Some(1), Some(2)]
+        );
+        view.cycle_sort(0, &results).unwrap();
+        assert_eq!(
+            ids(view.batches(&results)),
+            vec![Some(1), Some(2), Some(3), None]
+        );
+        view.cycle_sort(0, &results).unwrap();
+        assert_eq!(
+            ids(view.batches(&results)),
+            vec![None, Some(3), Some(2), Some(1)]
+        );
+        view.cycle_sort(0, &results).unwrap();
+        assert_eq!(
+            ids(view.batches(&results)),
+            vec![Some(3), None, Some(1), Some(2)]
+        );
+
+        // Sorting applies on top of a filter
+        let filtered = vec![results[0].slice(0, 1), results[1].clone()];
+        view.set_filter(Some(("id > 1".to_string(), filtered)), &results)
+            .unwrap();
+        view.cycle_sort(0, &results).unwrap();
+        assert_eq!(ids(view.batches(&results)), vec![Some(2), Some(3)]);
+        assert_eq!(view.filter(), Some("id > 1"));
+        view.set_filter(None, &results).unwrap();
+        assert_eq!(
+            ids(view.batches(&results)),
+            vec![Some(1), Some(2), Some(3), None]
+        );
+    }
+
+    #[test]
+    fn find_match_test() {
+        let results = batches();
+        let config = DisplayConfig::default();
+        assert_eq!(
+            find_match(&results, "ALI", None, true, &config).unwrap(),
+            Some((1, 1))
+        );
+        assert_eq!(
+            find_match(&results, "ali", Some(1), true, &config).unwrap(),
+            Some((3, 1))
+        );
+        // Wraps around in both directions
+        assert_eq!(
+            find_match(&results, "ali", Some(3), true, &config).unwrap(),
+            Some((1, 1))
+        );
+        assert_eq!(
+            find_match(&results, "ali", Some(1), false, &config).unwrap(),
+            Some((3, 1))
+        );
+        assert_eq!(
+            find_match(&results, "3", Some(2), true, &config).unwrap(),
+            Some((0, 0))
+        );
+        assert_eq!(
+            find_match(&results, "dave", None, true, &config).unwrap(),
+            None
+        );
+    }
+}
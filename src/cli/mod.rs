@@ -25,7 +25,8 @@ const LONG_ABOUT: &str = "
 Dft
 
 Environment Variables
-RUST_LOG { trace | debug | info | error }: Standard across rust ecosystem for determining log level of application.  Default is info.
+RUST_LOG { trace | debug | info | warn | error }: Standard across rust ecosystem for determining log level of application, optionally per target e.g. `info,datafusion=debug`.  Default is info.
+DFT_LOGLEVEL: Same as RUST_LOG, and takes precedence over it.
 ";
 
 #[derive(Clone, Debug, Parser)]
@@ -30,6 +30,7 @@ async fn main() -> Result<()> {
     telemetry::initialize_logs()?;
     let cli = cli::DftCli::parse();
     let state = state::initialize(&cli);
+    telemetry::configure_logs(&state.config.logging, &state.data_dir);
     run_app(cli.clone(), state).await?;
     Ok(())
 }
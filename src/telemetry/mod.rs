@@ -15,12 +15,347 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod trace;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use chrono::{DateTime, Local, SecondsFormat};
 use color_eyre::Result;
-use log::LevelFilter;
+use lazy_static::lazy_static;
+use log::{error, Level, LevelFilter, Log, Metadata, Record};
+use tracing_subscriber::layer::SubscriberExt;
 
-pub fn initialize_logs() -> Result<()> {
-    tui_logger::init_logger(LevelFilter::Debug).unwrap();
-    tui_logger::set_default_level(LevelFilter::Debug);
+use crate::app::config::{LogFormat, LoggingConfig, LOG_ENV, LOG_FILE};
+
+/// Level used for targets that no directive matches
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Records logged before the log file is opened are kept, up to this many, and written to it
+/// once it is
+const MAX_PENDING_LINES: usize = 1000;
+
+/// Level filter in the `RUST_LOG` format, e.g. `info,datafusion=warn,dft::app=trace`. A
+/// directive for a target also applies to the modules below it, and the most specific directive
+/// wins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// Directives for targets, longest target first
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Parse a filter, ignoring directives that can't be parsed
+    pub fn parse(spec: &str) -> Self {
+        let mut default = DEFAULT_LEVEL;
+        let mut targets = Vec::new();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        targets.push((target.trim().to_string(), level));
+                    }
+                }
+                None => match directive.parse() {
+                    Ok(level) => default = level,
+                    // A bare target enables every level for it
+                    Err(_) => targets.push((directive.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        targets.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Self { default, targets }
+    }
+
+    /// The filter from `DFT_LOGLEVEL` or `RUST_LOG`, in that order
+    fn from_env() -> Option<Self> {
+        [LOG_ENV.as_str(), "RUST_LOG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .map(|spec| Self::parse(&spec))
+    }
+
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .find(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    /// The most verbose level of any target
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::parse("")
+    }
+}
+
+/// Log file that is renamed to `<name>.1` when it reaches `max_size`, shifting older files up to
+/// `<name>.<max_files>`
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files > 0 {
+            let _ = std::fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+/// A record, kept until the log file it's written to is opened
+struct LogLine {
+    timestamp: DateTime<Local>,
+    level: Level,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+}
+
+impl LogLine {
+    fn new(record: &Record) -> Self {
+        Self {
+            timestamp: Local::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            message: record.args().to_string(),
+        }
+    }
 
+    fn format(&self, format: LogFormat) -> String {
+        let timestamp = self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false);
+        match format {
+            LogFormat::Text => format!(
+                "{} {:<5} {}: {}",
+                timestamp, self.level, self.target, self.message
+            ),
+            LogFormat::Json => serde_json::json!({
+                "timestamp": timestamp,
+                "level": self.level.as_str(),
+                "target": self.target,
+                "file": self.file,
+                "line": self.line,
+                "message": self.message,
+            })
+            .to_string(),
+        }
+    }
+}
+
+enum LogOutput {
+    /// The config, and so the log file, hasn't been read yet
+    Pending(Vec<LogLine>),
+    File(RotatingFile, LogFormat),
+    Disabled,
+}
+
+/// Logger that sends records to both the Logs tab and the log file
+struct DftLogger {
+    filter: RwLock<LogFilter>,
+    output: Mutex<LogOutput>,
+}
+
+impl Log for DftLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.read().unwrap().level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        tui_logger::Drain::new().log(record);
+        let mut output = self.output.lock().unwrap();
+        match &mut *output {
+            LogOutput::Pending(lines) if lines.len() < MAX_PENDING_LINES => {
+                lines.push(LogLine::new(record))
+            }
+            LogOutput::File(file, format) => {
+                // There's nowhere left to report a failure to write the log
+                let _ = file.write_line(&LogLine::new(record).format(*format));
+            }
+            LogOutput::Pending(_) | LogOutput::Disabled => {}
+        }
+    }
+
+    fn flush(&self) {
+        if let LogOutput::File(file, _) = &mut *self.output.lock().unwrap() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+lazy_static! {
+    static ref LOGGER: DftLogger = DftLogger {
+        filter: RwLock::new(LogFilter::from_env().unwrap_or_default()),
+        output: Mutex::new(LogOutput::Pending(Vec::new())),
+    };
+}
+
+fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *LOGGER.filter.write().unwrap() = filter;
+}
+
+/// Install the logger, filtering records by `DFT_LOGLEVEL` or `RUST_LOG`. Records are kept in
+/// memory until [`configure_logs`] opens the log file. Also installs the `tracing` subscriber
+/// that records the spans of each query.
+pub fn initialize_logs() -> Result<()> {
+    log::set_logger(&*LOGGER)?;
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::registry().with(trace::QueryTraceLayer),
+    )?;
+    set_filter(LOGGER.filter.read().unwrap().clone());
+    // Records are filtered before they reach the Logs tab
+    tui_logger::set_default_level(LevelFilter::Trace);
     Ok(())
 }
+
+/// Apply the logging config, opening the log file in `data_dir` and writing the records logged
+/// so far to it
+pub fn configure_logs(config: &LoggingConfig, data_dir: &Path) {
+    if let (None, Some(level)) = (LogFilter::from_env(), &config.level) {
+        set_filter(LogFilter::parse(level));
+    }
+    let file = if config.file {
+        let max_size = config.max_size_bytes().unwrap_or_else(|e| {
+            error!("Error reading log file size, using the default: {}", e);
+            LoggingConfig::default()
+                .max_size_bytes()
+                .unwrap_or_default()
+        });
+        match RotatingFile::open(
+            data_dir.join(LOG_FILE.as_str()),
+            max_size as u64,
+            config.max_files,
+        ) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                error!("Error opening log file: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut output = LOGGER.output.lock().unwrap();
+    let pending = match std::mem::replace(&mut *output, LogOutput::Disabled) {
+        LogOutput::Pending(lines) => lines,
+        _ => Vec::new(),
+    };
+    if let Some(mut file) = file {
+        for line in pending {
+            let _ = file.write_line(&line.format(config.format));
+        }
+        *output = LogOutput::File(file, config.format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::LevelFilter;
+
+    use super::{LogFilter, RotatingFile};
+
+    #[test]
+    fn log_filter_test() {
+        let filter = LogFilter::parse("warn, datafusion=info,datafusion_sql=off,dft::app=trace");
+        assert_eq!(filter.level("tokio"), LevelFilter::Warn);
+        assert_eq!(filter.level("datafusion"), LevelFilter::Info);
+        assert_eq!(filter.level("datafusion::physical_plan"), LevelFilter::Info);
+        assert_eq!(filter.level("datafusion_sql::planner"), LevelFilter::Off);
+        assert_eq!(filter.level("dft::app::handlers"), LevelFilter::Trace);
+        assert_eq!(filter.level("dft::ui"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+
+        let filter = LogFilter::parse("debug,not_a_level=loud");
+        assert_eq!(filter.level("not_a_level"), LevelFilter::Debug);
+        assert_eq!(LogFilter::parse("").level("dft"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn rotating_file_test() {
+        let dir = std::env::temp_dir().join(format!("dft-log-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("dft.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            file.write_line(line).unwrap();
+        }
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+        assert_eq!(read("dft.log").as_deref(), Some("fourth\n"));
+        assert_eq!(read("dft.log.1").as_deref(), Some("third\n"));
+        assert_eq!(read("dft.log.2").as_deref(), Some("second\n"));
+        assert_eq!(read("dft.log.3"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
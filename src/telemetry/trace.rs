@@ -0,0 +1,327 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Recording of the `tracing` spans of a query, exported as a Chrome trace
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use serde_json::{json, Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Name of the span that a query runs in. Only the spans inside one are recorded.
+pub const QUERY_SPAN: &str = "query";
+
+lazy_static! {
+    static ref LAST_TRACE: Mutex<Option<QueryTrace>> = Mutex::new(None);
+}
+
+/// The trace of the last query to finish
+pub fn last_query_trace() -> Option<QueryTrace> {
+    LAST_TRACE.lock().unwrap().clone()
+}
+
+/// A span of a query, timed from the start of the query
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceSpan {
+    pub name: String,
+    pub target: String,
+    pub fields: Vec<(String, String)>,
+    pub start: Duration,
+    pub duration: Duration,
+    /// Time spent with the span entered, e.g. polling an operator's stream
+    pub busy: Duration,
+}
+
+/// The spans recorded while a query ran, the query itself first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryTrace {
+    pub spans: Vec<TraceSpan>,
+}
+
+impl QueryTrace {
+    /// Assign spans to tracks so that the spans on a track are either disjoint or nested, as
+    /// the Chrome trace format requires of the spans of a thread. Returns the track of each span.
+    fn tracks(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.spans.len()).collect();
+        // Parents start no later, and last no shorter, than their children
+        order.sort_by_key(|i| {
+            let span = &self.spans[*i];
+            (span.start, std::cmp::Reverse(span.duration))
+        });
+        // The end of each span open on each track, innermost last
+        let mut tracks: Vec<Vec<Duration>> = Vec::new();
+        let mut assigned = vec![0; self.spans.len()];
+        for i in order {
+            let span = &self.spans[i];
+            let end = span.start + span.duration;
+            let track = tracks.iter_mut().position(|open| {
+                while open.last().is_some_and(|e| *e <= span.start) {
+                    open.pop();
+                }
+                match open.last() {
+                    Some(e) => end <= *e,
+                    None => true,
+                }
+            });
+            let track = track.unwrap_or_else(|| {
+                tracks.push(Vec::new());
+                tracks.len() - 1
+            });
+            tracks[track].push(end);
+            assigned[i] = track;
+        }
+        assigned
+    }
+
+    /// The trace in the Chrome trace event format, which Perfetto and `chrome://tracing` open
+    pub fn chrome_trace(&self) -> Value {
+        let tracks = self.tracks();
+        let mut events: Vec<Value> = Vec::new();
+        for track in 0..tracks.iter().max().map_or(0, |t| t + 1) {
+            events.push(json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": 1,
+                "tid": track,
+                "args": { "name": format!("Track {}", track) },
+            }));
+        }
+        for (span, track) in self.spans.iter().zip(tracks) {
+            let mut args: Map<String, Value> = span
+                .fields
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                .collect();
+            args.insert("busy_us".to_string(), (span.busy.as_micros() as u64).into());
+            events.push(json!({
+                "name": span.name,
+                "cat": span.target,
+                "ph": "X",
+                "ts": span.start.as_micros() as u64,
+                "dur": span.duration.as_micros() as u64,
+                "pid": 1,
+                "tid": track,
+                "args": args,
+            }));
+        }
+        json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(&self.chrome_trace())?)?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor(Vec<(String, String)>);
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+/// Timing of a span that's still open, kept in its extensions
+struct OpenSpan {
+    fields: Vec<(String, String)>,
+    start: Instant,
+    busy: Duration,
+    entered: Option<Instant>,
+}
+
+/// Spans of a query that have closed, kept in the extensions of its query span
+struct ClosedSpans(Vec<(TraceSpan, Instant)>);
+
+/// Layer recording the spans inside each query span, keeping the trace of the last query
+pub struct QueryTraceLayer;
+
+impl<S> Layer<S> for QueryTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let is_query = span.name() == QUERY_SPAN;
+        if !is_query && !span.scope().skip(1).any(|s| s.name() == QUERY_SPAN) {
+            return;
+        }
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let mut extensions = span.extensions_mut();
+        extensions.insert(OpenSpan {
+            fields: fields.0,
+            start: Instant::now(),
+            busy: Duration::ZERO,
+            entered: None,
+        });
+        if is_query {
+            extensions.insert(ClosedSpans(Vec::new()));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                let mut fields = FieldVisitor::default();
+                values.record(&mut fields);
+                for (name, value) in fields.0 {
+                    open.fields.retain(|(n, _)| *n != name);
+                    open.fields.push((name, value));
+                }
+            }
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                open.entered = Some(Instant::now());
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                if let Some(entered) = open.entered.take() {
+                    open.busy += entered.elapsed();
+                }
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+        let closed = TraceSpan {
+            name: span.name().to_string(),
+            target: span.metadata().target().to_string(),
+            fields: open.fields,
+            start: Duration::ZERO,
+            duration: open.start.elapsed(),
+            busy: open.busy,
+        };
+        if span.name() == QUERY_SPAN {
+            let children = span
+                .extensions_mut()
+                .remove::<ClosedSpans>()
+                .map(|c| c.0)
+                .unwrap_or_default();
+            let mut spans = vec![closed];
+            spans.extend(children.into_iter().map(|(mut child, start)| {
+                child.start = start.saturating_duration_since(open.start);
+                child
+            }));
+            *LAST_TRACE.lock().unwrap() = Some(QueryTrace { spans });
+        } else if let Some(query) = span.scope().skip(1).find(|s| s.name() == QUERY_SPAN) {
+            if let Some(closed_spans) = query.extensions_mut().get_mut::<ClosedSpans>() {
+                closed_spans.0.push((closed, open.start));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tracing::info_span;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::{last_query_trace, QueryTrace, QueryTraceLayer, TraceSpan, QUERY_SPAN};
+
+    fn span(name: &str, start: u64, duration: u64) -> TraceSpan {
+        TraceSpan {
+            name: name.to_string(),
+            target: "dft".to_string(),
+            fields: Vec::new(),
+            start: Duration::from_micros(start),
+            duration: Duration::from_micros(duration),
+            busy: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn chrome_trace_test() {
+        let trace = QueryTrace {
+            spans: vec![
+                span("query", 0, 100),
+                span("plan", 0, 10),
+                span("partition 0", 10, 80),
+                span("partition 1", 20, 80),
+                span("scan", 30, 10),
+            ],
+        };
+        // Overlapping partitions can't share a track, nested spans can
+        assert_eq!(trace.tracks(), vec![0, 0, 0, 1, 0]);
+
+        let chrome = trace.chrome_trace();
+        let events = chrome["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 7);
+        assert_eq!(events[2]["name"], "query");
+        assert_eq!(events[2]["ph"], "X");
+        assert_eq!(events[5]["ts"], 20);
+        assert_eq!(events[5]["dur"], 80);
+        assert_eq!(events[5]["tid"], 1);
+    }
+
+    #[test]
+    fn query_trace_layer_test() {
+        let subscriber = tracing_subscriber::registry().with(QueryTraceLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _outside = info_span!("outside").entered();
+            let query = info_span!(QUERY_SPAN, sql = "SELECT 1");
+            {
+                let _query = query.enter();
+                let _plan = info_span!("plan", rows = 1).entered();
+            }
+            drop(query);
+        });
+        let trace = last_query_trace().unwrap();
+        let names: Vec<&str> = trace.spans.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["query", "plan"]);
+        assert_eq!(
+            trace.spans[0].fields,
+            vec![("sql".to_string(), "SELECT 1".to_string())]
+        );
+        assert_eq!(
+            trace.spans[1].fields,
+            vec![("rows".to_string(), "1".to_string())]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use color_eyre::Result;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Chart, Dataset, GraphType, Paragraph, Widget, Wrap,
+    },
+};
+
+use crate::{
+    app::state::chart::{ChartConfig, ChartData, ChartKind, PlotColumn},
+    ui::theme::Theme,
+};
+
+/// Bounds that leave some room when all values are equal
+fn bounds(values: impl Iterator<Item = f64>) -> [f64; 2] {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    match (min.is_finite(), max.is_finite()) {
+        (true, true) if min < max => [min, max],
+        (true, true) => [min - 1.0, max + 1.0],
+        _ => [0.0, 1.0],
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Line or scatter chart with a dataset for each Y column
+fn render_xy_chart(
+    area: Rect,
+    buf: &mut Buffer,
+    x: &PlotColumn,
+    ys: &[(String, PlotColumn)],
+    chart: &ChartConfig,
+    block: Block,
+    theme: &Theme,
+) {
+    let points: Vec<Vec<(f64, f64)>> = ys
+        .iter()
+        .map(|(_, y)| {
+            x.values
+                .iter()
+                .zip(&y.values)
+                .filter_map(|(x, y)| Some(((*x)?, (*y)?)))
+                .collect()
+        })
+        .collect();
+    let graph_type = match chart.kind {
+        ChartKind::Line => GraphType::Line,
+        _ => GraphType::Scatter,
+    };
+    let datasets = ys
+        .iter()
+        .zip(&points)
+        .enumerate()
+        .map(|(i, ((name, _), points))| {
+            Dataset::default()
+                .name(name.clone())
+                .marker(Marker::Braille)
+                .graph_type(graph_type)
+                .style(theme.series(i))
+                .data(points)
+        })
+        .collect();
+
+    let x_bounds = bounds(points.iter().flatten().map(|(x, _)| *x));
+    let y_bounds = bounds(points.iter().flatten().map(|(_, y)| *y));
+    // The formatted values at either end of the X axis, so temporal and categorical values are
+    // labelled as they are shown in the results
+    let x_label = |bound: f64| {
+        x.values
+            .iter()
+            .position(|v| *v == Some(bound))
+            .map(|i| x.labels[i].clone())
+            .unwrap_or_else(|| format_number(bound))
+    };
+    let x_axis = Axis::default()
+        .title(chart.x.clone())
+        .style(theme.text)
+        .bounds(x_bounds)
+        .labels(vec![x_label(x_bounds[0]), x_label(x_bounds[1])]);
+    let y_mid = (y_bounds[0] + y_bounds[1]) / 2.0;
+    let y_axis = Axis::default().style(theme.text).bounds(y_bounds).labels(
+        [y_bounds[0], y_mid, y_bounds[1]]
+            .into_iter()
+            .map(format_number)
+            .collect::<Vec<_>>(),
+    );
+    Chart::new(datasets)
+        .block(block)
+        .x_axis(x_axis)
+        .y_axis(y_axis)
+        .render(area, buf);
+}
+
+/// The narrowest bar that still leaves room for its value
+const MIN_BAR_WIDTH: usize = 3;
+const MAX_BAR_WIDTH: usize = 10;
+
+/// Bar chart with a group of bars for each row, one bar per Y column. Only the rows that fit in
+/// the area are drawn.
+fn render_bar_chart(area: Rect, buf: &mut Buffer, data: &ChartData, block: Block, theme: &Theme) {
+    let legend: Vec<Span> = data
+        .ys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (name, _))| {
+            vec![
+                Span::styled(" ■ ", theme.series(i)),
+                Span::styled(name.clone(), theme.text),
+            ]
+        })
+        .collect();
+    let mut block = block.title_top(Line::from(legend).right_aligned());
+    let inner = block.inner(area);
+
+    // Each group is a bar per series with a gap after every bar, the last being the group gap
+    let rows = data.bar_heights.len();
+    let width = inner.width as usize;
+    let series = data.ys.len().max(1);
+    let bar_width = (width / rows.max(1) / series)
+        .saturating_sub(1)
+        .clamp(MIN_BAR_WIDTH, MAX_BAR_WIDTH);
+    let shown = width / (series * (bar_width + 1));
+    if shown < rows {
+        block = block.title_bottom(
+            Line::styled(format!(" first {} of {} rows ", shown, rows), theme.text).right_aligned(),
+        );
+    }
+
+    let mut bar_chart = BarChart::default()
+        .block(block)
+        .bar_width(bar_width as u16)
+        .group_gap(1)
+        .label_style(theme.text)
+        .value_style(theme.text);
+    for (row, heights) in data.bar_heights.iter().enumerate().take(shown) {
+        // Bars are drawn by their scaled height and labelled with the exact value. Negative
+        // values are rejected when the chart is set.
+        let bars: Vec<Bar> = data
+            .ys
+            .iter()
+            .zip(heights)
+            .enumerate()
+            .map(|(i, ((_, y), height))| {
+                Bar::default()
+                    .value(*height)
+                    .text_value(y.labels[row].clone())
+                    .style(theme.series(i))
+            })
+            .collect();
+        let group = BarGroup::default()
+            .label(Line::from(data.x.labels[row].clone()))
+            .bars(&bars);
+        bar_chart = bar_chart.data(group);
+    }
+    bar_chart.render(area, buf);
+}
+
+/// Draw `data` as configured by `chart`, showing an error in the block if the columns can't be
+/// plotted
+pub fn render_chart(
+    area: Rect,
+    buf: &mut Buffer,
+    data: &Result<ChartData>,
+    chart: &ChartConfig,
+    block: Block,
+    theme: &Theme,
+) {
+    match data {
+        Ok(data) => match chart.kind {
+            ChartKind::Bar => render_bar_chart(area, buf, data, block, theme),
+            _ => render_xy_chart(area, buf, &data.x, &data.ys, chart, block, theme),
+        },
+        Err(e) => Paragraph::new(format!("Unable to plot '{}': {}", chart, e))
+            .style(theme.text)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf),
+    }
+}
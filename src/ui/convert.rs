@@ -15,58 +15,96 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use color_eyre::{eyre::eyre, Result};
+use std::sync::Arc;
+
+use color_eyre::Result;
 use datafusion::arrow::{
-    array::{
-        BooleanArray, Date32Array, Date64Array, Float16Array, Float32Array, Float64Array,
-        Int16Array, Int32Array, Int64Array, Int8Array, ListArray, RecordBatch, StringArray,
-        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
-        TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
-    },
-    datatypes::{DataType, TimeUnit},
+    array::{Array, ArrayRef, RecordBatch},
+    compute::cast,
+    datatypes::DataType,
+    util::display::{ArrayFormatter, DurationFormat, FormatOptions},
 };
 use ratatui::{
     layout::Constraint,
-    style::{palette::tailwind, Stylize},
-    widgets::{Block, Borders, Cell, Row, Table},
+    text::Span,
+    widgets::{Cell, Row, Table},
 };
 
-macro_rules! convert_array_values_to_cells {
-    ($rows:expr, $arr:expr, $typ:ty) => {
-        if let Some(a) = $arr.as_any().downcast_ref::<$typ>() {
-            for i in 0..$rows.len() {
-                let cell = Cell::from(a.value(i).to_string())
-                    .bg(tailwind::BLACK)
-                    .fg(tailwind::WHITE);
-                $rows[i].push(cell);
-            }
+use crate::{app::config::DisplayConfig, ui::theme::Theme};
+
+/// Arrow formatting options derived from the display config
+pub fn format_options(config: &DisplayConfig) -> FormatOptions<'_> {
+    FormatOptions::new()
+        .with_display_error(true)
+        .with_null(&config.null_marker)
+        .with_date_format(config.date_format.as_deref())
+        .with_datetime_format(config.timestamp_format.as_deref())
+        .with_timestamp_format(config.timestamp_format.as_deref())
+        .with_timestamp_tz_format(config.timestamp_tz_format.as_deref())
+        .with_time_format(config.time_format.as_deref())
+        .with_duration_format(DurationFormat::Pretty)
+}
+
+/// Converts timezone aware timestamps to the configured display timezone. Only the timezone
+/// metadata changes so this is cheap, and arrays that can't be converted are returned as is.
+pub fn localize_timestamps(array: &ArrayRef, config: &DisplayConfig) -> ArrayRef {
+    match (array.data_type(), &config.timezone) {
+        (DataType::Timestamp(unit, Some(tz)), Some(display_tz)) if tz.as_ref() != display_tz => {
+            let target = DataType::Timestamp(*unit, Some(display_tz.as_str().into()));
+            cast(array, &target).unwrap_or_else(|_| Arc::clone(array))
         }
-    };
+        _ => Arc::clone(array),
+    }
 }
 
-pub fn record_batch_to_table_header_cells(record_batch: &RecordBatch) -> Vec<Cell> {
-    let mut cells = vec![Cell::new("#").bg(tailwind::ORANGE.c300).fg(tailwind::BLACK)];
+/// Format a single value of `array` into a cell, styling NULLs distinctly
+pub fn format_cell<'a>(
+    array: &dyn Array,
+    formatter: &ArrayFormatter,
+    idx: usize,
+    config: &DisplayConfig,
+    theme: &Theme,
+) -> Cell<'a> {
+    if array.is_null(idx) {
+        Cell::new(config.null_marker.clone()).style(theme.null)
+    } else {
+        Cell::new(formatter.value(idx).to_string()).style(theme.cell)
+    }
+}
+
+pub fn record_batch_to_table_header_cells<'a>(
+    record_batch: &'a RecordBatch,
+    theme: &Theme,
+) -> Vec<Cell<'a>> {
+    let mut cells = vec![Cell::new("#").style(theme.header)];
     record_batch.schema_ref().fields().iter().for_each(|f| {
-        let cell = Cell::new(f.name().as_str())
-            .bg(tailwind::ORANGE.c300)
-            .fg(tailwind::BLACK);
+        let cell = Cell::new(f.name().as_str()).style(theme.header);
         cells.push(cell);
     });
     cells
 }
 
-pub fn create_row_number_cells(record_batch: &RecordBatch) -> Vec<Cell> {
-    let cells: Vec<Cell> = (0..record_batch.num_rows())
-        .map(|i| {
-            Cell::new(i.to_string())
-                .bg(tailwind::BLACK)
-                .fg(tailwind::WHITE)
-        })
+/// Row numbers for the rows of `record_batch`, which starts at row `first_row` of the results
+pub fn create_row_number_cells<'a>(
+    record_batch: &RecordBatch,
+    first_row: usize,
+    theme: &Theme,
+) -> Vec<Cell<'a>> {
+    let cells: Vec<Cell> = (first_row..first_row + record_batch.num_rows())
+        .map(|i| Cell::new(i.to_string()).style(theme.cell))
         .collect();
     cells
 }
 
-pub fn record_batch_to_table_row_cells(record_batch: &RecordBatch) -> Result<Vec<Vec<Cell>>> {
+/// Format the rows of `record_batch`, styling the values that contain the `search` term
+/// (ignoring case) as matches
+pub fn record_batch_to_table_row_cells<'a>(
+    record_batch: &RecordBatch,
+    first_row: usize,
+    search: Option<&str>,
+    config: &DisplayConfig,
+    theme: &Theme,
+) -> Result<Vec<Vec<Cell<'a>>>> {
     let row_count = record_batch.num_rows();
     let column_count = record_batch.num_columns();
 
@@ -75,89 +113,76 @@ pub fn record_batch_to_table_row_cells(record_batch: &RecordBatch) -> Result<Vec
         rows.push(Vec::with_capacity(column_count))
     }
 
-    let row_number_cells = create_row_number_cells(record_batch);
+    let row_number_cells = create_row_number_cells(record_batch, first_row, theme);
 
     for (i, cell) in row_number_cells.into_iter().enumerate() {
         rows[i].push(cell);
     }
 
+    let options = format_options(config);
+    let search = search.map(str::to_lowercase);
     for arr in record_batch.columns() {
-        match arr.data_type() {
-            DataType::Utf8 => convert_array_values_to_cells!(rows, arr, StringArray),
-            DataType::Int8 => convert_array_values_to_cells!(rows, arr, Int8Array),
-            DataType::Int16 => convert_array_values_to_cells!(rows, arr, Int16Array),
-            DataType::Int32 => convert_array_values_to_cells!(rows, arr, Int32Array),
-            DataType::Int64 => convert_array_values_to_cells!(rows, arr, Int64Array),
-            DataType::UInt8 => convert_array_values_to_cells!(rows, arr, UInt8Array),
-            DataType::UInt16 => convert_array_values_to_cells!(rows, arr, UInt16Array),
-            DataType::UInt32 => convert_array_values_to_cells!(rows, arr, UInt32Array),
-            DataType::UInt64 => convert_array_values_to_cells!(rows, arr, UInt64Array),
-            DataType::Date32 => convert_array_values_to_cells!(rows, arr, Date32Array),
-            DataType::Date64 => convert_array_values_to_cells!(rows, arr, Date64Array),
-            DataType::Timestamp(TimeUnit::Second, _) => {
-                convert_array_values_to_cells!(rows, arr, TimestampSecondArray)
-            }
-            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
-                convert_array_values_to_cells!(rows, arr, TimestampNanosecondArray)
-            }
-            DataType::Timestamp(TimeUnit::Microsecond, _) => {
-                convert_array_values_to_cells!(rows, arr, TimestampMicrosecondArray)
-            }
-            DataType::Timestamp(TimeUnit::Millisecond, _) => {
-                convert_array_values_to_cells!(rows, arr, TimestampMillisecondArray)
-            }
-            DataType::Float16 => convert_array_values_to_cells!(rows, arr, Float16Array),
-            DataType::Float32 => convert_array_values_to_cells!(rows, arr, Float32Array),
-            DataType::Float64 => convert_array_values_to_cells!(rows, arr, Float64Array),
-            DataType::Boolean => convert_array_values_to_cells!(rows, arr, BooleanArray),
-            DataType::List(_) => {
-                if let Some(a) = arr.as_any().downcast_ref::<ListArray>() {
-                    for (i, d) in rows.iter_mut().enumerate().take(arr.len()) {
-                        let v = a.value(i);
-                        match v.data_type() {
-                            DataType::Int16 => {
-                                if let Some(i_arr) = v.as_any().downcast_ref::<Int16Array>() {
-                                    let combined: Vec<String> = i_arr
-                                        .iter()
-                                        .map(|maybe_v| {
-                                            if let Some(v) = maybe_v {
-                                                v.to_string()
-                                            } else {
-                                                "".to_string()
-                                            }
-                                        })
-                                        .collect();
-                                    d.push(Cell::from(combined.join(",")))
-                                }
-                            }
-                            DataType::Int32 => {
-                                if let Some(i_arr) = v.as_any().downcast_ref::<Int32Array>() {
-                                    let combined: Vec<String> = i_arr
-                                        .iter()
-                                        .map(|maybe_v| {
-                                            if let Some(v) = maybe_v {
-                                                v.to_string()
-                                            } else {
-                                                "".to_string()
-                                            }
-                                        })
-                                        .collect();
-                                    d.push(Cell::from(combined.join(",")))
-                                }
-                            }
-
-                            _ => {}
-                        }
+        let arr = localize_timestamps(arr, config);
+        let formatter = ArrayFormatter::try_new(arr.as_ref(), &options)?;
+        for (i, row) in rows.iter_mut().enumerate() {
+            let cell = match &search {
+                Some(term) => {
+                    let value = formatter.value(i).to_string();
+                    if value.to_lowercase().contains(term) {
+                        Cell::new(value).style(theme.search_match)
+                    } else {
+                        format_cell(arr.as_ref(), &formatter, i, config, theme)
                     }
                 }
-            }
-
-            dtype => return Err(eyre!("Table conversion not setup for type {}", dtype)),
+                None => format_cell(arr.as_ref(), &formatter, i, config, theme),
+            };
+            row.push(cell);
         }
     }
     Ok(rows)
 }
 
+/// Number of rows, from the start of the results, used to size columns
+const COLUMN_WIDTH_SAMPLE_ROWS: usize = 100;
+
+/// Width of each column of `batches` that fits its header and the first sampled values,
+/// bounded by the configured minimum and maximum column widths.
+pub fn column_widths(batches: &[RecordBatch], config: &DisplayConfig) -> Result<Vec<u16>> {
+    let Some(first_batch) = batches.first() else {
+        return Ok(Vec::new());
+    };
+    let mut widths: Vec<usize> = first_batch
+        .schema_ref()
+        .fields()
+        .iter()
+        .map(|f| Span::raw(f.name().as_str()).width())
+        .collect();
+
+    let options = format_options(config);
+    let mut remaining = COLUMN_WIDTH_SAMPLE_ROWS;
+    for batch in batches {
+        if remaining == 0 {
+            break;
+        }
+        let rows = batch.num_rows().min(remaining);
+        for (col, arr) in batch.columns().iter().enumerate() {
+            let arr = localize_timestamps(arr, config);
+            let formatter = ArrayFormatter::try_new(arr.as_ref(), &options)?;
+            for row in 0..rows {
+                let width = Span::raw(formatter.value(row).to_string()).width();
+                widths[col] = widths[col].max(width);
+            }
+        }
+        remaining -= rows;
+    }
+
+    let (min, max) = (config.min_column_width, config.max_column_width.max(1));
+    Ok(widths
+        .into_iter()
+        .map(|w| (w.min(u16::MAX as usize) as u16).clamp(min.min(max), max))
+        .collect())
+}
+
 pub fn empty_results_table<'frame>() -> Table<'frame> {
     let header_row = Row::new(vec!["Result"]);
     let value_row = [Row::new(vec!["No results"])];
@@ -165,47 +190,46 @@ pub fn empty_results_table<'frame>() -> Table<'frame> {
     Table::new(value_row, width).header(header_row)
 }
 
-pub fn record_batches_to_table<'frame, 'results>(
-    record_batches: &'results [RecordBatch],
-) -> Result<Table<'frame>>
-where
-    // The results come from sql_tab state which persists until the next query is run which is
-    // longer than a frame lifetime.
-    'results: 'frame,
-{
-    if record_batches.is_empty() {
-        Ok(empty_results_table())
-    } else {
-        let first_batch = &record_batches[0];
-        let header_cells = record_batch_to_table_header_cells(first_batch);
-        let header_row = Row::from_iter(header_cells).bold();
-        let rows: Result<Vec<Row>> = record_batches.iter().try_fold(Vec::new(), |mut acc, b| {
-            let batch_row_cells = record_batch_to_table_row_cells(b)?;
-            let rows: Vec<Row> = batch_row_cells.into_iter().map(Row::from_iter).collect();
-            acc.extend(rows);
-            Ok(acc)
-        });
-        let column_count = first_batch.num_columns() + 1;
-        let widths = (0..column_count).map(|_| Constraint::Fill(1));
-        let block = Block::default().borders(Borders::all());
-        Ok(Table::new(rows?, widths).header(header_row).block(block))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use datafusion::arrow::array::{
-        ArrayRef, Int16Array, Int32Array, Int64Array, Int8Array, RecordBatch, StringArray,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+    use datafusion::arrow::{
+        array::{
+            ArrayRef, Decimal128Array, Int16Array, Int32Array, Int64Array, Int8Array, ListArray,
+            RecordBatch, StringArray, StructArray, TimestampSecondArray, UInt16Array, UInt32Array,
+            UInt64Array, UInt8Array,
+        },
+        datatypes::{DataType, Field, Int32Type},
     };
     use ratatui::{
         style::{palette::tailwind, Stylize},
         widgets::Cell,
     };
 
-    use super::{record_batch_to_table_header_cells, record_batch_to_table_row_cells};
+    use super::{
+        column_widths, record_batch_to_table_header_cells, record_batch_to_table_row_cells,
+    };
+    use crate::{app::config::DisplayConfig, ui::theme::Theme};
+
+    #[test]
+    fn column_widths_test() {
+        let id: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let name: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("short"),
+            None,
+            Some("a much longer value that does not fit in the maximum column width"),
+        ]));
+        let description: ArrayRef = Arc::new(StringArray::from(vec!["x", "y", "z"]));
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", id),
+            ("name", name),
+            ("description", description),
+        ])
+        .unwrap();
+        let widths = column_widths(&[batch], &DisplayConfig::default()).unwrap();
+        assert_eq!(widths, vec![4, 40, 11]);
+    }
 
     #[test]
     fn record_batch_to_header_test() {
@@ -213,7 +237,7 @@ mod tests {
         let b: ArrayRef = Arc::new(Int8Array::from(vec![1, 2, 3]));
 
         let batch = RecordBatch::try_from_iter(vec![("a", a), ("b", b)]).unwrap();
-        let header_cells = record_batch_to_table_header_cells(&batch);
+        let header_cells = record_batch_to_table_header_cells(&batch, &Theme::dark());
         assert_eq!(
             header_cells,
             vec![
@@ -237,7 +261,14 @@ mod tests {
         let a: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c"]));
 
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -256,7 +287,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(Int8Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -275,7 +313,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(Int16Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -294,7 +339,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -313,7 +365,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -332,7 +391,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(UInt8Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -351,7 +417,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(UInt16Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -370,7 +443,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(UInt32Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -389,7 +469,14 @@ mod tests {
 
         let a: ArrayRef = Arc::new(UInt64Array::from(vec![1, 2, 3]));
         let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -412,7 +499,14 @@ mod tests {
         let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
         let b: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c"]));
         let batch = RecordBatch::try_from_iter(vec![("a", a), ("b", b)]).unwrap();
-        let a_table_cells = record_batch_to_table_row_cells(&batch).unwrap();
+        let a_table_cells = record_batch_to_table_row_cells(
+            &batch,
+            0,
+            None,
+            &DisplayConfig::default(),
+            &Theme::dark(),
+        )
+        .unwrap();
         let expected = vec![
             vec![
                 Cell::new("0").bg(tailwind::BLACK).fg(tailwind::WHITE),
@@ -432,4 +526,66 @@ mod tests {
         ];
         assert_eq!(a_table_cells, expected);
     }
+
+    #[test]
+    fn nulls_and_nested_types_to_rows_test() {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None]));
+        let b: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(12345), None])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let c: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            None,
+        ]));
+        let d: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("x", DataType::Utf8, true)),
+            Arc::new(StringArray::from(vec![Some("s"), None])) as ArrayRef,
+        )]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("a", a), ("b", b), ("c", c), ("d", d)]).unwrap();
+        let config = DisplayConfig {
+            null_marker: "∅".to_string(),
+            ..Default::default()
+        };
+        let theme = Theme::dark();
+        let table_cells =
+            record_batch_to_table_row_cells(&batch, 0, None, &config, &theme).unwrap();
+        let expected = vec![
+            vec![
+                Cell::new("0").style(theme.cell),
+                Cell::new("1").style(theme.cell),
+                Cell::new("123.45").style(theme.cell),
+                Cell::new("[1, ∅, 3]").style(theme.cell),
+                Cell::new("{x: s}").style(theme.cell),
+            ],
+            vec![
+                Cell::new("1").style(theme.cell),
+                Cell::new("∅").style(theme.null),
+                Cell::new("∅").style(theme.null),
+                Cell::new("∅").style(theme.null),
+                Cell::new("{x: ∅}").style(theme.cell),
+            ],
+        ];
+        assert_eq!(table_cells, expected);
+    }
+
+    #[test]
+    fn timestamp_timezone_to_rows_test() {
+        let a: ArrayRef =
+            Arc::new(TimestampSecondArray::from(vec![0]).with_timezone("+00:00".to_string()));
+        let batch = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+        let config = DisplayConfig {
+            timezone: Some("+02:00".to_string()),
+            ..Default::default()
+        };
+        let theme = Theme::dark();
+        let table_cells =
+            record_batch_to_table_row_cells(&batch, 0, None, &config, &theme).unwrap();
+        assert_eq!(
+            table_cells[0][1],
+            Cell::new("1970-01-01T02:00:00+02:00").style(theme.cell)
+        );
+    }
 }
@@ -0,0 +1,113 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+use crate::{
+    app::{
+        keymap::{Action, Keymap, Mode},
+        state::detail::{DetailNode, RecordDetail},
+    },
+    ui::{theme::Theme, SelectedTab},
+};
+
+fn tree_line<'a>(depth: usize, node: &DetailNode, theme: &Theme) -> Line<'a> {
+    let marker = match (node.has_children(), node.expanded()) {
+        (false, _) => "  ",
+        (true, false) => "▸ ",
+        (true, true) => "▾ ",
+    };
+    let value_style = if node.is_null() {
+        theme.null
+    } else {
+        theme.cell
+    };
+    // Only the first line of multi-line values fits in the tree, the rest is in the value pane
+    let value = node
+        .summary()
+        .unwrap_or_else(|| node.value().lines().next().unwrap_or_default());
+    Line::from(vec![
+        Span::styled(format!("{}{}", "  ".repeat(depth), marker), theme.text),
+        Span::styled(format!("{}: ", node.label()), theme.header),
+        Span::styled(value.to_string(), value_style),
+    ])
+}
+
+/// Render the record detail view as a popup over `area`, with the tree of values above the
+/// full value of the selected node.
+pub fn render_detail(
+    area: Rect,
+    buf: &mut Buffer,
+    detail: &RecordDetail,
+    tab: SelectedTab,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let help = keymap.help(
+        tab,
+        Mode::Detail,
+        &[Action::ToggleExpand, Action::CloseDetail],
+    );
+    let block = Block::default()
+        .title(format!(" Row {} ", detail.row()))
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    let inner = block.inner(popup);
+    block.render(popup, buf);
+
+    let [tree_area, value_area] =
+        Layout::vertical([Constraint::Fill(2), Constraint::Fill(1)]).areas(inner);
+    let lines = detail.lines();
+    let items = lines
+        .iter()
+        .map(|(depth, node)| tree_line(*depth, node, theme));
+    let list = List::new(items).highlight_style(theme.selected);
+    let mut list_state = ListState::default().with_selected(Some(detail.selected()));
+    StatefulWidget::render(list, tree_area, buf, &mut list_state);
+
+    if let Some(node) = detail.selected_node() {
+        let value_style = if node.is_null() {
+            theme.null
+        } else {
+            theme.text
+        };
+        let block = Block::default()
+            .title(format!(" {} ", node.label()))
+            .borders(Borders::TOP)
+            .border_style(theme.border);
+        Paragraph::new(node.value().to_string())
+            .style(value_style)
+            .wrap(Wrap { trim: false })
+            .scroll((detail.value_scroll(), 0))
+            .block(block)
+            .render(value_area, buf);
+    }
+}
@@ -0,0 +1,133 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap,
+    },
+};
+
+use crate::{
+    app::{
+        keymap::{Action, Keymap, Mode},
+        state::functions::{FunctionBrowser, FunctionInfo},
+    },
+    ui::{theme::Theme, SelectedTab},
+};
+
+fn details<'a>(function: &FunctionInfo, theme: &Theme) -> Vec<Line<'a>> {
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", label), theme.header),
+            Span::styled(value, theme.text),
+        ])
+    };
+    let mut lines = Vec::new();
+    if !function.aliases.is_empty() {
+        lines.push(field("Aliases", function.aliases.join(", ")));
+    }
+    for signature in &function.signatures {
+        lines.push(field(
+            "Signature",
+            format!("{}({})", function.name, signature),
+        ));
+    }
+    if let Some(return_type) = &function.return_type {
+        lines.push(field("Returns", return_type.clone()));
+    }
+    lines
+}
+
+/// Render the function browser as a popup over `area`, with a row for each function matching
+/// the search and the signatures and aliases of the selected function
+pub fn render_functions(
+    area: Rect,
+    buf: &mut Buffer,
+    functions: &FunctionBrowser,
+    tab: SelectedTab,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let help = keymap.help(
+        tab,
+        Mode::Functions,
+        &[
+            Action::InsertFunction,
+            Action::SearchFunctions,
+            Action::CloseFunctions,
+        ],
+    );
+    let filtered = functions.filtered();
+    let mut block = Block::default()
+        .title(format!(" Functions ({}) ", filtered.len()))
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    if let Some(filter) = functions.filter() {
+        block = block.title(Span::styled(format!(" Search: {} ", filter), theme.text));
+    }
+    let inner = block.inner(popup);
+    block.render(popup, buf);
+    let [table_area, details_area] =
+        Layout::vertical([Constraint::Fill(2), Constraint::Fill(1)]).areas(inner);
+
+    let rows = filtered.iter().map(|f| {
+        Row::new(vec![
+            f.name.clone(),
+            f.kind.name().to_string(),
+            f.return_type.clone().unwrap_or_default(),
+            f.volatility
+                .map(|v| format!("{:?}", v).to_lowercase())
+                .unwrap_or_default(),
+        ])
+        .style(theme.cell)
+    });
+    let widths = [
+        Constraint::Fill(3),
+        Constraint::Length(9),
+        Constraint::Fill(2),
+        Constraint::Length(10),
+    ];
+    let header = Row::new(vec!["Function", "Kind", "Returns", "Volatility"]).style(theme.header);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .highlight_style(theme.selected);
+    let mut state = TableState::default().with_selected(Some(functions.selected()));
+    StatefulWidget::render(table, table_area, buf, &mut state);
+
+    if let Some(function) = functions.selected_function() {
+        let block = Block::default()
+            .title(format!(" {} ", function.name))
+            .borders(Borders::TOP)
+            .border_style(theme.border);
+        Paragraph::new(details(function, theme))
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(details_area, buf);
+    }
+}
@@ -0,0 +1,61 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+use crate::{app::state::metadata::Metadata, ui::theme::Theme};
+
+/// Render the visible nodes of `metadata` as an indented tree, or its status while it is loading
+/// or if it couldn't be loaded
+pub fn render_metadata_tree(
+    area: Rect,
+    buf: &mut Buffer,
+    metadata: &Metadata,
+    block: Block,
+    theme: &Theme,
+) {
+    if let Some(status) = metadata.status() {
+        Paragraph::new(status.to_string())
+            .style(theme.text)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+        return;
+    }
+    let items = metadata.lines().into_iter().map(|(depth, node)| {
+        let marker = match (node.has_children(), node.expanded()) {
+            (false, _) => "  ",
+            (true, false) => "▸ ",
+            (true, true) => "▾ ",
+        };
+        Line::from(vec![
+            Span::styled(format!("{}{}", "  ".repeat(depth), marker), theme.text),
+            Span::styled(node.label().to_string(), theme.text),
+            Span::styled(format!("  {}", node.detail()), theme.null),
+        ])
+    });
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selected);
+    let mut state = ListState::default().with_selected(Some(metadata.selected()));
+    StatefulWidget::render(list, area, buf, &mut state);
+}
@@ -15,17 +15,27 @@
 // specific language governing permissions and limitations
 // under the License.
 
+pub mod chart;
 pub mod convert;
+pub mod detail;
+pub mod functions;
+pub mod metadata;
+pub mod profile;
+pub mod prompt;
+pub mod resources;
+pub mod results;
 pub mod tabs;
+pub mod theme;
 
-use ratatui::{prelude::*, style::palette::tailwind};
+use ratatui::prelude::*;
 use strum::{Display, EnumIter, FromRepr};
 
-use crate::app::App;
+use crate::app::{keymap::Keymap, App};
 
 use self::tabs::{context, history, logs, sql};
+use self::theme::Theme;
 
-#[derive(Clone, Copy, Debug, Display, FromRepr, EnumIter)]
+#[derive(Clone, Copy, Debug, Display, FromRepr, EnumIter, PartialEq, Eq, Hash)]
 pub enum SelectedTab {
     #[allow(clippy::upper_case_acronyms)]
     #[strum(to_string = "SQL")]
@@ -42,65 +52,33 @@ pub enum SelectedTab {
 }
 
 impl SelectedTab {
-    pub fn title(self) -> Line<'static> {
-        let padding = Span::from("  ");
+    /// Name of the tab in the `[keymap]` config section
+    pub fn keymap_name(self) -> &'static str {
         match self {
-            SelectedTab::SQL => {
-                let title = Span::from("SQL (1)").bold();
-                Line::from_iter(vec![padding.clone(), title, padding.clone()])
-                    .fg(tailwind::SLATE.c200)
-                    .bg(self.bg())
-            }
+            Self::SQL => "sql",
             #[cfg(feature = "flightsql")]
-            Self::FlightSQL => {
-                let title = Span::from("FlightSQL (2)").bold();
-                Line::from_iter(vec![padding.clone(), title, padding.clone()])
-                    .fg(tailwind::SLATE.c200)
-                    .bg(self.bg())
-            }
-            Self::Logs => {
-                #[cfg(feature = "flightsql")]
-                let title = Span::from("LOGS (4)").bold();
-
-                #[cfg(not(feature = "flightsql"))]
-                let title = Span::from("LOGS (3)").bold();
-
-                Line::from_iter(vec![padding.clone(), title, padding.clone()])
-                    .fg(tailwind::SLATE.c200)
-                    .bg(self.bg())
-            }
-            Self::Context => {
-                #[cfg(feature = "flightsql")]
-                let title = Span::from("CONTEXT (5)").bold();
-
-                #[cfg(not(feature = "flightsql"))]
-                let title = Span::from("CONTEXT (4)").bold();
-
-                Line::from_iter(vec![padding.clone(), title, padding.clone()])
-                    .fg(tailwind::SLATE.c200)
-                    .bg(self.bg())
-            }
-            Self::History => {
-                #[cfg(feature = "flightsql")]
-                let title = Span::from("HISTORY (3)");
-                #[cfg(not(feature = "flightsql"))]
-                let title = Span::from("HISTORY (2)");
-                Line::from_iter(vec![padding.clone(), title, padding.clone()])
-                    .fg(tailwind::SLATE.c200)
-                    .bg(self.bg())
-            }
+            Self::FlightSQL => "flightsql",
+            Self::History => "history",
+            Self::Logs => "logs",
+            Self::Context => "context",
         }
     }
 
-    const fn bg(self) -> Color {
-        match self {
-            Self::SQL => tailwind::ORANGE.c700,
-            Self::Logs => tailwind::ORANGE.c700,
-            Self::Context => tailwind::ORANGE.c700,
-            Self::History => tailwind::ORANGE.c700,
+    pub fn title(self, keymap: &Keymap, theme: &Theme) -> Line<'static> {
+        let padding = Span::from("  ");
+        let name = match self {
+            Self::SQL => "SQL",
             #[cfg(feature = "flightsql")]
-            Self::FlightSQL => tailwind::ORANGE.c700,
-        }
+            Self::FlightSQL => "FlightSQL",
+            Self::Logs => "LOGS",
+            Self::Context => "CONTEXT",
+            Self::History => "HISTORY",
+        };
+        let title = match keymap.tab_key(self) {
+            Some(key) => format!("{} ({})", name, key),
+            None => name.to_string(),
+        };
+        Line::from_iter(vec![padding.clone(), Span::from(title).bold(), padding]).style(theme.tab)
     }
 
     /// Get the previous tab, if there is no previous tab return the current tab.
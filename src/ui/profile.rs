@@ -0,0 +1,69 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::Line,
+    widgets::{Block, Borders, Clear, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::{
+        config::DisplayConfig,
+        keymap::{Action, Keymap, Mode},
+        state::profile::Profile,
+    },
+    ui::{results::ResultsTable, theme::Theme, SelectedTab},
+};
+
+/// Render the column profile as a popup over `area`, with a row of statistics for each column
+pub fn render_profile(
+    area: Rect,
+    buf: &mut Buffer,
+    profile: &Profile,
+    tab: SelectedTab,
+    keymap: &Keymap,
+    config: &DisplayConfig,
+    theme: &Theme,
+) {
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let help = keymap.help(tab, Mode::Profile, &[Action::CloseProfile]);
+    let sampled = match profile.sampled() {
+        Some(sampled) => format!(", top values and histograms of the first {}", sampled),
+        None => String::new(),
+    };
+    let block = Block::default()
+        .title(format!(
+            " Profile of {} ({} rows{}) ",
+            profile.table().unwrap_or("results"),
+            profile.rows(),
+            sampled
+        ))
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    let table = ResultsTable::new(profile.batches(), config, theme).block(block);
+    StatefulWidget::render(table, popup, buf, &mut profile.state().borrow_mut());
+}
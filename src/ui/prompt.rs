@@ -0,0 +1,40 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    text::Span,
+    widgets::Widget,
+};
+
+use crate::{app::state::prompt::Prompt, ui::theme::Theme};
+
+/// Render a prompt on a single line as `<label>: <input>`
+pub fn render_prompt(area: Rect, buf: &mut Buffer, prompt: &Prompt, theme: &Theme) {
+    let label = format!("{}: ", prompt.kind().label());
+    let [label_area, input_area] = Layout::horizontal([
+        Constraint::Length(label.chars().count() as u16),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    Span::styled(label, theme.border_active).render(label_area, buf);
+    let mut input = prompt.input().clone();
+    input.set_style(theme.text);
+    input.set_cursor_line_style(theme.text);
+    input.render(input_area, buf);
+}
@@ -0,0 +1,70 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datafusion::execution::memory_pool::human_readable_size;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::{app::resources::ResourceUsage, ui::theme::Theme};
+
+/// Reserved memory above these fractions of the limit is shown as a warning or an error
+const MEMORY_WARN_RATIO: f64 = 0.8;
+const MEMORY_ERROR_RATIO: f64 = 0.95;
+
+/// Style of the reserved memory, which stands out as it nears the limit
+pub fn memory_style(usage: &ResourceUsage, theme: &Theme) -> Style {
+    match usage.memory_ratio() {
+        Some(ratio) if ratio >= MEMORY_ERROR_RATIO => theme.log_error,
+        Some(ratio) if ratio >= MEMORY_WARN_RATIO => theme.log_warn,
+        _ => theme.text,
+    }
+}
+
+/// Reserved memory and its limit, e.g. `1.5 GB / 4.0 GB`
+pub fn memory_text(usage: &ResourceUsage) -> String {
+    let limit = usage
+        .limit
+        .map(human_readable_size)
+        .unwrap_or("unlimited".to_string());
+    format!("{} / {}", human_readable_size(usage.reserved), limit)
+}
+
+/// One line summary of the resources in use, shown in the status bar
+pub fn status_line(usage: &ResourceUsage, theme: &Theme) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(" Memory ", theme.header),
+        Span::styled(memory_text(usage), memory_style(usage, theme)),
+    ];
+    let mut field = |label: &str, value: String| {
+        spans.push(Span::styled(" | ", theme.text));
+        spans.push(Span::styled(format!("{} ", label), theme.header));
+        spans.push(Span::styled(value, theme.text));
+    };
+    if let Some(spilled) = usage.spilled {
+        field("Spill", human_readable_size(spilled));
+    }
+    field("Queries", usage.active_queries.to_string());
+    if let Some(rss) = usage.rss {
+        field("RSS", human_readable_size(rss));
+    }
+    if let Some(cpu) = usage.cpu {
+        field("CPU", format!("{:.0}%", cpu));
+    }
+    Line::from(spans)
+}
@@ -0,0 +1,213 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use datafusion::arrow::array::RecordBatch;
+use log::error;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Cell, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::{
+    app::{
+        config::DisplayConfig,
+        state::{results::ResultsState, view::SortColumn},
+    },
+    ui::{
+        convert::{
+            column_widths, empty_results_table, record_batch_to_table_header_cells,
+            record_batch_to_table_row_cells,
+        },
+        theme::Theme,
+    },
+};
+
+/// Table of query results that only formats the rows visible in its area, so the cost of a
+/// frame does not depend on the size of the results.
+pub struct ResultsTable<'a> {
+    batches: &'a [RecordBatch],
+    config: &'a DisplayConfig,
+    theme: &'a Theme,
+    block: Option<Block<'a>>,
+    sort: Option<SortColumn>,
+}
+
+impl<'a> ResultsTable<'a> {
+    pub fn new(batches: &'a [RecordBatch], config: &'a DisplayConfig, theme: &'a Theme) -> Self {
+        Self {
+            batches,
+            config,
+            theme,
+            block: None,
+            sort: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// The column the results are sorted by, marked in the header
+    pub fn sort(mut self, sort: Option<SortColumn>) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Formats the `columns` of rows `[start, end)` by slicing the batches that hold them
+    fn visible_rows(
+        &self,
+        state: &ResultsState,
+        columns: &[usize],
+        start: usize,
+        end: usize,
+    ) -> Vec<Row<'a>> {
+        let mut rows = Vec::with_capacity(end.saturating_sub(start));
+        let mut row = start;
+        while row < end {
+            let Some((batch_idx, batch_row)) = state.locate(row) else {
+                break;
+            };
+            let batch = &self.batches[batch_idx];
+            let len = (batch.num_rows() - batch_row).min(end - row);
+            let slice = batch.slice(batch_row, len);
+            let cells = slice
+                .project(columns)
+                .map_err(Into::into)
+                .and_then(|slice| {
+                    record_batch_to_table_row_cells(
+                        &slice,
+                        row,
+                        state.search(),
+                        self.config,
+                        self.theme,
+                    )
+                });
+            match cells {
+                Ok(cells) => rows.extend(cells.into_iter().map(Row::from_iter)),
+                Err(e) => rows.push(Row::new(vec![e.to_string()])),
+            }
+            row += len;
+        }
+        rows
+    }
+}
+
+/// Shows which column is selected, and whether there are columns hidden on either side
+fn column_indicator(state: &ResultsState, columns: &[usize]) -> String {
+    let hidden_left = columns.last().is_some_and(|last| last + 1 > columns.len());
+    let hidden_right = columns
+        .last()
+        .is_some_and(|last| last + 1 < state.column_count());
+    let mut indicator = format!(
+        " {}Column {} of {}{} ",
+        if hidden_left { "◀ " } else { "" },
+        state.selected_column() + 1,
+        state.column_count(),
+        if hidden_right { " ▶" } else { "" },
+    );
+    if state.frozen_columns() > 0 {
+        indicator.push_str(&format!("({} frozen) ", state.frozen_columns()));
+    }
+    indicator
+}
+
+impl<'a> StatefulWidget for ResultsTable<'a> {
+    type State = ResultsState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let inner = self.block.as_ref().map_or(area, |b| b.inner(area));
+
+        let Some(first_batch) = self.batches.iter().find(|b| b.num_columns() > 0) else {
+            if let Some(block) = self.block {
+                block.render(area, buf);
+            }
+            Widget::render(empty_results_table(), inner, buf);
+            return;
+        };
+
+        if state.column_widths().is_none() {
+            let widths = column_widths(self.batches, self.config).unwrap_or_else(|e| {
+                error!("Unable to size result columns: {}", e);
+                vec![self.config.max_column_width; first_batch.num_columns()]
+            });
+            state.set_column_widths(widths);
+        }
+
+        // The row number column fits the largest row number and is always shown
+        let row_number_width = state.row_count().saturating_sub(1).to_string().len().max(1) as u16;
+        let columns = state.visible_columns(inner.width.saturating_sub(row_number_width + 1));
+
+        if let Some(block) = self.block.clone() {
+            let indicator = column_indicator(state, &columns);
+            block
+                .title(Line::from(indicator).right_aligned())
+                .render(area, buf);
+        }
+
+        // One line is used by the header
+        state.set_viewport_rows(inner.height.saturating_sub(1) as usize);
+        let start = state.offset();
+        let end = (start + state.viewport_rows()).min(state.row_count());
+
+        let header_batch = match first_batch.project(&columns) {
+            Ok(batch) => batch,
+            Err(e) => {
+                Widget::render(Line::from(e.to_string()), inner, buf);
+                return;
+            }
+        };
+        let mut header_cells = record_batch_to_table_header_cells(&header_batch, self.theme);
+        for (pos, column) in columns.iter().enumerate() {
+            let selected = *column == state.selected_column();
+            let sort = self.sort.filter(|s| s.column == *column);
+            if selected || sort.is_some() {
+                let name = header_batch.schema_ref().field(pos).name();
+                let marker = match sort {
+                    Some(s) if s.descending => " ▼",
+                    Some(_) => " ▲",
+                    None => "",
+                };
+                let style = if selected {
+                    self.theme.selected
+                } else {
+                    self.theme.header
+                };
+                header_cells[pos + 1] = Cell::new(format!("{}{}", name, marker)).style(style);
+            }
+        }
+        let header = Row::from_iter(header_cells).bold();
+        let rows = self.visible_rows(state, &columns, start, end);
+
+        let column_widths = state.column_widths().as_deref().unwrap_or_default();
+        let widths = std::iter::once(Constraint::Length(row_number_width)).chain(
+            columns
+                .iter()
+                .map(|c| Constraint::Length(column_widths.get(*c).copied().unwrap_or(0))),
+        );
+        let table = Table::new(rows, widths)
+            .header(header)
+            .highlight_style(self.theme.selected);
+
+        let mut table_state =
+            TableState::default().with_selected(state.selected().map(|s| s - start));
+        StatefulWidget::render(table, inner, buf, &mut table_state);
+    }
+}
@@ -15,49 +15,360 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use datafusion::execution::memory_pool::human_readable_size;
 use ratatui::{
     buffer::Buffer,
-    layout::{Constraint, Direction, Layout, Rect},
-    widgets::{Block, Borders, List, Widget},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, Gauge, List, Paragraph, Row, StatefulWidget, Table, TableState,
+        Widget, Wrap,
+    },
 };
 
-use crate::app::App;
+use crate::{
+    app::{
+        keymap::{Action, Mode},
+        state::settings::{settings, SettingsRow},
+        App,
+    },
+    ui::{
+        metadata::render_metadata_tree,
+        prompt::render_prompt,
+        resources::{memory_style, memory_text},
+        results::ResultsTable,
+        SelectedTab,
+    },
+};
+
+pub fn render_catalog(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Catalog ")
+        .border_style(theme.border_active);
+    match app.state.context_tab.catalog() {
+        Some(catalog) => render_metadata_tree(area, buf, catalog, block, theme),
+        None => block.render(area, buf),
+    }
+}
+
+/// The type, name and properties of the selected catalog object
+pub fn render_object(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Details ")
+        .border_style(theme.border);
+    let node = app
+        .state
+        .context_tab
+        .catalog()
+        .as_ref()
+        .and_then(|c| c.selected_node());
+    let Some(node) = node else {
+        block.render(area, buf);
+        return;
+    };
+    let field = |name: &str, value: &str| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", name), theme.header),
+            Span::styled(value.to_string(), theme.text),
+        ])
+    };
+    let mut lines = vec![field("Type", node.detail())];
+    if let Some(name) = node.name() {
+        lines.push(field("Name", name));
+    }
+    for (name, value) in node.properties() {
+        lines.push(field(name, value));
+    }
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block)
+        .render(area, buf);
+}
+
+/// The first rows of the last table previewed
+pub fn render_preview(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Preview ")
+        .border_style(theme.border);
+    let Some(preview) = app.state.context_tab.preview() else {
+        Paragraph::new("Preview a table to show its first rows")
+            .style(theme.text)
+            .block(block)
+            .render(area, buf);
+        return;
+    };
+    let block = block.title_bottom(Span::styled(format!(" {} ", preview.sql()), theme.text));
+    match (preview.results(), app.state.context_tab.preview_state()) {
+        (Some(batches), Some(state)) => {
+            let table = ResultsTable::new(batches, &app.state.config.display, theme).block(block);
+            StatefulWidget::render(table, area, buf, &mut state.borrow_mut());
+        }
+        _ => Paragraph::new(preview.error().clone().unwrap_or_default())
+            .style(theme.text)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf),
+    }
+}
+
+pub fn render_context_help(area: Rect, buf: &mut Buffer, app: &App) {
+    if let Some(prompt) = app.state.context_tab.prompt() {
+        render_prompt(area, buf, prompt, &app.state.theme);
+        return;
+    }
+    let help = app.state.keymap.help(
+        SelectedTab::Context,
+        Mode::Normal,
+        &[
+            Action::ToggleExpand,
+            Action::InsertName,
+            Action::PreviewTable,
+            Action::RefreshMetadata,
+            Action::OpenSettings,
+        ],
+    );
+    Paragraph::new(help.join(" | "))
+        .style(app.state.theme.text)
+        .alignment(Alignment::Center)
+        .render(area, buf);
+}
 
 pub fn render_physical_optimizers(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(" Physical Optimizers ");
+        .title(" Physical Optimizers ")
+        .border_style(theme.border);
     let context = app.execution.session_ctx();
     let state_lock = context.state_ref();
     let state = state_lock.read();
     let physical_optimizer_names = state.physical_optimizers().iter().map(|opt| opt.name());
 
-    let list = List::new(physical_optimizer_names).block(block);
-    list.render(area, buf)
+    let list = List::new(physical_optimizer_names)
+        .style(theme.text)
+        .block(block);
+    Widget::render(list, area, buf)
 }
 
+/// The options of the session config that differ from DataFusion's defaults
 pub fn render_config(area: Rect, buf: &mut Buffer, app: &App) {
-    let block = Block::default().borders(Borders::ALL).title(" Config ");
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Modified Config ")
+        .border_style(theme.border);
     let context = app.execution.session_ctx();
     let state_lock = context.state_ref();
     let state = state_lock.read();
-    let config = state.config();
-    let bloom_filter_pruning = config.parquet_bloom_filter_pruning();
-    let parquet_row_group_pruning = config.parquet_pruning();
+    let null_marker = &app.state.config.display.null_marker;
+    let config_options = settings(state.config_options())
+        .into_iter()
+        .filter(|s| s.modified())
+        .map(|s| {
+            let value = s.value.as_deref().unwrap_or(null_marker);
+            format!("{} = {}", s.key, value)
+        });
+
+    let list = List::new(config_options).style(theme.text).block(block);
+    Widget::render(list, area, buf)
+}
+
+/// Memory reserved against the limit of the pool, the reservations of each consumer and the
+/// resources used by the process
+pub fn render_resources(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
+    let usage = &app.state.resources;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Resources ")
+        .border_style(theme.border);
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let [memory_area, process_area, consumers_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+
+    Gauge::default()
+        .ratio(usage.memory_ratio().unwrap_or_default().clamp(0.0, 1.0))
+        .label(Span::styled(
+            format!("Memory {}", memory_text(usage)),
+            theme.text,
+        ))
+        .gauge_style(memory_style(usage, theme))
+        .render(memory_area, buf);
+
+    let unavailable = || "n/a".to_string();
+    let process = format!(
+        "Spill {} | Queries {} | RSS {} | CPU {}",
+        usage
+            .spilled
+            .map(human_readable_size)
+            .unwrap_or_else(unavailable),
+        usage.active_queries,
+        usage
+            .rss
+            .map(human_readable_size)
+            .unwrap_or_else(unavailable),
+        usage
+            .cpu
+            .map(|cpu| format!("{:.0}%", cpu))
+            .unwrap_or_else(unavailable),
+    );
+    Paragraph::new(process)
+        .style(theme.text)
+        .render(process_area, buf);
 
-    let config_options = vec![
-        format!("Bloom filter pruning: {}", bloom_filter_pruning),
-        format!("Parquet row group pruning: {}", parquet_row_group_pruning),
+    let rows = usage.consumers.iter().map(|(name, consumer)| {
+        Row::new(vec![
+            name.clone(),
+            consumer.consumers.to_string(),
+            human_readable_size(consumer.reserved),
+        ])
+        .style(theme.cell)
+    });
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(9),
+        Constraint::Length(10),
     ];
+    let header = Row::new(vec!["Consumer", "Instances", "Reserved"]).style(theme.header);
+    Widget::render(Table::new(rows, widths).header(header), consumers_area, buf);
+}
+
+/// Render the session config editor as a popup over `area`, with the options grouped by
+/// namespace and the description of the selected option
+pub fn render_settings(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
+    let settings = app.state.context_tab.settings();
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let help = app.state.keymap.help(
+        SelectedTab::Context,
+        Mode::Settings,
+        &[
+            Action::EditSetting,
+            Action::FilterSettings,
+            Action::SaveSettings,
+            Action::CloseSettings,
+        ],
+    );
+    let mut block = Block::default()
+        .title(" Session Config ")
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    if let Some(filter) = settings.filter() {
+        block = block.title(Span::styled(format!(" Filter: {} ", filter), theme.text));
+    }
+    let inner = block.inner(popup);
+    block.render(popup, buf);
+    let [table_area, description_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(inner);
 
-    let list = List::new(config_options).block(block);
-    list.render(area, buf)
+    let null_marker = &app.state.config.display.null_marker;
+    let (rows, selected) = settings.rows();
+    let rows = rows.into_iter().map(|row| match row {
+        SettingsRow::Namespace(namespace) => {
+            Row::new(vec![String::new(), namespace.to_string()]).style(theme.header)
+        }
+        SettingsRow::Setting(setting) => {
+            let marker = if setting.modified() { "*" } else { "" };
+            let value = |value: &Option<String>| value.clone().unwrap_or(null_marker.clone());
+            let key = setting
+                .key
+                .strip_prefix("datafusion.")
+                .unwrap_or(&setting.key);
+            Row::new(vec![
+                marker.to_string(),
+                key.to_string(),
+                value(&setting.value),
+                value(&setting.default),
+            ])
+            .style(if setting.modified() {
+                theme.text
+            } else {
+                theme.cell
+            })
+        }
+    });
+    let widths = [
+        Constraint::Length(1),
+        Constraint::Fill(3),
+        Constraint::Fill(2),
+        Constraint::Fill(2),
+    ];
+    let header = Row::new(vec!["", "Option", "Value", "Default"]).style(theme.header);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .highlight_style(theme.selected);
+    let mut state = TableState::default().with_selected(selected);
+    StatefulWidget::render(table, table_area, buf, &mut state);
+
+    let description = settings
+        .selected_setting()
+        .map(|s| s.description)
+        .unwrap_or_default();
+    Paragraph::new(description)
+        .style(theme.text)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(theme.border),
+        )
+        .render(description_area, buf);
 }
 
 pub fn render_context(area: Rect, buf: &mut Buffer, app: &App) {
-    let constraints = vec![Constraint::Percentage(50), Constraint::Percentage(50)];
-    let [physical_optimizers_area, config_area] =
+    let constraints = vec![
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ];
+    let [catalog_area, session_area, help_area] =
         Layout::new(Direction::Vertical, constraints).areas(area);
+    let [tree_area, object_area] = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Percentage(40), Constraint::Percentage(60)],
+    )
+    .areas(catalog_area);
+    let [details_area, preview_area] = Layout::new(
+        Direction::Vertical,
+        [Constraint::Fill(1), Constraint::Fill(2)],
+    )
+    .areas(object_area);
+    let [physical_optimizers_area, config_area, resources_area] = Layout::new(
+        Direction::Horizontal,
+        [
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .areas(session_area);
+    render_catalog(tree_area, buf, app);
+    render_object(details_area, buf, app);
+    render_preview(preview_area, buf, app);
     render_physical_optimizers(physical_optimizers_area, buf, app);
     render_config(config_area, buf, app);
+    render_resources(resources_area, buf, app);
+    render_context_help(help_area, buf, app);
+    if app.state.context_tab.show_settings() {
+        render_settings(catalog_area.union(session_area), buf, app);
+    }
 }
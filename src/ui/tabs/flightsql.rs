@@ -17,68 +17,146 @@
 
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{palette::tailwind, Style, Stylize},
-    text::Span,
-    widgets::{Block, Borders, Paragraph, Row, StatefulWidget, Table, Widget},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap,
+    },
 };
 
-use crate::{app::App, ui::convert::record_batches_to_table};
+use crate::{
+    app::{
+        keymap::{Action, Mode},
+        state::{metadata::Metadata, tabs::flightsql::ConnectionStatus},
+        App,
+    },
+    ui::{
+        chart::render_chart, detail::render_detail, metadata::render_metadata_tree,
+        profile::render_profile, prompt::render_prompt, results::ResultsTable, SelectedTab,
+    },
+};
 
 pub fn render_sql_editor(area: Rect, buf: &mut Buffer, app: &App) {
-    let border_color = if app.state.flightsql_tab.editor_editable() {
-        tailwind::ORANGE.c500
+    let theme = &app.state.theme;
+    let border_style = if app.state.flightsql_tab.editor_editable() {
+        theme.border_active
     } else {
-        tailwind::WHITE
+        theme.border
     };
-    let title = Span::from(" Editor ").fg(tailwind::WHITE);
+    let title = Span::styled(" Editor ", theme.text);
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .fg(border_color);
+        .border_style(border_style);
     let mut editor = app.state.flightsql_tab.editor();
+    editor.set_style(theme.text);
     editor.set_block(block);
     editor.render(area, buf)
 }
 
 pub fn render_sql_results(area: Rect, buf: &mut Buffer, app: &App) {
-    let block = Block::default().title(" Results ").borders(Borders::ALL);
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .title(" Results ")
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    // The parameters of the statement in the editor, if it has been prepared
+    let editor_sql = app.state.flightsql_tab.editor().lines().join("");
+    let block = match app.state.flightsql_tab.prepared() {
+        Some(prepared) if !prepared.parameters.is_empty() && prepared.sql == editor_sql => {
+            let parameters: Vec<String> = prepared
+                .parameters
+                .iter()
+                .map(|(name, data_type)| format!("{} {}", name, data_type))
+                .collect();
+            block.title(Span::styled(
+                format!(" Parameters: {} ", parameters.join(", ")),
+                theme.text,
+            ))
+        }
+        _ => block,
+    };
+    if let Some(progress) = app.state.flightsql_tab.progress() {
+        let text = format!(
+            "Fetching results: {} rows in {} batches from {} endpoints",
+            progress.rows, progress.batches, progress.endpoints
+        );
+        Paragraph::new(text)
+            .style(theme.text)
+            .block(block)
+            .render(area, buf);
+        return;
+    }
     if let Some(q) = app.state.flightsql_tab.query() {
         if let Some(r) = q.results() {
             if let Some(s) = app.state.flightsql_tab.query_results_state() {
-                let stats = Span::from(format!(
-                    " {} rows in {}ms ",
-                    q.num_rows().unwrap_or(0),
-                    q.execution_time().as_millis()
-                ))
-                .fg(tailwind::WHITE);
-                let block = block.title_bottom(stats).fg(tailwind::ORANGE.c500);
-                let maybe_table = record_batches_to_table(r);
-                match maybe_table {
-                    Ok(table) => {
-                        let table = table
-                            .highlight_style(
-                                Style::default().bg(tailwind::WHITE).fg(tailwind::BLACK),
-                            )
-                            .block(block);
-
-                        let mut s = s.borrow_mut();
-                        StatefulWidget::render(table, area, buf, &mut s);
+                let view = app.state.flightsql_tab.results_view();
+                let displayed = app
+                    .state
+                    .flightsql_tab
+                    .displayed_results()
+                    .unwrap_or_default();
+                let total: usize = r.iter().map(|b| b.num_rows()).sum();
+                let mut s = s.borrow_mut();
+                let rows = match view.filter() {
+                    Some(_) => format!("{} of {} rows", s.row_count(), total),
+                    None => format!("{} rows", total),
+                };
+                let stats = Span::styled(
+                    format!(" {} in {}ms ", rows, q.execution_time().as_millis()),
+                    theme.text,
+                );
+                let copy_format = Line::styled(
+                    format!(" Copy as {} ", app.state.copy_format.name()),
+                    theme.text,
+                );
+                let mut block = block
+                    .title_bottom(stats)
+                    .title_bottom(copy_format.right_aligned())
+                    .border_style(theme.border_active);
+                if let Some(filter) = view.filter() {
+                    block = block.title(Span::styled(format!(" WHERE {} ", filter), theme.text));
+                }
+                match (
+                    app.state.flightsql_tab.chart(),
+                    app.state.flightsql_tab.chart_data(),
+                ) {
+                    (Some(chart), Some(data)) if app.state.flightsql_tab.show_chart() => {
+                        let block =
+                            block.title(Span::styled(format!(" Chart: {} ", chart), theme.text));
+                        render_chart(area, buf, data, chart, block, theme);
                     }
-                    Err(e) => {
-                        let row = Row::new(vec![e.to_string()]);
-                        let widths = vec![Constraint::Percentage(100)];
-                        let table = Table::new(vec![row], widths).block(block);
-                        Widget::render(table, area, buf);
+                    _ => {
+                        let table = ResultsTable::new(displayed, &app.state.config.display, theme)
+                            .block(block)
+                            .sort(view.sort());
+                        StatefulWidget::render(table, area, buf, &mut s);
                     }
                 }
             }
+        } else if let Some(rows) = q.affected_rows() {
+            let text = format!(
+                "{} rows affected in {}ms",
+                rows,
+                q.execution_time().as_millis()
+            );
+            Paragraph::new(text)
+                .style(theme.text)
+                .block(block)
+                .render(area, buf);
         } else if let Some(e) = q.error() {
             let row = Row::new(vec![e.to_string()]);
             let widths = vec![Constraint::Percentage(100)];
             let table = Table::new(vec![row], widths).block(block);
             Widget::render(table, area, buf);
         }
+    } else if let ConnectionStatus::Failed(e) = app.state.flightsql_tab.connection_status() {
+        Paragraph::new(format!("Unable to connect to the FlightSQL server: {}", e))
+            .style(theme.text)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
     } else {
         let row = Row::new(vec!["Run a query to generate results"]);
         let widths = vec![Constraint::Percentage(100)];
@@ -88,20 +166,160 @@ pub fn render_sql_results(area: Rect, buf: &mut Buffer, app: &App) {
 }
 
 pub fn render_sql_help(area: Rect, buf: &mut Buffer, app: &App) {
+    if let Some(prompt) = app.state.flightsql_tab.prompt() {
+        render_prompt(area, buf, prompt, &app.state.theme);
+        return;
+    }
     let block = Block::default();
+    let keymap = &app.state.keymap;
     let help = if app.state.flightsql_tab.editor_editable() {
-        vec!["'Esc' to exit edit mode"]
+        keymap.help(
+            SelectedTab::FlightSQL,
+            Mode::Edit,
+            &[Action::ExitEdit, Action::RunQuery],
+        )
     } else {
-        vec!["'e' to edit", "'c' to clear editor", "'Enter' to run query"]
+        keymap.help(
+            SelectedTab::FlightSQL,
+            Mode::Normal,
+            &[
+                Action::Edit,
+                Action::ClearEditor,
+                Action::RunQuery,
+                Action::GoToRow,
+                Action::SortColumn,
+                Action::FilterResults,
+                Action::Search,
+                Action::ToggleChart,
+                Action::OpenDetail,
+                Action::ProfileResults,
+                Action::OpenConnections,
+                Action::OpenMetadata,
+                Action::PrepareQuery,
+            ],
+        )
     };
 
     let help_text = help.join(" | ");
     let p = Paragraph::new(help_text)
+        .style(app.state.theme.text)
         .block(block)
         .alignment(Alignment::Center);
     p.render(area, buf);
 }
 
+fn status_style(status: &ConnectionStatus, app: &App) -> Style {
+    let theme = &app.state.theme;
+    match status {
+        ConnectionStatus::Connected(_) => theme.log_info,
+        ConnectionStatus::Connecting => theme.log_warn,
+        _ => theme.log_error,
+    }
+}
+
+/// The selected connection and its status, shown in the tab header
+pub fn connection_status(app: &App) -> Line<'static> {
+    let connections = app.state.config.flightsql.connections();
+    let name = connections
+        .get(app.state.flightsql_tab.connection())
+        .map(|c| c.name.as_str())
+        .unwrap_or_default();
+    let status = app.state.flightsql_tab.connection_status();
+    Line::from(vec![
+        Span::styled(format!(" FlightSQL {}: ", name), app.state.theme.text),
+        Span::styled(format!("{} ", status), status_style(status, app)),
+    ])
+}
+
+/// Render the connection picker as a popup over `area`, listing every configured connection
+pub fn render_connections(area: Rect, buf: &mut Buffer, app: &App, selected: usize) {
+    let theme = &app.state.theme;
+    let [popup] = Layout::vertical([Constraint::Percentage(50)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(70)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let current = app.state.flightsql_tab.connection();
+    let status = app.state.flightsql_tab.connection_status();
+    let rows = app
+        .state
+        .config
+        .flightsql
+        .connections()
+        .into_iter()
+        .enumerate()
+        .map(|(i, connection)| {
+            let (marker, status) = if i == current {
+                ("*", status.to_string())
+            } else {
+                ("", String::new())
+            };
+            Row::new(vec![
+                marker.to_string(),
+                connection.name.clone(),
+                connection.connection_url.clone(),
+                status,
+            ])
+            .style(theme.cell)
+        })
+        .collect::<Vec<_>>();
+    let widths = [
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+    ];
+    let help = app.state.keymap.help(
+        SelectedTab::FlightSQL,
+        Mode::Connections,
+        &[Action::Connect, Action::CloseConnections],
+    );
+    let block = Block::default()
+        .title(" Connections ")
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    let header = Row::new(vec!["", "Name", "URL", "Status"]).style(theme.header);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(block)
+        .highlight_style(theme.selected);
+    let mut state = TableState::default().with_selected(Some(selected));
+    StatefulWidget::render(table, popup, buf, &mut state);
+}
+
+/// Render the metadata browser as a popup over `area`, with a tree of the objects on the server
+pub fn render_metadata(area: Rect, buf: &mut Buffer, app: &App, metadata: &Metadata) {
+    let theme = &app.state.theme;
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+
+    let help = app.state.keymap.help(
+        SelectedTab::FlightSQL,
+        Mode::Metadata,
+        &[
+            Action::ToggleExpand,
+            Action::InsertName,
+            Action::RefreshMetadata,
+            Action::CloseMetadata,
+        ],
+    );
+    let block = Block::default()
+        .title(" Metadata ")
+        .title_bottom(Line::from(format!(" {} ", help.join(" | "))).centered())
+        .borders(Borders::ALL)
+        .border_style(theme.border_active);
+    render_metadata_tree(popup, buf, metadata, block, theme);
+}
+
 pub fn render_sql(area: Rect, buf: &mut Buffer, app: &App) {
     let constraints = vec![
         Constraint::Fill(1),
@@ -113,4 +331,33 @@ pub fn render_sql(area: Rect, buf: &mut Buffer, app: &App) {
     render_sql_editor(editor_area, buf, app);
     render_sql_results(results_area, buf, app);
     render_sql_help(help_area, buf, app);
+    if let Some(detail) = app.state.flightsql_tab.detail() {
+        render_detail(
+            area,
+            buf,
+            detail,
+            SelectedTab::FlightSQL,
+            &app.state.keymap,
+            &app.state.theme,
+        );
+    }
+    if let Some(profile) = app.state.flightsql_tab.profile() {
+        render_profile(
+            area,
+            buf,
+            profile,
+            SelectedTab::FlightSQL,
+            &app.state.keymap,
+            &app.state.config.display,
+            &app.state.theme,
+        );
+    }
+    if let Some(selected) = app.state.flightsql_tab.connection_picker() {
+        render_connections(area, buf, app, selected);
+    }
+    if let Some(metadata) = app.state.flightsql_tab.metadata() {
+        if app.state.flightsql_tab.show_metadata() {
+            render_metadata(area, buf, app, metadata);
+        }
+    }
 }
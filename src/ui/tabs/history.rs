@@ -15,52 +15,125 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use log::info;
+use log::trace;
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{palette::tailwind, Style, Stylize},
     widgets::{Block, Borders, Cell, Paragraph, Row, StatefulWidget, Table, Widget},
 };
 
-use crate::app::App;
+#[cfg(feature = "flightsql")]
+use crate::app::execution::FlightSQLStats;
+use crate::app::{
+    keymap::{Action, Mode},
+    state::tabs::history::{Context, HistoryQuery},
+    App,
+};
+use crate::ui::{theme::Theme, SelectedTab};
 
-fn render_query_placeholder(area: Rect, buf: &mut Buffer) {
+fn render_query_placeholder(area: Rect, buf: &mut Buffer, theme: &Theme) {
     let block = Block::default()
         .title(" Selected Query ")
-        .borders(Borders::ALL);
-    let paragraph = Paragraph::new("Your selected query will show here").block(block);
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    let paragraph = Paragraph::new("Your selected query will show here")
+        .style(theme.text)
+        .block(block);
     paragraph.render(area, buf);
 }
 
 pub fn render_query(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
     let block = Block::default()
         .title(" Selected Query ")
-        .borders(Borders::ALL);
+        .borders(Borders::ALL)
+        .border_style(theme.border);
     // TODO: This is horrible, simplify later
     if let Some(history_table_state) = app.state.history_tab.history_table_state() {
         if let Some(selected) = history_table_state.borrow().selected() {
-            info!("Selected: {}", selected);
+            trace!("Selected: {}", selected);
             if let Some(selected_query) = app.state.history_tab.history().get(selected) {
-                info!("Selected Query: {:?}", selected_query);
-                let query = Paragraph::new(selected_query.sql().as_str()).block(block);
+                trace!("Selected Query: {:?}", selected_query);
+                let mut text = selected_query.sql().clone();
+                #[cfg(feature = "flightsql")]
+                if let Some(stats) = selected_query.flightsql_stats() {
+                    text.push_str(&format!("\n\n{}", flightsql_stats_text(stats)));
+                }
+                if let Some(chart) = selected_query.chart() {
+                    text.push_str(&format!("\n\nChart: {}", chart));
+                }
+                let query = Paragraph::new(text).style(theme.text).block(block);
                 query.render(area, buf);
             } else {
-                info!("Rendering placeholder because no selected_query");
-                render_query_placeholder(area, buf);
+                trace!("Rendering placeholder because no selected_query");
+                render_query_placeholder(area, buf, theme);
             }
         } else {
-            render_query_placeholder(area, buf);
+            render_query_placeholder(area, buf, theme);
         }
     } else {
-        render_query_placeholder(area, buf);
+        render_query_placeholder(area, buf, theme);
+    }
+}
+
+/// How the results of a FlightSQL query were fetched, for the selected query
+#[cfg(feature = "flightsql")]
+fn flightsql_stats_text(stats: &FlightSQLStats) -> String {
+    let mut lines = vec![
+        format!(
+            "Endpoints: {} | Batches: {} | Rows: {}",
+            stats.endpoints, stats.batches, stats.rows
+        ),
+        format!(
+            "Received: {} bytes | First batch: {} | Transfer: {}ms",
+            stats.ipc_bytes,
+            stats
+                .time_to_first_batch
+                .map(|t| format!("{}ms", t.as_millis()))
+                .unwrap_or_else(|| "-".to_string()),
+            stats.transfer_time.as_millis()
+        ),
+    ];
+    if stats.total_records.is_some() || stats.total_bytes.is_some() {
+        let reported = |total: Option<i64>| total.map_or("-".to_string(), |t| t.to_string());
+        lines.push(format!(
+            "Server reported: {} records | {} bytes",
+            reported(stats.total_records),
+            reported(stats.total_bytes)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Bytes scanned by local queries, or received from the server by FlightSQL queries. The rows
+/// and batches received are shown with the selected query.
+fn bytes_cell(query: &HistoryQuery) -> String {
+    #[cfg(feature = "flightsql")]
+    if let Some(stats) = query.flightsql_stats() {
+        return stats.ipc_bytes.to_string();
+    }
+    query.scanned_bytes().to_string()
+}
+
+/// The header of the bytes column, for the contexts the queries in the history were run in
+fn bytes_header(history: &[HistoryQuery]) -> &'static str {
+    let flightsql = history
+        .iter()
+        .filter(|q| *q.context() == Context::FlightSQL)
+        .count();
+    match flightsql {
+        0 => "Scanned Bytes",
+        n if n == history.len() => "Received Bytes",
+        _ => "Scanned / Received Bytes",
     }
 }
 
 pub fn render_query_history(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
     let block = Block::default()
         .title(" Query History ")
-        .borders(Borders::ALL);
+        .borders(Borders::ALL)
+        .border_style(theme.border);
     let history = app.state.history_tab.history();
     let history_table_state = app.state.history_tab.history_table_state();
     match (history.is_empty(), history_table_state) {
@@ -72,37 +145,42 @@ pub fn render_query_history(area: Rect, buf: &mut Buffer, app: &App) {
         }
         (_, Some(table_state)) => {
             let widths = vec![
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
-                Constraint::Percentage(25),
+                Constraint::Length(6),
+                Constraint::Percentage(20),
+                Constraint::Fill(1),
+                Constraint::Length(18),
+                Constraint::Length(24),
             ];
             let history = app.state.history_tab.history();
+            let diff_mark = app.state.history_tab.diff_mark();
             let rows: Vec<Row> = history
                 .iter()
-                .map(|q| {
+                .enumerate()
+                .map(|(i, q)| {
+                    // Entries are numbered from 1 as they are referred to in diffs
+                    let marker = if diff_mark == Some(i) { "*" } else { "" };
                     Row::new(vec![
-                        Cell::from(q.context().as_str()),
+                        Cell::from(format!("#{}{}", i + 1, marker)),
+                        Cell::from(q.context_label()),
                         Cell::from(q.sql().as_str()),
                         Cell::from(q.execution_time().as_millis().to_string()),
-                        Cell::from(q.scanned_bytes().to_string()),
+                        Cell::from(bytes_cell(q)),
                     ])
+                    .style(theme.cell)
                 })
                 .collect();
 
             let header = Row::new(vec![
+                Cell::from("#"),
                 Cell::from("Context"),
                 Cell::from("Query"),
                 Cell::from("Execution Time(ms)"),
-                Cell::from("Scanned Bytes"),
+                Cell::from(bytes_header(history)),
             ])
-            .bg(tailwind::WHITE)
-            .fg(tailwind::BLACK);
+            .style(theme.header);
             let table = Table::new(rows, widths).header(header).block(block.clone());
 
-            let table = table
-                .highlight_style(Style::default().bg(tailwind::WHITE).fg(tailwind::BLACK))
-                .block(block);
+            let table = table.highlight_style(theme.selected).block(block);
 
             let mut table_state = table_state.borrow_mut();
             StatefulWidget::render(table, area, buf, &mut table_state);
@@ -112,14 +190,20 @@ pub fn render_query_history(area: Rect, buf: &mut Buffer, app: &App) {
 
 pub fn render_history_help(area: Rect, buf: &mut Buffer, app: &App) {
     let block = Block::default();
-    let help = if app.state.sql_tab.editor_editable() {
-        vec!["'Esc' to exit edit mode"]
-    } else {
-        vec!["'e' to edit", "'c' to clear editor", "'Enter' to run query"]
-    };
+    let help = app.state.keymap.help(
+        SelectedTab::History,
+        Mode::Normal,
+        &[
+            Action::SelectPreviousRow,
+            Action::SelectNextRow,
+            Action::MarkDiff,
+            Action::Diff,
+        ],
+    );
 
     let help_text = help.join(" | ");
     let p = Paragraph::new(help_text)
+        .style(app.state.theme.text)
         .block(block)
         .alignment(Alignment::Center);
     p.render(area, buf);
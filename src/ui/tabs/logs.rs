@@ -18,28 +18,47 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{palette::tailwind, Style},
     widgets::{Block, Paragraph, Widget},
 };
 use tui_logger::TuiLoggerSmartWidget;
 
-use crate::app::App;
+use crate::app::{
+    keymap::{Action, Mode},
+    App,
+};
+use crate::ui::SelectedTab;
 
 fn render_smart_widget(area: Rect, buf: &mut Buffer, app: &App) {
+    let theme = &app.state.theme;
     let logs = TuiLoggerSmartWidget::default()
-        .style_error(Style::default().fg(tailwind::RED.c700))
-        .style_debug(Style::default().fg(tailwind::ORANGE.c500))
-        .style_warn(Style::default().fg(tailwind::YELLOW.c700))
-        .style_trace(Style::default().fg(tailwind::GRAY.c700))
-        .style_info(Style::default().fg(tailwind::WHITE))
+        .border_style(theme.border)
+        .style_error(theme.log_error)
+        .style_debug(theme.log_debug)
+        .style_warn(theme.log_warn)
+        .style_trace(theme.log_trace)
+        .style_info(theme.log_info)
         .state(app.state.logs_tab.state());
     logs.render(area, buf);
 }
 
-fn render_logs_help(area: Rect, buf: &mut Buffer) {
-    let help_text = ["f - Focus logs", "h - Hide logs", "⇧ ⇩ - Select target"].join(" | ");
+fn render_logs_help(area: Rect, buf: &mut Buffer, app: &App) {
+    let help_text = app
+        .state
+        .keymap
+        .help(
+            SelectedTab::Logs,
+            Mode::Normal,
+            &[
+                Action::LogsFocus,
+                Action::LogsHide,
+                Action::LogsPreviousTarget,
+                Action::LogsNextTarget,
+            ],
+        )
+        .join(" | ");
     let block = Block::default();
     let help = Paragraph::new(help_text)
+        .style(app.state.theme.text)
         .block(block)
         .alignment(Alignment::Center);
     help.render(area, buf);
@@ -51,5 +70,5 @@ pub fn render_logs(area: Rect, buf: &mut Buffer, app: &App) {
     let constraints = vec![Constraint::Min(0), Constraint::Length(1)];
     let [logs_area, footer_area] = Layout::new(Direction::Vertical, constraints).areas(area);
     render_smart_widget(logs_area, buf, app);
-    render_logs_help(footer_area, buf);
+    render_logs_help(footer_area, buf, app);
 }
@@ -18,59 +18,85 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{palette::tailwind, Style, Stylize},
-    text::Span,
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Row, StatefulWidget, Table, Widget},
 };
 
-use crate::{app::App, ui::convert::record_batches_to_table};
+use crate::{
+    app::{
+        keymap::{Action, Mode},
+        App,
+    },
+    ui::{
+        chart::render_chart, detail::render_detail, functions::render_functions,
+        profile::render_profile, prompt::render_prompt, results::ResultsTable, SelectedTab,
+    },
+};
 
 pub fn render_sql_editor(area: Rect, buf: &mut Buffer, app: &App) {
-    let border_color = if app.state.sql_tab.editor_editable() {
-        tailwind::ORANGE.c500
+    let theme = &app.state.theme;
+    let border_style = if app.state.sql_tab.editor_editable() {
+        theme.border_active
     } else {
-        tailwind::WHITE
+        theme.border
     };
-    let title = Span::from(" Editor ").fg(tailwind::WHITE);
+    let title = Span::styled(" Editor ", theme.text);
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .fg(border_color);
+        .border_style(border_style);
     let mut editor = app.state.sql_tab.editor();
-    editor.set_style(Style::default().fg(tailwind::WHITE));
+    editor.set_style(theme.text);
     editor.set_block(block);
     editor.render(area, buf)
 }
 
 pub fn render_sql_results(area: Rect, buf: &mut Buffer, app: &App) {
-    let block = Block::default().title(" Results ").borders(Borders::ALL);
+    let theme = &app.state.theme;
+    let block = Block::default()
+        .title(" Results ")
+        .borders(Borders::ALL)
+        .border_style(theme.border);
     if let Some(q) = app.state.sql_tab.query() {
         if let Some(r) = q.results() {
             if let Some(s) = app.state.sql_tab.query_results_state() {
-                let stats = Span::from(format!(
-                    " {} rows in {}ms ",
-                    q.num_rows().unwrap_or(0),
-                    q.execution_time().as_millis()
-                ))
-                .fg(tailwind::WHITE);
-                let block = block.title_bottom(stats).fg(tailwind::ORANGE.c500);
-                let maybe_table = record_batches_to_table(r);
-                match maybe_table {
-                    Ok(table) => {
-                        let table = table
-                            .highlight_style(
-                                Style::default().bg(tailwind::WHITE).fg(tailwind::BLACK),
-                            )
-                            .block(block);
-
-                        let mut s = s.borrow_mut();
-                        StatefulWidget::render(table, area, buf, &mut s);
+                let view = app.state.sql_tab.results_view();
+                let displayed = app.state.sql_tab.displayed_results().unwrap_or_default();
+                let total: usize = r.iter().map(|b| b.num_rows()).sum();
+                let mut s = s.borrow_mut();
+                let rows = match view.filter() {
+                    Some(_) => format!("{} of {} rows", s.row_count(), total),
+                    None => format!("{} rows", total),
+                };
+                let stats = Span::styled(
+                    format!(" {} in {}ms ", rows, q.execution_time().as_millis()),
+                    theme.text,
+                );
+                let copy_format = Line::styled(
+                    format!(" Copy as {} ", app.state.copy_format.name()),
+                    theme.text,
+                );
+                let mut block = block
+                    .title_bottom(stats)
+                    .title_bottom(copy_format.right_aligned())
+                    .border_style(theme.border_active);
+                if let Some(diff) = app.state.sql_tab.diff() {
+                    block = block.title(Span::styled(format!(" {} ", diff), theme.text));
+                }
+                if let Some(filter) = view.filter() {
+                    block = block.title(Span::styled(format!(" WHERE {} ", filter), theme.text));
+                }
+                match (app.state.sql_tab.chart(), app.state.sql_tab.chart_data()) {
+                    (Some(chart), Some(data)) if app.state.sql_tab.show_chart() => {
+                        let block =
+                            block.title(Span::styled(format!(" Chart: {} ", chart), theme.text));
+                        render_chart(area, buf, data, chart, block, theme);
                     }
-                    Err(e) => {
-                        let row = Row::new(vec![e.to_string()]);
-                        let widths = vec![Constraint::Percentage(100)];
-                        let table = Table::new(vec![row], widths).block(block);
-                        Widget::render(table, area, buf);
+                    _ => {
+                        let table = ResultsTable::new(displayed, &app.state.config.display, theme)
+                            .block(block)
+                            .sort(view.sort());
+                        StatefulWidget::render(table, area, buf, &mut s);
                     }
                 }
             }
@@ -89,15 +115,40 @@ pub fn render_sql_results(area: Rect, buf: &mut Buffer, app: &App) {
 }
 
 pub fn render_sql_help(area: Rect, buf: &mut Buffer, app: &App) {
+    if let Some(prompt) = app.state.sql_tab.prompt() {
+        render_prompt(area, buf, prompt, &app.state.theme);
+        return;
+    }
     let block = Block::default();
+    let keymap = &app.state.keymap;
     let help = if app.state.sql_tab.editor_editable() {
-        vec!["'Esc' to exit edit mode"]
+        keymap.help(
+            SelectedTab::SQL,
+            Mode::Edit,
+            &[Action::ExitEdit, Action::RunQuery],
+        )
     } else {
-        vec!["'e' to edit", "'c' to clear editor", "'Enter' to run query"]
+        keymap.help(
+            SelectedTab::SQL,
+            Mode::Normal,
+            &[
+                Action::Edit,
+                Action::ClearEditor,
+                Action::RunQuery,
+                Action::GoToRow,
+                Action::SortColumn,
+                Action::FilterResults,
+                Action::Search,
+                Action::ToggleChart,
+                Action::OpenDetail,
+                Action::ProfileResults,
+            ],
+        )
     };
 
     let help_text = help.join(" | ");
     let p = Paragraph::new(help_text)
+        .style(app.state.theme.text)
         .block(block)
         .alignment(Alignment::Center);
     p.render(area, buf);
@@ -114,4 +165,35 @@ pub fn render_sql(area: Rect, buf: &mut Buffer, app: &App) {
     render_sql_editor(editor_area, buf, app);
     render_sql_results(results_area, buf, app);
     render_sql_help(help_area, buf, app);
+    if let Some(detail) = app.state.sql_tab.detail() {
+        render_detail(
+            area,
+            buf,
+            detail,
+            SelectedTab::SQL,
+            &app.state.keymap,
+            &app.state.theme,
+        );
+    }
+    if let Some(profile) = app.state.sql_tab.profile() {
+        render_profile(
+            area,
+            buf,
+            profile,
+            SelectedTab::SQL,
+            &app.state.keymap,
+            &app.state.config.display,
+            &app.state.theme,
+        );
+    }
+    if let Some(functions) = app.state.sql_tab.functions() {
+        render_functions(
+            area,
+            buf,
+            functions,
+            SelectedTab::SQL,
+            &app.state.keymap,
+            &app.state.theme,
+        );
+    }
 }
@@ -0,0 +1,331 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::str::FromStr;
+
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use log::error;
+use ratatui::style::{palette::tailwind, Color, Modifier, Style};
+
+use crate::app::config::{CustomThemeConfig, ThemeConfig};
+
+/// Colours and styles used by every render function in `ui`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Unselected tab titles
+    pub tab: Style,
+    /// The selected tab title
+    pub tab_highlight: Style,
+    /// Plain text such as editor contents and help lines
+    pub text: Style,
+    /// Borders of inactive blocks
+    pub border: Style,
+    /// Borders of focused blocks, e.g. the editor in edit mode or the results
+    pub border_active: Style,
+    /// Table header rows
+    pub header: Style,
+    /// Table cells
+    pub cell: Style,
+    /// Selected table row
+    pub selected: Style,
+    /// NULL values in table cells
+    pub null: Style,
+    /// Table cells matching the results search
+    pub search_match: Style,
+    /// Colours of the series of a chart, in order
+    pub series: Vec<Color>,
+    pub log_error: Style,
+    pub log_warn: Style,
+    pub log_info: Style,
+    pub log_debug: Style,
+    pub log_trace: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            tab: Style::new()
+                .fg(tailwind::SLATE.c200)
+                .bg(tailwind::ORANGE.c700),
+            tab_highlight: Style::new().bg(tailwind::ORANGE.c500),
+            text: Style::new().fg(tailwind::WHITE),
+            border: Style::new().fg(tailwind::WHITE),
+            border_active: Style::new().fg(tailwind::ORANGE.c500),
+            header: Style::new().fg(tailwind::BLACK).bg(tailwind::ORANGE.c300),
+            cell: Style::new().fg(tailwind::WHITE).bg(tailwind::BLACK),
+            selected: Style::new().fg(tailwind::BLACK).bg(tailwind::WHITE),
+            null: Style::new()
+                .fg(tailwind::GRAY.c500)
+                .bg(tailwind::BLACK)
+                .add_modifier(Modifier::ITALIC),
+            search_match: Style::new().fg(tailwind::BLACK).bg(tailwind::YELLOW.c400),
+            series: vec![
+                tailwind::CYAN.c400,
+                tailwind::YELLOW.c400,
+                tailwind::FUCHSIA.c400,
+                tailwind::GREEN.c400,
+                tailwind::RED.c400,
+                tailwind::BLUE.c400,
+            ],
+            log_error: Style::new().fg(tailwind::RED.c700),
+            log_warn: Style::new().fg(tailwind::YELLOW.c700),
+            log_info: Style::new().fg(tailwind::WHITE),
+            log_debug: Style::new().fg(tailwind::ORANGE.c500),
+            log_trace: Style::new().fg(tailwind::GRAY.c700),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            tab: Style::new()
+                .fg(tailwind::SLATE.c900)
+                .bg(tailwind::ORANGE.c200),
+            tab_highlight: Style::new().fg(tailwind::WHITE).bg(tailwind::ORANGE.c600),
+            text: Style::new().fg(tailwind::SLATE.c900),
+            border: Style::new().fg(tailwind::SLATE.c500),
+            border_active: Style::new().fg(tailwind::ORANGE.c700),
+            header: Style::new()
+                .fg(tailwind::SLATE.c900)
+                .bg(tailwind::ORANGE.c200),
+            cell: Style::new().fg(tailwind::SLATE.c900).bg(tailwind::WHITE),
+            selected: Style::new().fg(tailwind::WHITE).bg(tailwind::SLATE.c700),
+            null: Style::new()
+                .fg(tailwind::GRAY.c400)
+                .bg(tailwind::WHITE)
+                .add_modifier(Modifier::ITALIC),
+            search_match: Style::new()
+                .fg(tailwind::SLATE.c900)
+                .bg(tailwind::YELLOW.c200),
+            series: vec![
+                tailwind::CYAN.c700,
+                tailwind::AMBER.c700,
+                tailwind::FUCHSIA.c700,
+                tailwind::GREEN.c700,
+                tailwind::RED.c700,
+                tailwind::BLUE.c700,
+            ],
+            log_error: Style::new().fg(tailwind::RED.c700),
+            log_warn: Style::new().fg(tailwind::AMBER.c700),
+            log_info: Style::new().fg(tailwind::SLATE.c900),
+            log_debug: Style::new().fg(tailwind::ORANGE.c700),
+            log_trace: Style::new().fg(tailwind::GRAY.c500),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            tab: Style::new().fg(Color::White).bg(Color::Black),
+            tab_highlight: Style::new()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            text: Style::new().fg(Color::White),
+            border: Style::new().fg(Color::White),
+            border_active: Style::new().fg(Color::Yellow),
+            header: Style::new()
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            cell: Style::new().fg(Color::White).bg(Color::Black),
+            selected: Style::new().fg(Color::Black).bg(Color::Yellow),
+            null: Style::new()
+                .fg(Color::LightMagenta)
+                .bg(Color::Black)
+                .add_modifier(Modifier::ITALIC),
+            search_match: Style::new()
+                .fg(Color::Black)
+                .bg(Color::LightMagenta)
+                .add_modifier(Modifier::BOLD),
+            series: vec![
+                Color::LightCyan,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::LightGreen,
+                Color::LightRed,
+                Color::LightBlue,
+            ],
+            log_error: Style::new().fg(Color::LightRed),
+            log_warn: Style::new().fg(Color::LightYellow),
+            log_info: Style::new().fg(Color::White),
+            log_debug: Style::new().fg(Color::LightCyan),
+            log_trace: Style::new().fg(Color::Gray),
+        }
+    }
+
+    /// Uses the terminal's own foreground and background colours, relying on modifiers rather
+    /// than backgrounds for emphasis.
+    pub fn terminal() -> Self {
+        Self {
+            tab: Style::new(),
+            tab_highlight: Style::new().add_modifier(Modifier::REVERSED),
+            text: Style::new(),
+            border: Style::new(),
+            border_active: Style::new().add_modifier(Modifier::BOLD),
+            header: Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            cell: Style::new(),
+            selected: Style::new().add_modifier(Modifier::REVERSED),
+            null: Style::new().add_modifier(Modifier::DIM | Modifier::ITALIC),
+            search_match: Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            // The terminal's own palette for the named colours
+            series: vec![
+                Color::Cyan,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Green,
+                Color::Red,
+                Color::Blue,
+            ],
+            log_error: Style::new().fg(Color::Red),
+            log_warn: Style::new().fg(Color::Yellow),
+            log_info: Style::new(),
+            log_debug: Style::new().fg(Color::Cyan),
+            log_trace: Style::new().add_modifier(Modifier::DIM),
+        }
+    }
+
+    pub fn preset(name: &str) -> Result<Self> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            "high_contrast" => Ok(Self::high_contrast()),
+            "terminal" | "terminal_default" => Ok(Self::terminal()),
+            _ => Err(eyre!("Unknown theme preset '{}'", name)),
+        }
+    }
+
+    /// Build the theme from config. Invalid presets or colours are logged and ignored so that a
+    /// typo does not prevent the app from starting.
+    pub fn from_config(config: &ThemeConfig) -> Self {
+        match config {
+            ThemeConfig::Preset(name) => Self::preset(name).unwrap_or_else(|e| {
+                error!("{}", e);
+                Self::default()
+            }),
+            ThemeConfig::Custom(custom) => {
+                let mut theme = custom
+                    .preset
+                    .as_deref()
+                    .map(|name| {
+                        Self::preset(name).unwrap_or_else(|e| {
+                            error!("{}", e);
+                            Self::default()
+                        })
+                    })
+                    .unwrap_or_default();
+                theme.apply_overrides(custom);
+                theme
+            }
+        }
+    }
+
+    fn apply_overrides(&mut self, custom: &CustomThemeConfig) {
+        let overrides = [
+            (&mut self.tab, &custom.tab_fg, &custom.tab_bg),
+            (
+                &mut self.tab_highlight,
+                &custom.tab_highlight_fg,
+                &custom.tab_highlight_bg,
+            ),
+            (&mut self.text, &custom.text_fg, &None),
+            (&mut self.border, &custom.border_fg, &None),
+            (&mut self.border_active, &custom.border_active_fg, &None),
+            (&mut self.header, &custom.header_fg, &custom.header_bg),
+            (&mut self.cell, &custom.cell_fg, &custom.cell_bg),
+            (&mut self.selected, &custom.selected_fg, &custom.selected_bg),
+            (&mut self.null, &custom.null_fg, &None),
+            (
+                &mut self.search_match,
+                &custom.search_match_fg,
+                &custom.search_match_bg,
+            ),
+        ];
+        for (style, fg, bg) in overrides {
+            if let Some(fg) = fg.as_deref().and_then(parse_color) {
+                *style = style.fg(fg);
+            }
+            if let Some(bg) = bg.as_deref().and_then(parse_color) {
+                *style = style.bg(bg);
+            }
+        }
+        if let Some(series) = &custom.series {
+            let series: Vec<Color> = series.iter().filter_map(|c| parse_color(c)).collect();
+            if !series.is_empty() {
+                self.series = series;
+            }
+        }
+    }
+
+    /// Style of the `index`th series of a chart, cycling through the series colours
+    pub fn series(&self, index: usize) -> Style {
+        match self.series.len() {
+            0 => self.text,
+            len => Style::new().fg(self.series[index % len]),
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Option<Color> {
+    match Color::from_str(color) {
+        Ok(color) => Some(color),
+        Err(_) => {
+            error!("Unable to parse theme colour '{}'", color);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Style};
+
+    use super::Theme;
+    use crate::app::config::ThemeConfig;
+
+    #[test]
+    fn theme_from_config_test() {
+        let config: ThemeConfig = toml::from_str::<toml::Table>("theme = \"light\"")
+            .unwrap()
+            .remove("theme")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(Theme::from_config(&config), Theme::light());
+
+        let config: ThemeConfig = toml::from_str(
+            r##"
+            preset = "terminal"
+            cell_fg = "green"
+            selected_bg = "#112233"
+            series = ["red", "not a colour", "blue"]
+            "##,
+        )
+        .unwrap();
+        let theme = Theme::from_config(&config);
+        assert_eq!(theme.cell, Style::new().fg(Color::Green));
+        assert_eq!(theme.selected.bg, Some(Color::Rgb(0x11, 0x22, 0x33)));
+        assert_eq!(theme.header, Theme::terminal().header);
+        assert_eq!(theme.series, vec![Color::Red, Color::Blue]);
+        assert_eq!(theme.series(3), Style::new().fg(Color::Blue));
+    }
+}